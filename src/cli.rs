@@ -2,8 +2,18 @@
 //!
 //! Defines all subcommands and their arguments.
 
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
 
+use crate::commands::{Age, OutputFormat, Shell, StatsFormat};
+use crate::formats::{Conflict, Format};
+
+use crate::models::{EntryRef, SortOrder};
+use crate::output_template::OutputTemplate;
+use crate::storage::BackendKind;
+use crate::template::parse_assignment;
+
 /// A CLI tool to save, manage, and search frequently used commands
 #[derive(Parser, Debug)]
 #[command(name = "cmd-keeper")]
@@ -12,6 +22,14 @@ use clap::{Parser, Subcommand};
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
+
+    /// Database file to use instead of the default (overrides CMD_KEEPER_DB)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub db: Option<PathBuf>,
+
+    /// Named database kept under the config directory, e.g. `work`
+    #[arg(long, global = true, value_name = "NAME", conflicts_with = "db")]
+    pub profile: Option<String>,
 }
 
 /// Available subcommands
@@ -35,6 +53,15 @@ pub enum Commands {
         /// Optional tags for categorization (comma-separated)
         #[arg(short, long, value_delimiter = ',')]
         tags: Option<Vec<String>>,
+
+        /// Shell to run the command with, e.g. bash, zsh, fish or "bash -i" (default: $SHELL)
+        #[arg(long)]
+        shell: Option<String>,
+
+        /// Save to the project file instead of the global database, creating
+        /// `.cmd-keeper.json` here if none is found
+        #[arg(long)]
+        local: bool,
     },
 
     /// List all saved commands
@@ -43,24 +70,99 @@ pub enum Commands {
         /// Show full command without truncation
         #[arg(short, long)]
         full: bool,
+
+        /// Only show commands with this tag or one below it (e.g. `cloud` includes `cloud/aws`)
+        #[arg(short, long)]
+        tag: Option<String>,
+
+        /// Sort order (`frecency` puts frequently and recently used commands first)
+        #[arg(short, long, value_enum, default_value_t)]
+        sort: SortOrder,
+
+        /// Output format (`json`, `ndjson` and `tsv` are meant for scripts)
+        #[arg(short, long, value_enum, default_value_t)]
+        output: OutputFormat,
+
+        /// Print each command through a template instead of a table,
+        /// e.g. '{{id}}\t{{command|oneline}}  # {{description}}'
+        #[arg(long, value_name = "TEMPLATE", value_parser = OutputTemplate::parse, conflicts_with = "output")]
+        format: Option<OutputTemplate>,
     },
 
     /// Search commands by keyword
     #[command(visible_alias = "s")]
     Search {
-        /// Keyword to search for (searches in command, description, and tags)
+        /// Query to search for, e.g. `docker tag:prod -tag:old "exact phrase"`
+        ///
+        /// Words are fuzzy-matched against command, description, and tags.
+        /// Also supported: `tag:`, `cmd:` and `desc:` fields, `created:`
+        /// dates (`2026-01-01`, `>2026-01-01`, `2026-01-01..2026-01-31`),
+        /// `-` to negate, `OR` and parentheses.
         keyword: String,
 
         /// Show full command without truncation
         #[arg(short, long)]
         full: bool,
+
+        /// Match the keyword as a plain substring instead of a query
+        #[arg(short, long)]
+        exact: bool,
+
+        /// Sort order (`frecency` puts frequently and recently used commands first)
+        #[arg(short, long, value_enum, default_value_t)]
+        sort: SortOrder,
+
+        /// Output format (`json`, `ndjson` and `tsv` are meant for scripts)
+        #[arg(short, long, value_enum, default_value_t)]
+        output: OutputFormat,
+
+        /// Print each command through a template instead of a table (see `list`)
+        #[arg(long, value_name = "TEMPLATE", value_parser = OutputTemplate::parse, conflicts_with = "output")]
+        format: Option<OutputTemplate>,
+    },
+
+    /// Show every field of a command by ID
+    Show {
+        /// ID of the command to show (`p3` for a project command)
+        id: EntryRef,
+
+        /// Output format (`json`, `ndjson` and `tsv` are meant for scripts)
+        #[arg(short, long, value_enum, default_value_t)]
+        output: OutputFormat,
+    },
+
+    /// Report usage statistics, e.g. to find commands worth pruning
+    Stats {
+        /// List never-used commands created more than this many days ago
+        #[arg(long, value_name = "DAYS", default_value_t = 90)]
+        unused_days: u64,
+
+        /// Number of commands in the most used, least used and failure lists
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t)]
+        output: StatsFormat,
+    },
+
+    /// List tags with their number of commands, or rename, merge and remove them
+    Tags {
+        #[command(subcommand)]
+        action: Option<TagsAction>,
+    },
+
+    /// List deleted commands, or restore and purge them
+    Trash {
+        #[command(subcommand)]
+        action: Option<TrashAction>,
     },
 
     /// Edit an existing command by ID
     #[command(visible_alias = "e")]
     Edit {
-        /// ID of the command to edit
-        id: u64,
+        /// ID of the command to edit (`p3` for a project command)
+        id: EntryRef,
 
         /// New command string (optional)
         #[arg(short, long)]
@@ -73,13 +175,18 @@ pub enum Commands {
         /// New tags (comma-separated, optional)
         #[arg(short, long, value_delimiter = ',')]
         tags: Option<Vec<String>>,
+
+        /// New shell to run the command with (optional, "" to use $SHELL)
+        #[arg(long)]
+        shell: Option<String>,
     },
 
     /// Delete a command by ID
     #[command(visible_alias = "rm")]
     Delete {
-        /// ID of the command to delete
-        id: u64,
+        /// ID of the command to move to the trash (`p3` for a project
+        /// command, which is deleted for good)
+        id: EntryRef,
 
         /// Skip confirmation prompt
         #[arg(short, long)]
@@ -89,10 +196,222 @@ pub enum Commands {
     /// Copy a command to clipboard by ID
     #[command(visible_alias = "cp")]
     Copy {
-        /// ID of the command to copy
-        id: u64,
+        /// ID of the command to copy (`p3` for a project command)
+        id: EntryRef,
+
+        /// Value for a placeholder, skipping its prompt (repeatable)
+        #[arg(short, long, value_name = "NAME=VALUE", value_parser = parse_assignment)]
+        set: Vec<(String, String)>,
+    },
+
+    /// Run a command by ID through the shell
+    ///
+    /// Exits with the exit code of the command.
+    #[command(visible_alias = "r")]
+    Run {
+        /// ID of the command to run (`p3` for a project command)
+        id: EntryRef,
+
+        /// Value for a placeholder, skipping its prompt (repeatable)
+        #[arg(short, long, value_name = "NAME=VALUE", value_parser = parse_assignment)]
+        set: Vec<(String, String)>,
+
+        /// Shell to use instead of the entry's shell or $SHELL
+        #[arg(long)]
+        shell: Option<String>,
+    },
+
+    /// Pick a command interactively and print it (used by the shell integration)
+    ///
+    /// The picker is drawn on the terminal, so the output can be captured.
+    /// Exits with code 1 if cancelled.
+    Pick {
+        /// Initial filter query
+        #[arg(short, long)]
+        query: Option<String>,
+
+        /// Write the chosen command to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Print the shell integration script (binds Ctrl-G to the picker)
+    ///
+    /// Add `eval "$(cmd-keeper init bash)"` to ~/.bashrc,
+    /// `eval "$(cmd-keeper init zsh)"` to ~/.zshrc, or
+    /// `cmd-keeper init fish | source` to ~/.config/fish/config.fish.
+    Init {
+        /// Shell to print the script for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+
+    /// Export saved commands to a file or stdout
+    Export {
+        /// Output format (default: from the output file's extension, or json)
+        #[arg(short, long, value_enum)]
+        format: Option<Format>,
+
+        /// File to write instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Only export commands with this tag or one below it
+        #[arg(short, long)]
+        tag: Option<String>,
+    },
+
+    /// Import commands from other sources
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
+    },
+
+    /// Move the database to another storage backend
+    Migrate {
+        /// Backend to move the data to
+        #[arg(long, value_enum)]
+        to: BackendKind,
     },
 
-    /// Show the path to the database file
+    /// Show the recorded changes of a command
+    History {
+        /// ID of the command
+        id: EntryRef,
+    },
+
+    /// Revert the last change to the database
+    Undo,
+
+    /// Apply the last undone change again
+    Redo,
+
+    /// Sync the database through a git remote, merging entry by entry
+    Sync {
+        /// Remote repository to sync with; remembered for later syncs
+        #[arg(long, value_name = "URL")]
+        remote: Option<String>,
+    },
+
+    /// Check the database for problems and repair them, showing each fix
+    /// before applying it
+    Doctor {
+        /// Apply every fix without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// List the automatic backups of the database, or restore one
+    Backup {
+        #[command(subcommand)]
+        action: Option<BackupAction>,
+    },
+
+    /// Show the path to the database file and why it was chosen
     Path,
 }
+
+/// Actions of the backup command
+#[derive(Subcommand, Debug)]
+pub enum BackupAction {
+    /// List backups, newest first
+    #[command(visible_alias = "ls")]
+    List,
+
+    /// Replace the database with a backup, after showing what would change
+    Restore {
+        /// Timestamp of the backup, as shown by `backup list` (a unique
+        /// prefix is enough)
+        timestamp: String,
+
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+/// Actions of the trash command
+#[derive(Subcommand, Debug)]
+pub enum TrashAction {
+    /// List deleted commands, most recently deleted first
+    #[command(visible_alias = "ls")]
+    List,
+
+    /// Move a deleted command back into the list, under its old ID
+    Restore {
+        /// ID of the deleted command
+        id: u64,
+    },
+
+    /// Delete commands in the trash for good
+    Purge {
+        /// Only purge commands deleted longer ago than this, e.g. 30d, 2w or 12h
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<Age>,
+
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+/// Sources for the import command
+#[derive(Subcommand, Debug)]
+pub enum ImportSource {
+    /// Import a JSON, YAML, TOML, CSV, navi or pet file (see `export`)
+    File {
+        /// File to import, or - for stdin
+        path: PathBuf,
+
+        /// Input format (default: from the file extension)
+        #[arg(short, long, value_enum)]
+        format: Option<Format>,
+
+        /// What to do with entries whose command or ID is already saved
+        #[arg(long, value_enum, default_value_t)]
+        on_conflict: Conflict,
+    },
+
+    /// Pick commands from a shell's history and describe them
+    History {
+        /// Shell whose history to read
+        #[arg(short, long, value_enum)]
+        shell: Shell,
+
+        /// History file to read instead of the shell's default
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+    },
+}
+
+/// Actions of the tags command; tags are matched ignoring case, together
+/// with the tags below them
+#[derive(Subcommand, Debug)]
+pub enum TagsAction {
+    /// Rename a tag on every command
+    Rename {
+        /// Tag to rename
+        old: String,
+
+        /// New name (must not be in use; see `merge`)
+        new: String,
+    },
+
+    /// Replace one or more tags with another on every command
+    Merge {
+        /// Tags to merge
+        #[arg(required = true)]
+        sources: Vec<String>,
+
+        /// Tag to merge them into (may already exist)
+        #[arg(long)]
+        into: String,
+    },
+
+    /// Remove a tag from every command (the commands are kept)
+    #[command(visible_alias = "remove")]
+    Rm {
+        /// Tag to remove
+        tag: String,
+    },
+}
@@ -3,17 +3,43 @@
 use anyhow::Result;
 use colored::Colorize;
 
+use crate::models::{CommandEntry, Source};
 use crate::storage::Storage;
 
-/// Adds a new command to the database
-pub fn execute(command: &str, description: &str, tags: Option<Vec<String>>) -> Result<()> {
-    let storage = Storage::new()?;
-    let mut db = storage.load()?;
+/// Adds a new command to the database, or to the project file if `local`
+pub fn execute(
+    command: &str,
+    description: &str,
+    tags: Option<Vec<String>>,
+    shell: Option<String>,
+    local: bool,
+) -> Result<()> {
+    let mut storage = Storage::new()?;
 
-    let tags = tags.unwrap_or_default();
-    let id = db.add(command.to_string(), description.to_string(), tags.clone());
+    let source = if local {
+        let project = storage.project_or_create()?;
+        if !project.path().exists() {
+            println!(
+                "{} Creating project file {}",
+                "✓".green(),
+                project.path().display().to_string().yellow()
+            );
+        }
+        Source::Project
+    } else {
+        Source::Global
+    };
 
-    storage.save(&db)?;
+    let tags = tags.unwrap_or_default();
+    let shell = shell.filter(|s| !s.trim().is_empty());
+    let mut entry = CommandEntry::new(
+        0,
+        command.to_string(),
+        description.to_string(),
+        tags.clone(),
+    );
+    entry.shell = shell.clone();
+    let id = storage.add(source, entry)?;
 
     println!("{}", "✓ Command saved successfully!".green().bold());
     println!("  {} {}", "ID:".dimmed(), id);
@@ -22,6 +48,9 @@ pub fn execute(command: &str, description: &str, tags: Option<Vec<String>>) -> R
     if !tags.is_empty() {
         println!("  {} {}", "Tags:".dimmed(), tags.join(", "));
     }
+    if let Some(shell) = shell {
+        println!("  {} {}", "Shell:".dimmed(), shell);
+    }
 
     Ok(())
 }
@@ -0,0 +1,144 @@
+//! Backup command implementations
+//!
+//! Every change keeps a copy of the previous database file (see
+//! [`crate::storage::backup`]); these commands list the copies and put one
+//! back in place.
+
+use std::io::{self, Write};
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use colored::Colorize;
+use tabled::{builder::Builder, settings::Style};
+
+use super::journal::print_fields;
+use crate::error::CmdKeeperError;
+use crate::storage::journal;
+use crate::storage::Storage;
+
+/// Lists the backups of the database, newest first
+pub fn list() -> Result<()> {
+    let storage = Storage::new()?;
+    let backups = storage.backups().list()?;
+
+    if backups.is_empty() {
+        println!(
+            "{}",
+            "No backups yet; one is taken before every change.".yellow()
+        );
+        return Ok(());
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record(["Timestamp", "Taken", "Commands", "Size"]);
+    for backup in &backups {
+        let commands = match backup.load() {
+            Ok(db) => db.entries.len().to_string(),
+            Err(_) => "unreadable".to_string(),
+        };
+        builder.push_record([
+            backup.timestamp(),
+            backup
+                .taken_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+            commands,
+            format_size(backup.size()?),
+        ]);
+    }
+    let mut table = builder.build();
+    table.with(Style::rounded());
+
+    println!("{}", table);
+    println!(
+        "\n{} restore one with `cmd-keeper backup restore <timestamp>`; a unique prefix is enough",
+        "Tip:".dimmed()
+    );
+    Ok(())
+}
+
+/// Replaces the database with a backup after showing what would change
+///
+/// The current file is backed up first, so a restore can be restored away.
+pub fn restore(timestamp: &str, force: bool) -> Result<()> {
+    let storage = Storage::new()?;
+    let backup = storage.backups().find(timestamp)?;
+    let restored = backup
+        .load()
+        .map_err(|e| anyhow!("Backup {} can't be read: {}", backup.timestamp(), e))?;
+
+    println!(
+        "{} {} ({} command(s))",
+        "Backup:".dimmed(),
+        backup.timestamp().cyan(),
+        restored.entries.len()
+    );
+    match storage.load() {
+        Ok(current) => {
+            let changes = journal::diff(&current, &restored);
+            if changes.is_empty() {
+                println!("{}", "The backup matches the current database.".green());
+                return Ok(());
+            }
+            println!("Restoring it would change:");
+            for change in &changes {
+                let command = change
+                    .after
+                    .as_ref()
+                    .or(change.before.as_ref())
+                    .map(|e| e.command.as_str())
+                    .unwrap_or_default();
+                println!(
+                    "  {:<9} {}  {}",
+                    change.action().bold(),
+                    change.id.to_string().cyan(),
+                    command
+                );
+                if change.action() == "edited" {
+                    print_fields(change);
+                }
+            }
+        }
+        Err(e) => {
+            let reason = match e {
+                CmdKeeperError::CorruptDatabase { reason, .. } => reason,
+                e => e.to_string(),
+            };
+            println!(
+                "{} {}\nRestoring replaces it with the backup.",
+                "The current database can't be read:".red(),
+                reason
+            );
+        }
+    }
+
+    if !force {
+        print!("{}", "Restore this backup? [y/N] ".yellow());
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if !matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
+            println!("{}", "Restore cancelled.".dimmed());
+            return Ok(());
+        }
+    }
+
+    storage.replace(&restored)?;
+    println!(
+        "{} Restored backup {}.",
+        "✓".green().bold(),
+        backup.timestamp().cyan()
+    );
+    Ok(())
+}
+
+/// Formats a file size, e.g. `12.3 KB`
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
@@ -4,30 +4,39 @@ use anyhow::{bail, Context, Result};
 use arboard::Clipboard;
 use colored::Colorize;
 
+use super::fill::fill_placeholders;
+use crate::models::EntryRef;
 use crate::storage::Storage;
 
 /// Copies a command to the clipboard by ID
-pub fn execute(id: u64) -> Result<()> {
+///
+/// Placeholders in the command are filled from `set` or asked for on stdin.
+pub fn execute(id: EntryRef, set: &[(String, String)]) -> Result<()> {
     let storage = Storage::new()?;
-    let db = storage.load()?;
 
     // Find the command
-    let entry = match db.find_by_id(id) {
+    let entry = match storage.find(id)? {
         Some(e) => e,
         None => {
             bail!("Command with ID {} not found", id);
         }
     };
 
+    let command = fill_placeholders(&entry.command, set)?;
+
     // Copy to clipboard
     let mut clipboard = Clipboard::new().context("Failed to access clipboard")?;
     clipboard
-        .set_text(&entry.command)
+        .set_text(&command)
         .context("Failed to copy to clipboard")?;
 
+    if let Err(e) = storage.record_use(id, None) {
+        eprintln!("{} Could not record usage: {}", "⚠".yellow(), e);
+    }
+
     println!("{} Command copied to clipboard!", "✓".green().bold());
-    println!("  {} {}", "ID:".dimmed(), entry.id);
-    println!("  {} {}", "Command:".dimmed(), entry.command.cyan());
+    println!("  {} {}", "ID:".dimmed(), id);
+    println!("  {} {}", "Command:".dimmed(), command.cyan());
 
     Ok(())
 }
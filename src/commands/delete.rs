@@ -4,16 +4,18 @@ use anyhow::{bail, Result};
 use colored::Colorize;
 use std::io::{self, Write};
 
+use crate::models::{EntryRef, Source};
 use crate::storage::Storage;
 
-/// Deletes a command by ID
-pub fn execute(id: u64, force: bool) -> Result<()> {
+/// Moves a command to the trash by ID
+///
+/// Project commands have no trash and are deleted for good.
+pub fn execute(id: EntryRef, force: bool) -> Result<()> {
     let storage = Storage::new()?;
-    let mut db = storage.load()?;
 
     // Check if the command exists
-    let entry = match db.find_by_id(id) {
-        Some(e) => e.clone(),
+    let entry = match storage.find(id)? {
+        Some(e) => e,
         None => {
             bail!("Command with ID {} not found", id);
         }
@@ -22,7 +24,7 @@ pub fn execute(id: u64, force: bool) -> Result<()> {
     // Confirm deletion unless --force is used
     if !force {
         println!("{}", "Command to delete:".yellow());
-        println!("  {} {}", "ID:".dimmed(), entry.id);
+        println!("  {} {}", "ID:".dimmed(), id);
         println!("  {} {}", "Command:".dimmed(), entry.command);
         println!("  {} {}", "Description:".dimmed(), entry.description);
         println!();
@@ -42,16 +44,30 @@ pub fn execute(id: u64, force: bool) -> Result<()> {
         }
     }
 
-    // Perform deletion
-    if db.remove_by_id(id) {
-        storage.save(&db)?;
-        println!(
+    // Perform deletion against the current state on disk
+    storage.update_in(id.source, |db| {
+        let deleted = match id.source {
+            Source::Global => db.trash_by_id(id.id),
+            Source::Project => db.remove_by_id(id.id),
+        };
+        if !deleted {
+            bail!("Failed to delete command with ID {}", id);
+        }
+        Ok(())
+    })?;
+
+    match id.source {
+        Source::Global => println!(
+            "{} Command {} moved to the trash (restore it with `cmd-keeper trash restore {}`).",
+            "✓".green(),
+            id.to_string().cyan(),
+            id
+        ),
+        Source::Project => println!(
             "{} Command {} deleted successfully.",
             "✓".green(),
             id.to_string().cyan()
-        );
-    } else {
-        bail!("Failed to delete command with ID {}", id);
+        ),
     }
 
     Ok(())
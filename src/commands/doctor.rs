@@ -0,0 +1,119 @@
+//! Doctor command implementation
+//!
+//! Checks the global database (see [`crate::doctor`]) and repairs it. A JSON
+//! file that can't be read at all is salvaged entry by entry first (see
+//! [`crate::storage::salvage`]). Every fix is shown and confirmed before
+//! anything is written, and the file as it was is kept as a backup.
+
+use std::fs;
+use std::io::{self, Write};
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+use crate::doctor::{self, Fix};
+use crate::error::CmdKeeperError;
+use crate::storage::salvage::salvage;
+use crate::storage::{BackendKind, Storage};
+
+/// Diagnoses the database and applies the fixes that are confirmed, or all
+/// of them with `yes`
+pub fn execute(yes: bool) -> Result<()> {
+    let storage = Storage::new()?;
+    println!(
+        "{} {}",
+        "Checking".dimmed(),
+        storage.db_path().display().to_string().cyan()
+    );
+
+    let (mut db, salvaged) = match storage.load() {
+        Ok(db) => (db, false),
+        Err(e @ (CmdKeeperError::CorruptDatabase { .. } | CmdKeeperError::Migration { .. }))
+            if storage.kind() == BackendKind::Json =>
+        {
+            let reason = match e {
+                CmdKeeperError::CorruptDatabase { reason, .. } => reason,
+                e => e.to_string(),
+            };
+            let salvaged = salvage(&fs::read_to_string(storage.db_path())?);
+            println!("\n{} The database can't be read: {}", "✗".red(), reason);
+            println!(
+                "  {} {} command(s) and {} in the trash can be recovered",
+                "Salvage:".dimmed(),
+                salvaged.db.entries.len(),
+                salvaged.db.trash.len()
+            );
+            for lost in &salvaged.lost {
+                println!("  {} {}", "Lost:".dimmed(), lost.red());
+            }
+            println!(
+                "  {} rewrite the file with what can be recovered (the file as it is goes to backups/)",
+                "Fix:".dimmed()
+            );
+            if !yes && !confirm()? {
+                println!("{}", "Nothing changed.".dimmed());
+                return Ok(());
+            }
+            (salvaged.db, true)
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let fixes = doctor::plan(&db);
+    if fixes.is_empty() && !salvaged {
+        println!("{} No problems found.", "✓".green().bold());
+        return Ok(());
+    }
+
+    let mut accepted: Vec<Fix> = Vec::new();
+    for fix in &fixes {
+        println!("\n{} {}", "✗".red(), fix.problem());
+        println!("  {} {}", "Fix:".dimmed(), fix.action());
+        if yes || confirm()? {
+            accepted.push(fix.clone());
+        }
+    }
+
+    if salvaged {
+        doctor::apply(&mut db, &accepted);
+        storage.replace(&db)?;
+    } else if !accepted.is_empty() {
+        storage.update(|db| {
+            // The fixes address entries by position, so they only fit the
+            // database they were planned on
+            if doctor::plan(db) != fixes {
+                bail!("The database changed while checking it; run `cmd-keeper doctor` again");
+            }
+            doctor::apply(db, &accepted);
+            Ok(())
+        })?;
+    }
+
+    println!();
+    if salvaged {
+        println!(
+            "{} Rewrote the database with {} command(s).",
+            "✓".green().bold(),
+            db.entries.len()
+        );
+    }
+    if !fixes.is_empty() {
+        println!(
+            "{} Applied {} of {} fix(es).",
+            "✓".green().bold(),
+            accepted.len(),
+            fixes.len()
+        );
+    }
+    Ok(())
+}
+
+/// Asks whether to apply a fix
+fn confirm() -> Result<bool> {
+    print!("  {}", "Apply? [y/N] ".yellow());
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
+}
@@ -3,47 +3,59 @@
 use anyhow::{bail, Result};
 use colored::Colorize;
 
+use crate::models::EntryRef;
 use crate::storage::Storage;
 
 /// Edits an existing command in the database
 pub fn execute(
-    id: u64,
+    id: EntryRef,
     command: Option<String>,
     description: Option<String>,
     tags: Option<Vec<String>>,
+    shell: Option<String>,
 ) -> Result<()> {
     // Check if at least one field is provided
-    if command.is_none() && description.is_none() && tags.is_none() {
-        bail!("At least one of --command, --description, or --tags must be provided");
+    if command.is_none() && description.is_none() && tags.is_none() && shell.is_none() {
+        bail!("At least one of --command, --description, --tags, or --shell must be provided");
     }
 
     let storage = Storage::new()?;
-    let mut db = storage.load()?;
 
-    // Check if command exists
-    let entry = db.find_by_id(id);
-    if entry.is_none() {
-        bail!("Command with ID {} not found", id);
-    }
-
-    // Perform update
-    if db.update(id, command.clone(), description.clone(), tags.clone()) {
-        storage.save(&db)?;
-
-        println!("{}", "✓ Command updated successfully!".green().bold());
-        println!("  {} {}", "ID:".dimmed(), id);
-
-        if let Some(cmd) = command {
-            println!("  {} {}", "Command:".dimmed(), cmd);
+    storage.update_in(id.source, |db| {
+        // Check if command exists
+        if db.find_by_id(id.id).is_none() {
+            bail!("Command with ID {} not found", id);
         }
-        if let Some(desc) = description {
-            println!("  {} {}", "Description:".dimmed(), desc);
+
+        // Perform update
+        if !db.update(id.id, command.clone(), description.clone(), tags.clone()) {
+            bail!("Failed to update command with ID {}", id);
         }
-        if let Some(t) = tags {
-            println!("  {} {}", "Tags:".dimmed(), t.join(", "));
+        if let (Some(shell), Some(entry)) = (&shell, db.find_by_id_mut(id.id)) {
+            entry.shell = Some(shell.clone()).filter(|s| !s.trim().is_empty());
         }
-    } else {
-        bail!("Failed to update command with ID {}", id);
+        Ok(())
+    })?;
+
+    println!("{}", "✓ Command updated successfully!".green().bold());
+    println!("  {} {}", "ID:".dimmed(), id);
+
+    if let Some(cmd) = command {
+        println!("  {} {}", "Command:".dimmed(), cmd);
+    }
+    if let Some(desc) = description {
+        println!("  {} {}", "Description:".dimmed(), desc);
+    }
+    if let Some(t) = tags {
+        println!("  {} {}", "Tags:".dimmed(), t.join(", "));
+    }
+    if let Some(shell) = shell {
+        let shell = if shell.trim().is_empty() {
+            "$SHELL".to_string()
+        } else {
+            shell
+        };
+        println!("  {} {}", "Shell:".dimmed(), shell);
     }
 
     Ok(())
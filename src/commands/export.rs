@@ -0,0 +1,46 @@
+//! Export command implementation
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::formats::{self, Format};
+use crate::storage::Storage;
+use crate::tags;
+
+/// Exports saved commands, optionally only those with a tag
+///
+/// Only the global database is exported; project files are shared as-is.
+/// The format defaults to the output file's extension, or JSON. Without an
+/// output file the export goes to stdout.
+pub fn execute(format: Option<Format>, output: Option<&Path>, tag: Option<&str>) -> Result<()> {
+    let storage = Storage::new()?;
+
+    let mut entries = storage.load()?.entries;
+    if let Some(tag) = tag {
+        entries.retain(|e| e.tags.iter().any(|t| tags::matches(t, tag)));
+    }
+
+    let format = format
+        .or_else(|| output.and_then(Format::from_path))
+        .unwrap_or(Format::Json);
+    let content = formats::export(&entries, format)?;
+
+    match output {
+        Some(path) => {
+            fs::write(path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!(
+                "{} Exported {} command(s) to {}",
+                "✓".green().bold(),
+                entries.len().to_string().cyan(),
+                path.display().to_string().yellow()
+            );
+        }
+        None => print!("{}", content),
+    }
+
+    Ok(())
+}
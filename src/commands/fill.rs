@@ -0,0 +1,60 @@
+//! Placeholder filling for the copy and run commands
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::template;
+
+/// Fills the placeholders of a command template
+///
+/// Values given with `--set` are used as-is; any remaining placeholders are
+/// asked for on stdin, offering the default if there is one. Prompts go to
+/// stderr, so they stay out of piped output.
+pub fn fill_placeholders(command: &str, set: &[(String, String)]) -> Result<String> {
+    let placeholders = template::placeholders(command);
+
+    for (name, _) in set {
+        if !placeholders.iter().any(|p| &p.name == name) {
+            bail!("Unknown placeholder '{}'", name);
+        }
+    }
+
+    let mut values: HashMap<String, String> = set.iter().cloned().collect();
+
+    for placeholder in &placeholders {
+        if values.contains_key(&placeholder.name) {
+            continue;
+        }
+
+        let mut prompt = format!("{}", placeholder.name.cyan().bold());
+        if let Some(desc) = &placeholder.description {
+            prompt.push_str(&format!(" ({})", desc.dimmed()));
+        }
+        if let Some(default) = &placeholder.default {
+            prompt.push_str(&format!(" [{}]", default.yellow()));
+        }
+
+        loop {
+            eprint!("{}: ", prompt);
+            io::stderr().flush()?;
+
+            let mut input = String::new();
+            if io::stdin().read_line(&mut input)? == 0 {
+                bail!("No value given for placeholder '{}'", placeholder.name);
+            }
+
+            let input = input.trim_end_matches(['\r', '\n']);
+            if !input.is_empty() {
+                values.insert(placeholder.name.clone(), input.to_string());
+                break;
+            }
+            if placeholder.default.is_some() {
+                break;
+            }
+        }
+    }
+
+    Ok(template::render(command, &values)?)
+}
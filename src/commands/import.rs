@@ -0,0 +1,188 @@
+//! Import command implementation
+
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+
+use super::Shell;
+use crate::formats::{self, Conflict, Format};
+use crate::history;
+use crate::storage::Storage;
+use crate::tui;
+
+/// Imports commands from a file exported by cmd-keeper or written by hand
+///
+/// `-` reads from stdin. The format defaults to the file's extension.
+pub fn file(path: &Path, format: Option<Format>, conflict: Conflict) -> Result<()> {
+    let Some(format) = format.or_else(|| Format::from_path(path)) else {
+        bail!(
+            "Cannot tell the format of {}; pass it with --format",
+            path.display()
+        );
+    };
+
+    let content = if path == Path::new("-") {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        content
+    } else {
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?
+    };
+    let records = formats::import(&content, format)?;
+
+    let storage = Storage::new()?;
+    let summary =
+        storage.update(|db| Ok::<_, anyhow::Error>(formats::merge(db, records, conflict)))?;
+
+    println!(
+        "{} Imported {} command(s)",
+        "✓".green().bold(),
+        (summary.added + summary.updated).to_string().cyan()
+    );
+    if summary.updated > 0 {
+        println!("  {} {}", "Overwritten:".dimmed(), summary.updated);
+    }
+    if summary.renumbered > 0 {
+        println!("  {} {}", "Given a new ID:".dimmed(), summary.renumbered);
+    }
+    if summary.skipped > 0 {
+        println!(
+            "  {} {} (already saved; use --on-conflict overwrite or renumber)",
+            "Skipped:".dimmed(),
+            summary.skipped
+        );
+    }
+    Ok(())
+}
+
+/// Imports commands picked from a shell's history
+///
+/// The history is deduplicated and commands that are already saved are left
+/// out. The user marks the ones to keep in a selection list, then gives each
+/// a description and tags on stdin.
+pub fn history(shell: Shell, file: Option<&Path>) -> Result<()> {
+    let path = match file {
+        Some(path) => path.to_path_buf(),
+        None => default_history_file(shell)?,
+    };
+    let content = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let commands = match shell {
+        Shell::Bash => history::parse_bash(&content),
+        Shell::Zsh => history::parse_zsh(&content),
+        Shell::Fish => history::parse_fish(&content),
+    };
+
+    let storage = Storage::new()?;
+    let saved: HashSet<String> = storage
+        .load()?
+        .entries
+        .into_iter()
+        .map(|e| e.command.trim().to_string())
+        .collect();
+    let candidates: Vec<String> = history::dedupe(commands)
+        .into_iter()
+        .filter(|c| !saved.contains(c))
+        .collect();
+
+    if candidates.is_empty() {
+        println!(
+            "{} No new commands found in {}",
+            "✗".red(),
+            path.display().to_string().yellow()
+        );
+        return Ok(());
+    }
+
+    let Some(picked) = tui::select_many("Import from history", &candidates)? else {
+        println!("{}", "Import cancelled.".yellow());
+        return Ok(());
+    };
+
+    println!(
+        "Describe the {} selected command(s) (Ctrl+D to stop):\n",
+        picked.len().to_string().cyan()
+    );
+
+    let mut new_entries = Vec::new();
+    for index in picked {
+        let command = &candidates[index];
+        println!("{} {}", "$".dimmed(), command.cyan().bold());
+
+        let Some(description) = prompt("Description")? else {
+            break;
+        };
+        let Some(tags) = prompt("Tags (comma-separated)")? else {
+            break;
+        };
+        let tags: Vec<String> = tags
+            .split(',')
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        new_entries.push((command.clone(), description, tags));
+        println!();
+    }
+
+    if new_entries.is_empty() {
+        println!("{}", "Nothing imported.".yellow());
+        return Ok(());
+    }
+
+    let count = new_entries.len();
+    storage.update(|db| {
+        for (command, description, tags) in new_entries {
+            db.add(command, description, tags);
+        }
+        Ok::<_, anyhow::Error>(())
+    })?;
+
+    println!(
+        "{} Imported {} command(s)",
+        "✓".green().bold(),
+        count.to_string().cyan()
+    );
+    Ok(())
+}
+
+/// Returns where a shell keeps its history by default
+fn default_history_file(shell: Shell) -> Result<PathBuf> {
+    let Some(home) = dirs::home_dir() else {
+        bail!("Could not determine the home directory; pass the history file with --file");
+    };
+
+    let histfile = env::var_os("HISTFILE").map(PathBuf::from);
+    Ok(match shell {
+        Shell::Bash => histfile.unwrap_or_else(|| home.join(".bash_history")),
+        Shell::Zsh => histfile.unwrap_or_else(|| {
+            env::var_os("ZDOTDIR")
+                .map(PathBuf::from)
+                .unwrap_or(home.clone())
+                .join(".zsh_history")
+        }),
+        Shell::Fish => env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".local").join("share"))
+            .join("fish")
+            .join("fish_history"),
+    })
+}
+
+/// Asks for a line on stdin, returning None at end of input
+fn prompt(label: &str) -> Result<Option<String>> {
+    print!("  {}: ", label.dimmed());
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input)? == 0 {
+        println!();
+        return Ok(None);
+    }
+    Ok(Some(input.trim().to_string()))
+}
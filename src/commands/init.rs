@@ -0,0 +1,100 @@
+//! Init command implementation
+//!
+//! Prints the shell integration: a widget bound to Ctrl-G that opens the
+//! picker with the current command line as the query and replaces the line
+//! with the chosen command, ready to be edited or run.
+
+use clap::ValueEnum;
+
+/// Shells with an integration script
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+const BASH: &str = r#"# cmd-keeper shell integration for bash
+# Add to ~/.bashrc:  eval "$(cmd-keeper init bash)"
+
+__cmd_keeper_widget() {
+    local selected
+    selected="$(cmd-keeper pick --query "$READLINE_LINE")"
+    if [[ -n $selected ]]; then
+        READLINE_LINE=$selected
+        READLINE_POINT=${#READLINE_LINE}
+    fi
+}
+
+bind -m emacs-standard -x '"\C-g": __cmd_keeper_widget'
+bind -m vi-insert -x '"\C-g": __cmd_keeper_widget'
+"#;
+
+const ZSH: &str = r#"# cmd-keeper shell integration for zsh
+# Add to ~/.zshrc:  eval "$(cmd-keeper init zsh)"
+
+__cmd_keeper_widget() {
+    local selected
+    selected="$(cmd-keeper pick --query "$BUFFER" </dev/tty)"
+    if [[ -n $selected ]]; then
+        BUFFER=$selected
+        CURSOR=${#BUFFER}
+    fi
+    zle reset-prompt
+}
+
+zle -N __cmd_keeper_widget
+bindkey -M emacs '^G' __cmd_keeper_widget
+bindkey -M viins '^G' __cmd_keeper_widget
+"#;
+
+const FISH: &str = r#"# cmd-keeper shell integration for fish
+# Add to ~/.config/fish/config.fish:  cmd-keeper init fish | source
+
+function __cmd_keeper_widget
+    set -l selected (cmd-keeper pick --query (commandline) | string collect)
+    if test -n "$selected"
+        commandline --replace -- $selected
+    end
+    commandline -f repaint
+end
+
+bind \cg __cmd_keeper_widget
+bind -M insert \cg __cmd_keeper_widget 2>/dev/null
+"#;
+
+/// Prints the integration script for a shell
+pub fn execute(shell: Shell) {
+    print!("{}", script(shell));
+}
+
+/// Returns the integration script for a shell
+fn script(shell: Shell) -> &'static str {
+    match shell {
+        Shell::Bash => BASH,
+        Shell::Zsh => ZSH,
+        Shell::Fish => FISH,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripts_bind_widget_to_picker() {
+        let bindings = [
+            (
+                Shell::Bash,
+                r#"bind -m emacs-standard -x '"\C-g": __cmd_keeper_widget'"#,
+            ),
+            (Shell::Zsh, "bindkey -M emacs '^G' __cmd_keeper_widget"),
+            (Shell::Fish, r"bind \cg __cmd_keeper_widget"),
+        ];
+        for (shell, binding) in bindings {
+            let script = script(shell);
+            assert!(script.contains(binding), "{:?}", shell);
+            assert!(script.contains("cmd-keeper pick --query"), "{:?}", shell);
+        }
+    }
+}
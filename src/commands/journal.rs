@@ -0,0 +1,119 @@
+//! History, undo and redo command implementations
+//!
+//! All three read the change journal kept next to the global database (see
+//! [`crate::storage::journal`]). Project commands are versioned with the
+//! project instead.
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+use crate::models::{CommandEntry, EntryRef, Source};
+use crate::storage::journal::{Change, Kind, Record};
+use crate::storage::Storage;
+
+/// Shows the recorded changes of a command, oldest first
+pub fn history(id: EntryRef) -> Result<()> {
+    if id.source == Source::Project {
+        bail!("Project commands have no history here; see the project's version control");
+    }
+    let storage = Storage::new()?;
+    let records = storage.journal().read()?;
+
+    let mut found = false;
+    for record in &records {
+        for change in record.changes.iter().filter(|c| c.id == id.id) {
+            found = true;
+            print_change(record, change);
+        }
+    }
+    if !found {
+        println!(
+            "{}",
+            format!("No recorded changes to command {}.", id).yellow()
+        );
+    }
+    Ok(())
+}
+
+/// Reverts the last change that wasn't undone
+pub fn undo() -> Result<()> {
+    match Storage::new()?.undo()? {
+        Some(record) => println!(
+            "{} Undid #{}: {}",
+            "✓".green().bold(),
+            record.seq,
+            record.summary()
+        ),
+        None => println!("{}", "Nothing to undo.".yellow()),
+    }
+    Ok(())
+}
+
+/// Applies the last undone change again
+pub fn redo() -> Result<()> {
+    match Storage::new()?.redo()? {
+        Some(record) => println!(
+            "{} Redid #{}: {}",
+            "✓".green().bold(),
+            record.seq,
+            record.summary()
+        ),
+        None => println!("{}", "Nothing to redo.".yellow()),
+    }
+    Ok(())
+}
+
+/// Prints one change of a record with the fields it changed
+fn print_change(record: &Record, change: &Change) {
+    let note = match record.kind {
+        Kind::Change => String::new(),
+        Kind::Undo(seq) => format!(" (undo of #{})", seq),
+        Kind::Redo(seq) => format!(" (redo of #{})", seq),
+    };
+    println!(
+        "{} {}  {}{}",
+        format!("#{}", record.seq).cyan(),
+        record.at.format("%Y-%m-%d %H:%M").to_string().dimmed(),
+        change.action().bold(),
+        note.dimmed()
+    );
+
+    print_fields(change);
+}
+
+/// Prints the fields a change touches: the changed ones of an edited entry,
+/// or the command of an added or deleted one
+pub(super) fn print_fields(change: &Change) {
+    match (&change.before, &change.after) {
+        (Some(before), Some(after)) => {
+            for ((name, old), (_, new)) in fields(before).into_iter().zip(fields(after)) {
+                if old != new {
+                    println!(
+                        "    {} {} {} {}",
+                        name.dimmed(),
+                        old.red(),
+                        "→".dimmed(),
+                        new.green()
+                    );
+                }
+            }
+        }
+        (None, Some(entry)) | (Some(entry), None) => {
+            println!("    {} {}", "command:".dimmed(), entry.command);
+        }
+        (None, None) => {}
+    }
+}
+
+/// The journaled fields of an entry, for showing what changed
+fn fields(entry: &CommandEntry) -> [(&'static str, String); 4] {
+    [
+        ("command:", entry.command.clone()),
+        ("description:", entry.description.clone()),
+        ("tags:", entry.tags_display()),
+        (
+            "shell:",
+            entry.shell.clone().unwrap_or_else(|| "$SHELL".to_string()),
+        ),
+    ]
+}
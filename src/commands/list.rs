@@ -1,91 +1,62 @@
 //! List command implementation
 
+use std::io::Write;
+
 use anyhow::Result;
 use colored::Colorize;
-use tabled::{
-    settings::{object::Columns, Modify, Style, Width},
-    Table, Tabled,
-};
 
-use crate::models::CommandEntry;
+use super::output::{self, OutputFormat};
+use crate::models::{self, SortOrder};
+use crate::output_template::OutputTemplate;
 use crate::storage::Storage;
 
-/// Table row for display
-#[derive(Tabled)]
-struct CommandRow {
-    #[tabled(rename = "ID")]
-    id: u64,
-    #[tabled(rename = "Command")]
-    command: String,
-    #[tabled(rename = "Description")]
-    description: String,
-    #[tabled(rename = "Tags")]
-    tags: String,
-}
+/// Lists all saved commands, or only those carrying a tag
+///
+/// Entries are printed as a table, through `template` if given, or in a
+/// machine-readable `format`.
+pub fn execute(
+    full: bool,
+    tag: Option<&str>,
+    sort: SortOrder,
+    format: OutputFormat,
+    template: Option<&OutputTemplate>,
+) -> Result<()> {
+    let storage = Storage::new()?;
 
-impl From<&CommandEntry> for CommandRow {
-    fn from(entry: &CommandEntry) -> Self {
-        Self {
-            id: entry.id,
-            command: entry.command.clone(),
-            description: entry.description.clone(),
-            tags: entry.tags_display(),
-        }
+    let mut entries = match tag {
+        Some(tag) => storage.find_by_tag(tag)?,
+        None => storage.load_merged()?.list_all().to_vec(),
+    };
+    if sort == SortOrder::Frecency {
+        models::sort_by_frecency(&mut entries, |e| e);
     }
-}
 
-/// Truncates a string to the specified length
-fn truncate(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else {
-        format!("{}...", &s[..max_len.saturating_sub(3)])
+    if let Some(template) = template {
+        return output::print_templated(&entries, template);
     }
-}
-
-/// Creates a CommandRow with optional truncation
-fn create_row(entry: &CommandEntry, full: bool) -> CommandRow {
-    if full {
-        CommandRow::from(entry)
-    } else {
-        CommandRow {
-            id: entry.id,
-            command: truncate(&entry.command, 50),
-            description: truncate(&entry.description, 40),
-            tags: truncate(&entry.tags_display(), 20),
-        }
+    if format != OutputFormat::Table {
+        return output::print_entries(&entries, format);
     }
-}
-
-/// Lists all saved commands
-pub fn execute(full: bool) -> Result<()> {
-    let storage = Storage::new()?;
-    let db = storage.load()?;
-
-    let entries = db.list_all();
 
     if entries.is_empty() {
-        println!("{}", "No commands saved yet.".yellow());
-        println!("Use {} to add your first command.", "cmd-keeper add".cyan());
+        match tag {
+            Some(tag) => println!("{} No commands tagged '{}'", "✗".red(), tag.yellow()),
+            None => {
+                println!("{}", "No commands saved yet.".yellow());
+                println!("Use {} to add your first command.", "cmd-keeper add".cyan());
+            }
+        }
         return Ok(());
     }
 
-    let rows: Vec<CommandRow> = entries.iter().map(|e| create_row(e, full)).collect();
-
-    let mut table = Table::new(rows);
-    table.with(Style::rounded());
-
-    if !full {
-        table.with(Modify::new(Columns::single(1)).with(Width::truncate(50).suffix("...")));
-        table.with(Modify::new(Columns::single(2)).with(Width::truncate(40).suffix("...")));
-    }
-
-    println!("{}", table);
-    println!(
-        "\n{} {} command(s)",
-        "Total:".dimmed(),
-        entries.len().to_string().cyan()
-    );
-
-    Ok(())
+    output::print_with(|out| {
+        output::write_table(out, entries.iter().map(|e| (e, &[][..])), full)?;
+        writeln!(
+            out,
+            "\n{} {} command(s)",
+            "Total:".dimmed(),
+            entries.len().to_string().cyan()
+        )?;
+        output::write_project_note(out, &storage, &entries)
+    })
 }
@@ -0,0 +1,84 @@
+//! Migrate command implementation
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use colored::Colorize;
+use std::fs;
+
+use crate::storage::location::{self, Origin};
+use crate::storage::{BackendKind, Storage};
+
+/// Moves the database to another storage backend
+///
+/// The new file is written next to the current one, checked, and then the
+/// old file is renamed aside so that the new one is picked up from now on.
+pub fn execute(to: BackendKind) -> Result<()> {
+    let origin = location::current()?.origin;
+    let source = Storage::new()?;
+
+    if source.kind() == to {
+        println!(
+            "Already using the {} backend: {}",
+            to.to_string().cyan(),
+            source.db_path().display()
+        );
+        return Ok(());
+    }
+
+    let target_path = source.db_path().with_file_name(to.default_file_name());
+    if target_path.exists() {
+        bail!(
+            "{} already exists. Move it aside before migrating.",
+            target_path.display()
+        );
+    }
+
+    let _lock = source.lock()?;
+    let db = source.load()?;
+
+    let target = Storage::with_path(target_path.clone());
+    target.save(&db)?;
+
+    let copied = target.load()?.entries.len();
+    if copied != db.entries.len() {
+        let _ = fs::remove_file(&target_path);
+        bail!(
+            "Migration check failed: wrote {} of {} entries. Nothing was changed.",
+            copied,
+            db.entries.len()
+        );
+    }
+
+    // Retire the old file so the new backend is used from now on
+    let retired = if source.db_path().exists() {
+        let mut retired = source.db_path().clone().into_os_string();
+        retired.push(format!(".{}.bak", Utc::now().format("%Y%m%d%H%M%S")));
+        fs::rename(source.db_path(), &retired)?;
+        Some(retired)
+    } else {
+        None
+    };
+
+    println!(
+        "{} Migrated {} command(s) to the {} backend",
+        "✓".green().bold(),
+        copied.to_string().cyan(),
+        to.to_string().cyan()
+    );
+    println!("  {} {}", "Database:".dimmed(), target_path.display());
+    if let Some(retired) = retired {
+        println!(
+            "  {} {}",
+            "Old file kept at:".dimmed(),
+            retired.to_string_lossy()
+        );
+    }
+    // Explicit paths are used as-is, so they have to be updated by hand
+    match origin {
+        Origin::Flag => println!("  Pass the new file to --db from now on."),
+        Origin::Env => println!("  Point {} at the new file.", location::DB_ENV),
+        Origin::Profile(_) | Origin::Default => {}
+    }
+
+    Ok(())
+}
@@ -3,15 +3,58 @@
 //! This module contains the business logic for each CLI command.
 
 mod add;
+mod backup;
 mod copy;
 mod delete;
+mod doctor;
 mod edit;
+mod export;
+mod fill;
+mod import;
+mod init;
+mod journal;
 mod list;
+mod migrate;
+mod output;
+mod pick;
+mod run;
 mod search;
+mod show;
+mod stats;
+mod sync;
+mod tags;
+mod trash;
 
 pub use add::execute as add;
+pub use backup::list as backup_list;
+pub use backup::restore as backup_restore;
 pub use copy::execute as copy;
 pub use delete::execute as delete;
+pub use doctor::execute as doctor;
 pub use edit::execute as edit;
+pub use export::execute as export;
+pub use import::file as import_file;
+pub use import::history as import_history;
+pub use init::execute as init;
+pub use init::Shell;
+pub use journal::history;
+pub use journal::redo;
+pub use journal::undo;
 pub use list::execute as list;
+pub use migrate::execute as migrate;
+pub use output::OutputFormat;
+pub use pick::execute as pick;
+pub use run::execute as run;
 pub use search::execute as search;
+pub use show::execute as show;
+pub use stats::execute as stats;
+pub use stats::StatsFormat;
+pub use sync::execute as sync;
+pub use tags::list as tags_list;
+pub use tags::merge as tags_merge;
+pub use tags::remove as tags_remove;
+pub use tags::rename as tags_rename;
+pub use trash::list as trash_list;
+pub use trash::purge as trash_purge;
+pub use trash::restore as trash_restore;
+pub use trash::Age;
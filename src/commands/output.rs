@@ -0,0 +1,382 @@
+//! Output of entries for list, search and show
+//!
+//! By default entries are printed as a table whose columns are
+//! [`OutputTemplate`]s, and `--format` replaces the table with a template
+//! of the user's own. With `--output json|ndjson|tsv`, entries are printed
+//! in a stable, flat schema instead: every field of [`CommandEntry`] is
+//! always present, `null` (or an empty TSV cell) when unset, and no colors
+//! are used. The exception is `deleted_at`, as entries in the trash are
+//! never printed this way.
+
+use std::io::{self, Write};
+
+use anyhow::Result;
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Serialize;
+use tabled::{builder::Builder, settings::Style};
+
+use crate::models::{CommandEntry, EntryRef, Source};
+use crate::output_template::OutputTemplate;
+use crate::storage::Storage;
+
+/// How list, search and show print entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable table
+    #[default]
+    Table,
+    /// A JSON array (a single object for `show`)
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Tab-separated values with a header row
+    Tsv,
+}
+
+/// Columns of the table: header, and template when truncated and in full
+const TABLE_COLUMNS: [(&str, &str, &str); 4] = [
+    ("ID", "{{id}}", "{{id}}"),
+    ("Command", "{{command|truncate:50}}", "{{command}}"),
+    (
+        "Description",
+        "{{description|truncate:40}}",
+        "{{description}}",
+    ),
+    (
+        "Tags",
+        "{{tags|truncate:20|default:-}}",
+        "{{tags|default:-}}",
+    ),
+];
+
+/// Columns of the TSV output, in the order of [`EntryRecord`]'s fields
+const TSV_HEADER: &str = "id\tcommand\tdescription\ttags\tcreated_at\tupdated_at\tshell\t\
+                          use_count\tlast_used_at\tlast_exit_code\trun_count\tfailure_count\tsource";
+
+/// An entry in the output schema
+#[derive(Serialize)]
+struct EntryRecord<'a> {
+    /// The number alone, also for project entries; TSV writes `p3` for
+    /// those, as text has no types to keep
+    id: u64,
+    command: &'a str,
+    description: &'a str,
+    tags: &'a [String],
+    created_at: DateTime<Utc>,
+    updated_at: Option<DateTime<Utc>>,
+    shell: Option<&'a str>,
+    use_count: u64,
+    last_used_at: Option<DateTime<Utc>>,
+    last_exit_code: Option<i32>,
+    run_count: u64,
+    failure_count: u64,
+    /// `global`, or `project` for entries of the project file
+    source: Source,
+}
+
+impl<'a> From<&'a CommandEntry> for EntryRecord<'a> {
+    fn from(entry: &'a CommandEntry) -> Self {
+        Self {
+            id: entry.id,
+            command: &entry.command,
+            description: &entry.description,
+            tags: &entry.tags,
+            created_at: entry.created_at,
+            updated_at: entry.updated_at,
+            shell: entry.shell.as_deref(),
+            use_count: entry.usage.use_count,
+            last_used_at: entry.usage.last_used_at,
+            last_exit_code: entry.usage.last_exit_code,
+            run_count: entry.usage.run_count,
+            failure_count: entry.usage.failure_count,
+            source: entry.source,
+        }
+    }
+}
+
+impl EntryRecord<'_> {
+    /// Formats the record as a TSV row (without the line break)
+    fn tsv_row(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let reference = EntryRef {
+            source: self.source,
+            id: self.id,
+        };
+        [
+            reference.to_string(),
+            tsv_escape(self.command),
+            tsv_escape(self.description),
+            tsv_escape(&self.tags.join(",")),
+            timestamp(self.created_at),
+            optional(self.updated_at.map(timestamp)),
+            optional(self.shell.map(tsv_escape)),
+            self.use_count.to_string(),
+            optional(self.last_used_at.map(timestamp)),
+            optional(self.last_exit_code.map(|c| c.to_string())),
+            self.run_count.to_string(),
+            self.failure_count.to_string(),
+            self.source.to_string(),
+        ]
+        .join("\t")
+    }
+}
+
+/// Formats a time like the JSON output does
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Escapes backslashes, tabs and line breaks so a value fits in one cell
+fn tsv_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Writes entries as a table, highlighting the given character positions
+/// of each command, and truncating long values unless `full` is set
+pub fn write_table<'a>(
+    out: &mut impl Write,
+    rows: impl IntoIterator<Item = (&'a CommandEntry, &'a [usize])>,
+    full: bool,
+) -> Result<()> {
+    let columns: Vec<OutputTemplate> = TABLE_COLUMNS
+        .iter()
+        .map(|(_, short, long)| {
+            OutputTemplate::parse(if full { long } else { short })
+                .expect("built-in column templates are valid")
+        })
+        .collect();
+
+    let mut builder = Builder::default();
+    builder.push_record(TABLE_COLUMNS.iter().map(|(header, _, _)| *header));
+    for (entry, indices) in rows {
+        builder.push_record(
+            columns
+                .iter()
+                .map(|column| column.render_highlighted(entry, indices)),
+        );
+    }
+
+    let mut table = builder.build();
+    table.with(Style::rounded());
+    writeln!(out, "{}", table)?;
+    Ok(())
+}
+
+/// Notes where the project commands (`p3`) among the written entries come
+/// from, if there are any
+pub fn write_project_note<'a>(
+    out: &mut impl Write,
+    storage: &Storage,
+    entries: impl IntoIterator<Item = &'a CommandEntry>,
+) -> Result<()> {
+    let count = entries
+        .into_iter()
+        .filter(|e| e.source == Source::Project)
+        .count();
+    if let (Some(project), true) = (storage.project(), count > 0) {
+        writeln!(
+            out,
+            "{} {} command(s) marked p<ID> are from {}",
+            "Project:".dimmed(),
+            count.to_string().cyan(),
+            project.path().display().to_string().yellow()
+        )?;
+    }
+    Ok(())
+}
+
+/// Prints each entry through a user-supplied template, one per line
+pub fn print_templated<'a>(
+    entries: impl IntoIterator<Item = &'a CommandEntry>,
+    template: &OutputTemplate,
+) -> Result<()> {
+    print_with(|out| {
+        for entry in entries {
+            writeln!(out, "{}", template.render(entry))?;
+        }
+        Ok(())
+    })
+}
+
+/// Writes entries in a machine-readable format
+///
+/// `OutputFormat::Table` is left to the caller and writes nothing.
+pub fn write_entries<'a>(
+    out: &mut impl Write,
+    entries: impl IntoIterator<Item = &'a CommandEntry>,
+    format: OutputFormat,
+) -> Result<()> {
+    let records = entries.into_iter().map(EntryRecord::from);
+
+    match format {
+        OutputFormat::Table => {}
+        OutputFormat::Json => {
+            let records: Vec<EntryRecord> = records.collect();
+            serde_json::to_writer_pretty(&mut *out, &records)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Ndjson => {
+            for record in records {
+                serde_json::to_writer(&mut *out, &record)?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Tsv => {
+            writeln!(out, "{}", TSV_HEADER)?;
+            for record in records {
+                writeln!(out, "{}", record.tsv_row())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints entries to stdout in a machine-readable format
+pub fn print_entries<'a>(
+    entries: impl IntoIterator<Item = &'a CommandEntry>,
+    format: OutputFormat,
+) -> Result<()> {
+    print_with(|out| write_entries(out, entries, format))
+}
+
+/// Prints a single entry to stdout; JSON output is an object, not an array
+pub fn print_entry(entry: &CommandEntry, format: OutputFormat) -> Result<()> {
+    if format == OutputFormat::Json {
+        print_with(|out| {
+            serde_json::to_writer_pretty(&mut *out, &EntryRecord::from(entry))?;
+            writeln!(out)?;
+            Ok(())
+        })
+    } else {
+        print_entries([entry], format)
+    }
+}
+
+/// Writes to a locked stdout through `f`
+///
+/// Output is often piped into `head`, `fzf` or the like, which may close
+/// the pipe before everything is written; that ends the output quietly
+/// instead of failing.
+pub fn print_with(f: impl FnOnce(&mut io::StdoutLock<'static>) -> Result<()>) -> Result<()> {
+    let mut out = io::stdout().lock();
+    ignore_broken_pipe(f(&mut out).and_then(|()| Ok(out.flush()?)))
+}
+
+/// Treats a closed stdout, e.g. when piped into `head`, as the end of the
+/// output rather than an error
+fn ignore_broken_pipe(result: Result<()>) -> Result<()> {
+    match result {
+        Err(e) if is_broken_pipe(&e) => Ok(()),
+        result => result,
+    }
+}
+
+/// Returns true if writing failed because the reader went away
+fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    let kind = error
+        .downcast_ref::<io::Error>()
+        .map(io::Error::kind)
+        .or_else(|| {
+            error
+                .downcast_ref::<serde_json::Error>()
+                .and_then(serde_json::Error::io_error_kind)
+        });
+    kind == Some(io::ErrorKind::BrokenPipe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sample() -> Vec<CommandEntry> {
+        let mut entry = CommandEntry::new(
+            3,
+            "printf 'a\\tb'\nls".to_string(),
+            "Two\tcolumns".to_string(),
+            vec!["x".to_string(), "y".to_string()],
+        );
+        entry.updated_at = Some(Utc.with_ymd_and_hms(2024, 1, 18, 16, 45, 0).unwrap());
+        entry.usage.use_count = 2;
+        entry.usage.last_exit_code = Some(1);
+        vec![entry]
+    }
+
+    fn render(entries: &[CommandEntry], format: OutputFormat) -> String {
+        let mut out = Vec::new();
+        write_entries(&mut out, entries, format).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_json_has_every_field() {
+        let entries = sample();
+        let value: serde_json::Value =
+            serde_json::from_str(&render(&entries, OutputFormat::Json)).unwrap();
+        let object = value[0].as_object().unwrap();
+        let mut columns: Vec<&str> = TSV_HEADER.split('\t').collect();
+        columns.sort_unstable();
+        assert!(object.keys().eq(columns));
+        assert_eq!(object["shell"], serde_json::Value::Null);
+        assert_eq!(object["updated_at"], "2024-01-18T16:45:00Z");
+        assert_eq!(object["tags"], serde_json::json!(["x", "y"]));
+
+        let ndjson = render(&entries, OutputFormat::Ndjson);
+        assert_eq!(ndjson.lines().count(), 1);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&ndjson).unwrap(),
+            value[0]
+        );
+    }
+
+    #[test]
+    fn test_broken_pipe_is_not_an_error() {
+        let broken = || io::Error::from(io::ErrorKind::BrokenPipe);
+        assert!(ignore_broken_pipe(Err(broken().into())).is_ok());
+        assert!(ignore_broken_pipe(Err(serde_json::Error::io(broken()).into())).is_ok());
+        let other = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert!(ignore_broken_pipe(Err(other.into())).is_err());
+    }
+
+    #[test]
+    fn test_tsv_escapes_values() {
+        let tsv = render(&sample(), OutputFormat::Tsv);
+        let lines: Vec<&str> = tsv.lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        let cells: Vec<&str> = lines[1].split('\t').collect();
+        assert_eq!(cells.len(), 13);
+        assert_eq!(cells[1], "printf 'a\\\\tb'\\nls");
+        assert_eq!(cells[2], "Two\\tcolumns");
+        assert_eq!(cells[3], "x,y");
+        assert_eq!(cells[5], "2024-01-18T16:45:00Z");
+        assert_eq!(&cells[6..], ["", "2", "", "1", "0", "0", "global"]);
+    }
+
+    #[test]
+    fn test_project_ids_stay_apart() {
+        let mut entries = sample();
+        let mut project = CommandEntry::new(3, "make".to_string(), String::new(), vec![]);
+        project.source = Source::Project;
+        entries.push(project);
+
+        let tsv = render(&entries, OutputFormat::Tsv);
+        let ids: Vec<&str> = tsv
+            .lines()
+            .skip(1)
+            .map(|l| l.split('\t').next().unwrap())
+            .collect();
+        assert_eq!(ids, vec!["3", "p3"]);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&entries, OutputFormat::Json)).unwrap();
+        assert_eq!(json[1]["id"], 3);
+        assert_eq!(json[1]["source"], "project");
+    }
+}
@@ -0,0 +1,27 @@
+//! Pick command implementation
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::tui;
+
+/// Lets the user pick a command interactively and prints it
+///
+/// The picker is drawn on the terminal, so only the chosen command (with
+/// placeholders filled) ends up on stdout, or in `output` if given. Returns
+/// false if the picker was cancelled.
+pub fn execute(query: Option<&str>, output: Option<&Path>) -> Result<bool> {
+    let Some(command) = tui::pick(query)? else {
+        return Ok(false);
+    };
+
+    match output {
+        Some(path) => fs::write(path, &command)
+            .with_context(|| format!("Failed to write to {}", path.display()))?,
+        None => println!("{}", command),
+    }
+
+    Ok(true)
+}
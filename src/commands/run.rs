@@ -0,0 +1,44 @@
+//! Run command implementation
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+use super::fill::fill_placeholders;
+use crate::executor;
+use crate::models::EntryRef;
+use crate::storage::Storage;
+
+/// Runs a command by ID through the shell and returns its exit code
+///
+/// Placeholders in the command are filled from `set` or asked for on stdin.
+pub fn execute(id: EntryRef, set: &[(String, String)], shell: Option<&str>) -> Result<i32> {
+    let storage = Storage::new()?;
+
+    // Find the command
+    let entry = match storage.find(id)? {
+        Some(e) => e,
+        None => {
+            bail!("Command with ID {} not found", id);
+        }
+    };
+
+    let command = fill_placeholders(&entry.command, set)?;
+    let shell = executor::resolve_shell(shell, entry.shell.as_deref());
+
+    eprintln!("{} {}", "$".dimmed(), command.cyan().bold());
+    let exit_code = executor::run(&shell, &command)?;
+
+    if let Err(e) = storage.record_use(id, Some(exit_code)) {
+        eprintln!("{} Could not record usage: {}", "⚠".yellow(), e);
+    }
+
+    if exit_code != 0 {
+        eprintln!(
+            "{} Command exited with code {}",
+            "⚠".yellow(),
+            exit_code.to_string().yellow()
+        );
+    }
+
+    Ok(exit_code)
+}
@@ -1,62 +1,65 @@
 //! Search command implementation
 
-use anyhow::Result;
+use std::io::Write;
+
+use anyhow::{anyhow, Result};
 use colored::Colorize;
-use tabled::{
-    settings::{object::Columns, Modify, Style, Width},
-    Table, Tabled,
-};
 
-use crate::models::CommandEntry;
+use super::output::{self, OutputFormat};
+use crate::models::{self, CommandDatabase, CommandEntry, SortOrder};
+use crate::output_template::OutputTemplate;
+use crate::query::Query;
 use crate::storage::Storage;
 
-/// Table row for display
-#[derive(Tabled)]
-struct CommandRow {
-    #[tabled(rename = "ID")]
-    id: u64,
-    #[tabled(rename = "Command")]
-    command: String,
-    #[tabled(rename = "Description")]
-    description: String,
-    #[tabled(rename = "Tags")]
-    tags: String,
-}
-
-/// Truncates a string to the specified length
-fn truncate(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else {
-        format!("{}...", &s[..max_len.saturating_sub(3)])
-    }
-}
+/// Searches commands by keyword
+///
+/// By default the keyword is parsed as a query (see [`crate::query`]) and
+/// results are ranked by relevance. With `exact`, entries containing the
+/// keyword are listed in ID order, using the backend's index where it has
+/// one. `SortOrder::Frecency` puts the most used matches first instead.
+///
+/// Results are printed like [`super::list`] prints entries.
+pub fn execute(
+    keyword: &str,
+    full: bool,
+    exact: bool,
+    sort: SortOrder,
+    format: OutputFormat,
+    template: Option<&OutputTemplate>,
+) -> Result<()> {
+    let storage = Storage::new()?;
 
-/// Creates a CommandRow with optional truncation
-fn create_row(entry: &CommandEntry, full: bool) -> CommandRow {
-    if full {
-        CommandRow {
-            id: entry.id,
-            command: entry.command.clone(),
-            description: entry.description.clone(),
-            tags: entry.tags_display(),
+    // Matching entries with the matched character indices of their command
+    let results: Vec<(CommandEntry, Vec<usize>)> = if exact {
+        let mut entries = storage.search_text(keyword)?;
+        if sort == SortOrder::Frecency {
+            models::sort_by_frecency(&mut entries, |e| e);
         }
+        entries.into_iter().map(|e| (e, Vec::new())).collect()
     } else {
-        CommandRow {
-            id: entry.id,
-            command: truncate(&entry.command, 50),
-            description: truncate(&entry.description, 40),
-            tags: truncate(&entry.tags_display(), 20),
+        let query = Query::parse(keyword).map_err(|e| {
+            anyhow!(
+                "Invalid query: {}\n\n  {}",
+                e,
+                e.pointer(keyword).replace('\n', "\n  ")
+            )
+        })?;
+        let db = candidates(&storage, &query)?;
+        let mut hits = db.search_ranked(&query);
+        if sort == SortOrder::Frecency {
+            models::sort_by_frecency(&mut hits, |hit| hit.entry);
         }
-    }
-}
-
-/// Searches commands by keyword
-pub fn execute(keyword: &str, full: bool) -> Result<()> {
-    let storage = Storage::new()?;
-    let db = storage.load()?;
+        hits.into_iter()
+            .map(|hit| (hit.entry.clone(), hit.command_indices))
+            .collect()
+    };
 
-    let results = db.search(keyword);
+    if let Some(template) = template {
+        return output::print_templated(results.iter().map(|(entry, _)| entry), template);
+    }
+    if format != OutputFormat::Table {
+        return output::print_entries(results.iter().map(|(entry, _)| entry), format);
+    }
 
     if results.is_empty() {
         println!(
@@ -67,23 +70,39 @@ pub fn execute(keyword: &str, full: bool) -> Result<()> {
         return Ok(());
     }
 
-    println!(
-        "🔍 Found {} result(s) for '{}':\n",
-        results.len().to_string().cyan(),
-        keyword.yellow()
-    );
-
-    let rows: Vec<CommandRow> = results.iter().map(|e| create_row(e, full)).collect();
-
-    let mut table = Table::new(rows);
-    table.with(Style::rounded());
+    output::print_with(|out| {
+        writeln!(
+            out,
+            "🔍 Found {} result(s) for '{}':\n",
+            results.len().to_string().cyan(),
+            keyword.yellow()
+        )?;
+        output::write_table(
+            out,
+            results
+                .iter()
+                .map(|(entry, indices)| (entry, indices.as_slice())),
+            full,
+        )?;
+        output::write_project_note(out, &storage, results.iter().map(|(entry, _)| entry))
+    })
+}
 
-    if !full {
-        table.with(Modify::new(Columns::single(1)).with(Width::truncate(50).suffix("...")));
-        table.with(Modify::new(Columns::single(2)).with(Width::truncate(40).suffix("...")));
+/// Loads the entries a query is ranked over
+///
+/// With an indexed backend and no project file, the index narrows them down
+/// to the entries containing the words of a plain query, which all match.
+/// Only if there are none (e.g. for `gco`) is every entry loaded for fuzzy
+/// matching.
+fn candidates(storage: &Storage, query: &Query) -> Result<CommandDatabase> {
+    if storage.is_indexed() && storage.project().is_none() {
+        if let Some(text) = query.plain_text() {
+            let mut db = CommandDatabase::new();
+            db.entries = storage.search_text(&text)?;
+            if !db.search_ranked(query).is_empty() {
+                return Ok(db);
+            }
+        }
     }
-
-    println!("{}", table);
-
-    Ok(())
+    Ok(storage.load_merged()?)
 }
@@ -0,0 +1,89 @@
+//! Show command implementation
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+use super::output::{self, OutputFormat};
+use crate::models::{EntryRef, Source};
+use crate::storage::Storage;
+use crate::template;
+
+/// Shows every field of a command by ID
+pub fn execute(id: EntryRef, format: OutputFormat) -> Result<()> {
+    let storage = Storage::new()?;
+
+    let entry = match storage.find(id)? {
+        Some(e) => e,
+        None => {
+            bail!("Command with ID {} not found", id);
+        }
+    };
+
+    if format != OutputFormat::Table {
+        return output::print_entry(&entry, format);
+    }
+
+    println!(
+        "{} {}",
+        "ID:         ".dimmed(),
+        entry.reference().to_string().cyan()
+    );
+    if let (Source::Project, Some(project)) = (entry.source, storage.project()) {
+        println!("{} {}", "Source:     ".dimmed(), project.path().display());
+    }
+    println!("{} {}", "Command:    ".dimmed(), entry.command.green());
+    println!("{} {}", "Description:".dimmed(), entry.description);
+    println!("{} {}", "Tags:       ".dimmed(), entry.tags_display());
+    println!(
+        "{} {}",
+        "Shell:      ".dimmed(),
+        entry.shell.as_deref().unwrap_or("$SHELL")
+    );
+    println!(
+        "{} {}",
+        "Created:    ".dimmed(),
+        entry.created_at.format("%Y-%m-%d %H:%M")
+    );
+
+    let usage = &entry.usage;
+    let used = match usage.last_used_at {
+        Some(last_used) => {
+            let mut text = format!(
+                "{} time(s), last {}",
+                usage.use_count,
+                last_used.format("%Y-%m-%d %H:%M")
+            );
+            if let Some(code) = usage.last_exit_code {
+                text.push_str(&format!(" (exit code {})", code));
+            }
+            text
+        }
+        None => "never".to_string(),
+    };
+    println!("{} {}", "Used:       ".dimmed(), used);
+    if usage.run_count > 0 {
+        println!(
+            "{} {} run(s), {} failed",
+            "Runs:       ".dimmed(),
+            usage.run_count,
+            usage.failure_count
+        );
+    }
+
+    let placeholders = template::placeholders(&entry.command);
+    if !placeholders.is_empty() {
+        println!("{}", "Placeholders:".dimmed());
+        for p in placeholders {
+            let mut line = format!("  {}", p.name.yellow());
+            if let Some(default) = &p.default {
+                line.push_str(&format!(" = {}", default));
+            }
+            if let Some(description) = &p.description {
+                line.push_str(&format!("  {}", description.dimmed()));
+            }
+            println!("{}", line);
+        }
+    }
+
+    Ok(())
+}
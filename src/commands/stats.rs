@@ -0,0 +1,412 @@
+//! Stats command implementation
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Utc};
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Serialize;
+use tabled::{builder::Builder, settings::Style};
+
+use super::output;
+use crate::models::CommandEntry;
+use crate::storage::Storage;
+
+/// How the stats command prints its report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum StatsFormat {
+    /// Human-readable tables
+    #[default]
+    Table,
+    /// A single JSON object
+    Json,
+}
+
+/// Usage report over the whole database
+#[derive(Debug, Serialize)]
+struct Stats {
+    total: usize,
+    used: usize,
+    never_used: usize,
+    total_uses: u64,
+    untagged: usize,
+    tags: Vec<TagCount>,
+    most_used: Vec<UsageRow>,
+    least_used: Vec<UsageRow>,
+    /// Never used and older than `stale_days`
+    stale: Vec<StaleRow>,
+    stale_days: u64,
+    added_per_month: Vec<MonthCount>,
+    failure_rates: Vec<FailureRow>,
+}
+
+#[derive(Debug, Serialize)]
+struct TagCount {
+    tag: String,
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct UsageRow {
+    id: u64,
+    command: String,
+    use_count: u64,
+    last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+struct StaleRow {
+    id: u64,
+    command: String,
+    created_at: DateTime<Utc>,
+    age_days: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct MonthCount {
+    /// `YYYY-MM`
+    month: String,
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct FailureRow {
+    id: u64,
+    command: String,
+    runs: u64,
+    failures: u64,
+    /// Share of failed runs, from 0 to 1
+    failure_rate: f64,
+}
+
+impl Stats {
+    /// Computes the report; `top` limits the most used, least used and
+    /// failure rate lists
+    fn compute(entries: &[CommandEntry], now: DateTime<Utc>, stale_days: u64, top: usize) -> Self {
+        let used: Vec<&CommandEntry> = entries.iter().filter(|e| e.usage.use_count > 0).collect();
+        let usage_row = |e: &&CommandEntry| UsageRow {
+            id: e.id,
+            command: e.command.clone(),
+            use_count: e.usage.use_count,
+            last_used_at: e.usage.last_used_at,
+        };
+
+        let mut tag_counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for tag in entries.iter().flat_map(|e| &e.tags) {
+            *tag_counts.entry(tag).or_default() += 1;
+        }
+        let mut tags: Vec<TagCount> = tag_counts
+            .into_iter()
+            .map(|(tag, count)| TagCount {
+                tag: tag.to_string(),
+                count,
+            })
+            .collect();
+        tags.sort_by_key(|t| Reverse(t.count));
+
+        let mut most_used = used.clone();
+        most_used.sort_by(|a, b| {
+            b.usage
+                .use_count
+                .cmp(&a.usage.use_count)
+                .then(b.usage.last_used_at.cmp(&a.usage.last_used_at))
+        });
+        let mut least_used = used.clone();
+        least_used.sort_by(|a, b| {
+            a.usage
+                .use_count
+                .cmp(&b.usage.use_count)
+                .then(a.usage.last_used_at.cmp(&b.usage.last_used_at))
+        });
+
+        let stale = entries
+            .iter()
+            .filter(|e| e.usage.use_count == 0)
+            .map(|e| StaleRow {
+                id: e.id,
+                command: e.command.clone(),
+                created_at: e.created_at,
+                age_days: (now - e.created_at).num_days(),
+            })
+            .filter(|row| u64::try_from(row.age_days).is_ok_and(|age| age >= stale_days))
+            .collect();
+
+        let mut failure_rates: Vec<FailureRow> = entries
+            .iter()
+            .filter(|e| e.usage.run_count > 0)
+            .map(|e| FailureRow {
+                id: e.id,
+                command: e.command.clone(),
+                runs: e.usage.run_count,
+                failures: e.usage.failure_count,
+                failure_rate: e.usage.failure_count as f64 / e.usage.run_count as f64,
+            })
+            .collect();
+        failure_rates.sort_by(|a, b| {
+            b.failure_rate
+                .total_cmp(&a.failure_rate)
+                .then(b.runs.cmp(&a.runs))
+        });
+        failure_rates.truncate(top);
+
+        Self {
+            total: entries.len(),
+            used: used.len(),
+            never_used: entries.len() - used.len(),
+            total_uses: used.iter().map(|e| e.usage.use_count).sum(),
+            untagged: entries.iter().filter(|e| e.tags.is_empty()).count(),
+            tags,
+            most_used: most_used.iter().take(top).map(usage_row).collect(),
+            least_used: least_used.iter().take(top).map(usage_row).collect(),
+            stale,
+            stale_days,
+            added_per_month: added_per_month(entries),
+            failure_rates,
+        }
+    }
+}
+
+/// Counts entries by the month they were created, from the first month to
+/// the last, including months in between without any
+fn added_per_month(entries: &[CommandEntry]) -> Vec<MonthCount> {
+    let mut counts: BTreeMap<(i32, u32), usize> = BTreeMap::new();
+    for entry in entries {
+        let created = entry.created_at;
+        *counts.entry((created.year(), created.month())).or_default() += 1;
+    }
+
+    let (Some(&first), Some(&last)) = (counts.keys().next(), counts.keys().next_back()) else {
+        return Vec::new();
+    };
+
+    let mut months = Vec::new();
+    let (mut year, mut month) = first;
+    while (year, month) <= last {
+        months.push(MonthCount {
+            month: format!("{:04}-{:02}", year, month),
+            count: counts.get(&(year, month)).copied().unwrap_or(0),
+        });
+        if month == 12 {
+            year += 1;
+            month = 1;
+        } else {
+            month += 1;
+        }
+    }
+    months
+}
+
+/// Prints usage statistics of the saved commands
+///
+/// Entries never used and created more than `stale_days` ago are listed as
+/// candidates for pruning; `top` limits the other lists.
+pub fn execute(stale_days: u64, top: usize, format: StatsFormat) -> Result<()> {
+    let storage = Storage::new()?;
+    let db = storage.load()?;
+    let stats = Stats::compute(db.list_all(), Utc::now(), stale_days, top);
+
+    match format {
+        StatsFormat::Json => output::print_with(|out| {
+            serde_json::to_writer_pretty(&mut *out, &stats)?;
+            writeln!(out)?;
+            Ok(())
+        }),
+        StatsFormat::Table => {
+            print_report(&stats);
+            Ok(())
+        }
+    }
+}
+
+/// Prints the report as a series of tables
+fn print_report(stats: &Stats) {
+    println!(
+        "{} {} command(s), {} used ({} use(s) in total), {} never used",
+        "Total:".dimmed(),
+        stats.total.to_string().cyan(),
+        stats.used.to_string().cyan(),
+        stats.total_uses,
+        stats.never_used.to_string().yellow()
+    );
+    if stats.total == 0 {
+        return;
+    }
+
+    let mut rows: Vec<[String; 2]> = stats
+        .tags
+        .iter()
+        .map(|t| [t.tag.clone(), t.count.to_string()])
+        .collect();
+    if stats.untagged > 0 {
+        rows.push(["(untagged)".to_string(), stats.untagged.to_string()]);
+    }
+    print_section("Entries per tag", ["Tag", "Entries"], rows);
+
+    let usage_rows = |list: &[UsageRow]| -> Vec<[String; 4]> {
+        list.iter()
+            .map(|r| {
+                [
+                    r.id.to_string(),
+                    truncated(&r.command),
+                    r.use_count.to_string(),
+                    r.last_used_at
+                        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect()
+    };
+    let usage_header = ["ID", "Command", "Uses", "Last used"];
+    print_section("Most used", usage_header, usage_rows(&stats.most_used));
+    print_section("Least used", usage_header, usage_rows(&stats.least_used));
+
+    print_section(
+        &format!("Never used, older than {} days", stats.stale_days),
+        ["ID", "Command", "Created", "Age (days)"],
+        stats
+            .stale
+            .iter()
+            .map(|r| {
+                [
+                    r.id.to_string(),
+                    truncated(&r.command),
+                    r.created_at.format("%Y-%m-%d").to_string(),
+                    r.age_days.to_string(),
+                ]
+            })
+            .collect(),
+    );
+
+    print_section(
+        "Added per month",
+        ["Month", "Added"],
+        stats
+            .added_per_month
+            .iter()
+            .map(|m| [m.month.clone(), m.count.to_string()])
+            .collect(),
+    );
+
+    print_section(
+        "Failure rate",
+        ["ID", "Command", "Runs", "Failed"],
+        stats
+            .failure_rates
+            .iter()
+            .map(|r| {
+                [
+                    r.id.to_string(),
+                    truncated(&r.command),
+                    r.runs.to_string(),
+                    format!("{} ({:.0}%)", r.failures, r.failure_rate * 100.0),
+                ]
+            })
+            .collect(),
+    );
+}
+
+/// Prints a titled table, or a note if it has no rows
+fn print_section<const N: usize>(title: &str, header: [&str; N], rows: Vec<[String; N]>) {
+    println!("\n{}", title.cyan().bold());
+    if rows.is_empty() {
+        println!("  {}", "(none)".dimmed());
+        return;
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record(header);
+    for row in rows {
+        builder.push_record(row);
+    }
+    let mut table = builder.build();
+    table.with(Style::rounded());
+    println!("{}", table);
+}
+
+/// Shortens a command to one line of at most 50 characters
+fn truncated(command: &str) -> String {
+    let line = command.replace('\n', " ");
+    if line.chars().count() <= 50 {
+        line
+    } else {
+        line.chars().take(47).chain("...".chars()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn entry(
+        id: u64,
+        tags: &[&str],
+        days_old: i64,
+        uses: u64,
+        runs: u64,
+        failures: u64,
+    ) -> CommandEntry {
+        let now = Utc::now();
+        let mut entry = CommandEntry::new(
+            id,
+            format!("cmd {}", id),
+            String::new(),
+            tags.iter().map(|t| t.to_string()).collect(),
+        );
+        entry.created_at = now - Duration::days(days_old);
+        entry.usage.use_count = uses;
+        entry.usage.last_used_at = (uses > 0).then_some(now);
+        entry.usage.run_count = runs;
+        entry.usage.failure_count = failures;
+        entry
+    }
+
+    #[test]
+    fn test_compute() {
+        let entries = vec![
+            entry(1, &["git"], 200, 10, 4, 1),
+            entry(2, &["git", "ops"], 120, 0, 0, 0),
+            entry(3, &[], 10, 0, 0, 0),
+            entry(4, &["ops"], 5, 2, 2, 2),
+        ];
+        let stats = Stats::compute(&entries, Utc::now(), 90, 1);
+
+        assert_eq!((stats.total, stats.used, stats.never_used), (4, 2, 2));
+        assert_eq!(stats.total_uses, 12);
+        assert_eq!(stats.untagged, 1);
+        assert_eq!(stats.tags.len(), 2);
+        assert!(stats.tags.iter().all(|t| t.count == 2));
+
+        assert_eq!(stats.most_used[0].id, 1);
+        assert_eq!(stats.least_used[0].id, 4);
+        let stale: Vec<u64> = stats.stale.iter().map(|r| r.id).collect();
+        assert_eq!(stale, vec![2]);
+
+        assert_eq!(stats.failure_rates.len(), 1);
+        assert_eq!(stats.failure_rates[0].id, 4);
+        assert_eq!(stats.failure_rates[0].failure_rate, 1.0);
+    }
+
+    #[test]
+    fn test_added_per_month_fills_gaps() {
+        let mut entries = vec![entry(1, &[], 0, 0, 0, 0), entry(2, &[], 0, 0, 0, 0)];
+        entries[0].created_at = "2025-11-03T10:00:00Z".parse().unwrap();
+        entries[1].created_at = "2026-01-20T10:00:00Z".parse().unwrap();
+
+        let months: Vec<(String, usize)> = added_per_month(&entries)
+            .into_iter()
+            .map(|m| (m.month, m.count))
+            .collect();
+        assert_eq!(
+            months,
+            vec![
+                ("2025-11".to_string(), 1),
+                ("2025-12".to_string(), 0),
+                ("2026-01".to_string(), 1),
+            ]
+        );
+    }
+}
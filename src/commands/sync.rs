@@ -0,0 +1,148 @@
+//! Sync command implementation
+//!
+//! The database directory is a git repository; syncing commits local
+//! changes, fetches the remote, merges entry by entry (see [`crate::sync`])
+//! and pushes. The merged database is committed on top of the remote
+//! branch, so the history stays linear as with `git pull --rebase`.
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+use crate::models::CommandDatabase;
+use crate::storage::git::Repo;
+use crate::storage::journal::{self, Kind};
+use crate::storage::{self, BackendKind, Storage};
+use crate::sync::{self, MergeReport};
+
+/// Syncs the database with its git remote, setting the remote first if given
+pub fn execute(remote: Option<&str>) -> Result<()> {
+    let storage = Storage::new()?;
+    if storage.kind() != BackendKind::Json {
+        bail!(
+            "Sync needs the JSON backend. Run `cmd-keeper migrate --to json` first, \
+             then sync again."
+        );
+    }
+
+    let dir = storage::parent_dir(storage.db_path());
+    let file = storage::file_name(storage.db_path());
+    let repo = match Repo::open(&dir) {
+        Some(repo) => repo,
+        None => {
+            println!(
+                "{} {}",
+                "Creating git repository in".dimmed(),
+                dir.display()
+            );
+            Repo::init(&dir)?
+        }
+    };
+    repo.mark_synced()?;
+    if let Some(url) = remote {
+        repo.set_remote(url)?;
+    }
+    let Some(url) = repo.remote_url()? else {
+        bail!("No remote set. Run `cmd-keeper sync --remote <url>` once to set it.");
+    };
+
+    let _lock = storage.lock()?;
+    repo.commit_file(&file, "Update commands")?;
+    let branch = repo.branch()?;
+    let local_head = repo.head()?;
+    let remote_head = repo.fetch(&branch)?;
+
+    let report = match remote_head.as_deref() {
+        // The remote branch doesn't exist yet
+        None => MergeReport::default(),
+        Some(remote) => match local_head.as_deref() {
+            Some(local) if repo.is_ancestor(remote, local)? => MergeReport::default(),
+            Some(local) if !repo.is_ancestor(local, remote)? => {
+                merge_diverged(&storage, &repo, &file, local, remote)?
+            }
+            _ => fast_forward(&storage, &repo, remote)?,
+        },
+    };
+
+    if repo.head()? != remote_head {
+        repo.push(&branch)?;
+    }
+
+    println!("{} Synced with {}", "✓".green().bold(), url.cyan());
+    print_report(&report);
+    Ok(())
+}
+
+/// Takes the remote branch as it is when there are no local commits on top
+fn fast_forward(storage: &Storage, repo: &Repo, remote: &str) -> Result<MergeReport> {
+    let before = storage.load()?;
+    repo.reset_hard(remote)?;
+    let after = storage.load()?;
+    storage
+        .journal()
+        .append(Kind::Change, journal::diff(&before, &after))?;
+    // Merging onto an unchanged local copy reports what the remote changed
+    Ok(sync::merge(&before, &before, &after).1)
+}
+
+/// Merges diverged local and remote commits and commits the result on top
+/// of the remote branch
+fn merge_diverged(
+    storage: &Storage,
+    repo: &Repo,
+    file: &str,
+    local: &str,
+    remote: &str,
+) -> Result<MergeReport> {
+    let read = |commit: &str| -> Result<CommandDatabase> {
+        Ok(match repo.show(commit, file)? {
+            Some(content) => storage::parse_json(&content)?,
+            None => CommandDatabase::new(),
+        })
+    };
+    let base = match repo.merge_base(local, remote)? {
+        Some(base) => read(&base)?,
+        None => CommandDatabase::new(),
+    };
+    let before = read(local)?;
+    let (merged, report) = sync::merge(&base, &before, &read(remote)?);
+
+    repo.reset_soft(remote)?;
+    storage.save(&merged)?;
+    // Journaled like any other change, so undo can step back across a sync
+    storage
+        .journal()
+        .append(Kind::Change, journal::diff(&before, &merged))?;
+    repo.commit_file(file, "Update commands")?;
+    Ok(report)
+}
+
+/// Prints what the sync changed locally
+fn print_report(report: &MergeReport) {
+    if report.is_empty() {
+        println!("  {}", "No changes from the remote.".dimmed());
+        return;
+    }
+    for (count, what) in [
+        (report.added, "added"),
+        (report.updated, "updated"),
+        (report.removed, "removed"),
+    ] {
+        if count > 0 {
+            println!("  {} command(s) {}", count.to_string().cyan(), what);
+        }
+    }
+    if report.conflicts > 0 {
+        println!(
+            "  {} command(s) edited on both sides; kept the newer edit",
+            report.conflicts.to_string().yellow()
+        );
+    }
+    for (old, new) in &report.renumbered {
+        println!(
+            "  {} Local command {} is now {} (the ID was taken on the remote)",
+            "!".yellow(),
+            old,
+            new.to_string().cyan()
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Tags command implementation
+//!
+//! Tags are matched ignoring case, like `list --tag`, so renaming `K8s`
+//! also catches `k8s`, and the tags below a tag go along with it (see
+//! [`crate::tags`]). Each change rewrites every affected entry in a single
+//! save.
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+use tabled::{builder::Builder, settings::Style};
+
+use crate::storage::Storage;
+
+/// Lists all tags with the number of commands carrying them
+pub fn list() -> Result<()> {
+    let storage = Storage::new()?;
+    let tags = storage.load()?.tag_counts();
+
+    if tags.is_empty() {
+        println!("{}", "No tags in use.".yellow());
+        return Ok(());
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record(["Tag", "Commands"]);
+    for (tag, count) in &tags {
+        builder.push_record([tag.clone(), count.to_string()]);
+    }
+    let mut table = builder.build();
+    table.with(Style::rounded());
+
+    println!("{}", table);
+    println!(
+        "\n{} {} tag(s)",
+        "Total:".dimmed(),
+        tags.len().to_string().cyan()
+    );
+    Ok(())
+}
+
+/// Renames a tag on every command
+///
+/// Fails if the new name is already in use; use [`merge`] to combine tags.
+pub fn rename(old: &str, new: &str) -> Result<()> {
+    let new = validate(new)?;
+    let storage = Storage::new()?;
+
+    let changed = storage.update(|db| {
+        if !db.has_tag(old) {
+            bail!("No commands tagged '{}'", old);
+        }
+        if !new.eq_ignore_ascii_case(old) && db.has_tag(new) {
+            bail!(
+                "Tag '{}' already exists; use `cmd-keeper tags merge {} --into {}` to combine them",
+                new,
+                old,
+                new
+            );
+        }
+        Ok(db.replace_tags(&[old], Some(new)))
+    })?;
+
+    println!(
+        "{} Renamed tag '{}' to '{}' on {} command(s)",
+        "✓".green(),
+        old.yellow(),
+        new.cyan(),
+        changed
+    );
+    Ok(())
+}
+
+/// Replaces several tags with one on every command
+pub fn merge(sources: &[String], target: &str) -> Result<()> {
+    let target = validate(target)?;
+    let storage = Storage::new()?;
+
+    let sources: Vec<&str> = sources.iter().map(String::as_str).collect();
+    let changed = storage.update(|db| {
+        let missing: Vec<&str> = sources.iter().copied().filter(|s| !db.has_tag(s)).collect();
+        if missing.len() == sources.len() {
+            bail!("No commands tagged {}", quoted_list(&missing));
+        }
+        for tag in missing {
+            eprintln!("{} No commands tagged '{}'", "⚠".yellow(), tag);
+        }
+        Ok(db.replace_tags(&sources, Some(target)))
+    })?;
+
+    println!(
+        "{} Merged {} into '{}' on {} command(s)",
+        "✓".green(),
+        quoted_list(&sources).yellow(),
+        target.cyan(),
+        changed
+    );
+    Ok(())
+}
+
+/// Removes a tag from every command (the commands themselves are kept)
+pub fn remove(tag: &str) -> Result<()> {
+    let storage = Storage::new()?;
+
+    let changed = storage.update(|db| {
+        if !db.has_tag(tag) {
+            bail!("No commands tagged '{}'", tag);
+        }
+        Ok(db.replace_tags(&[tag], None))
+    })?;
+
+    println!(
+        "{} Removed tag '{}' from {} command(s)",
+        "✓".green(),
+        tag.yellow(),
+        changed
+    );
+    Ok(())
+}
+
+/// Trims a new tag name and checks that it can be stored
+fn validate(tag: &str) -> Result<&str> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        bail!("Tag names cannot be empty");
+    }
+    if tag.contains(',') {
+        bail!("Tag names cannot contain commas");
+    }
+    Ok(tag)
+}
+
+/// Formats tags as `'a', 'b'`
+fn quoted_list(tags: &[&str]) -> String {
+    tags.iter()
+        .map(|t| format!("'{}'", t))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
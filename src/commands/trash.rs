@@ -0,0 +1,182 @@
+//! Trash command implementation
+//!
+//! `delete` moves commands of the global database to the trash, where they
+//! keep their ID and are hidden from every other command until restored or
+//! purged.
+
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{Duration, Utc};
+use colored::Colorize;
+use tabled::{builder::Builder, settings::Style};
+
+use crate::storage::Storage;
+
+/// How long ago something happened, e.g. `30d`
+#[derive(Debug, Clone, Copy)]
+pub struct Age(Duration);
+
+impl FromStr for Age {
+    type Err = String;
+
+    /// Parses a number followed by `h` (hours), `d` (days) or `w` (weeks)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{}' is not an age (e.g. 30d, 2w or 12h)", s);
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: i64 = number.parse().map_err(|_| invalid())?;
+        let duration = match unit {
+            "h" => Duration::try_hours(number),
+            "d" => Duration::try_days(number),
+            "w" => Duration::try_weeks(number),
+            _ => None,
+        };
+        // Ages reaching back before the earliest representable time are
+        // rejected, so cutoffs can always be computed
+        duration
+            .filter(|&d| Utc::now().checked_sub_signed(d).is_some())
+            .map(Self)
+            .ok_or_else(invalid)
+    }
+}
+
+/// Lists the commands in the trash, most recently deleted first
+pub fn list() -> Result<()> {
+    let storage = Storage::new()?;
+    let mut trash = storage.load()?.trash;
+
+    if trash.is_empty() {
+        println!("{}", "The trash is empty.".yellow());
+        return Ok(());
+    }
+
+    trash.sort_by_key(|e| std::cmp::Reverse(e.deleted_at));
+    let mut builder = Builder::default();
+    builder.push_record(["ID", "Command", "Description", "Deleted"]);
+    for entry in &trash {
+        let deleted = entry
+            .deleted_at
+            .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        builder.push_record([
+            entry.id.to_string(),
+            entry.command.clone(),
+            entry.description.clone(),
+            deleted,
+        ]);
+    }
+    let mut table = builder.build();
+    table.with(Style::rounded());
+
+    println!("{}", table);
+    println!(
+        "\n{} {} command(s) in the trash",
+        "Total:".dimmed(),
+        trash.len().to_string().cyan()
+    );
+    Ok(())
+}
+
+/// Moves a command out of the trash, keeping its ID unless another command
+/// has taken it since
+pub fn restore(id: u64) -> Result<()> {
+    let storage = Storage::new()?;
+
+    let restored = storage.update(|db| match db.restore_from_trash(id) {
+        Some(restored) => Ok(restored),
+        None => bail!("Command {} is not in the trash", id),
+    })?;
+
+    if restored == id {
+        println!(
+            "{} Command {} restored.",
+            "✓".green().bold(),
+            id.to_string().cyan()
+        );
+    } else {
+        println!(
+            "{} Command {} restored as {}, as ID {} is taken.",
+            "✓".green().bold(),
+            id.to_string().cyan(),
+            restored.to_string().cyan(),
+            id
+        );
+    }
+    Ok(())
+}
+
+/// Deletes the commands in the trash for good, or only those deleted more
+/// than `older_than` ago
+pub fn purge(older_than: Option<Age>, force: bool) -> Result<()> {
+    let storage = Storage::new()?;
+    let cutoff = older_than
+        .map(|Age(age)| {
+            Utc::now()
+                .checked_sub_signed(age)
+                .ok_or_else(|| anyhow!("The age is too large"))
+        })
+        .transpose()?;
+
+    let count = storage
+        .load()?
+        .trash
+        .iter()
+        .filter(|e| cutoff.is_none_or(|cutoff| e.deleted_at.is_none_or(|at| at < cutoff)))
+        .count();
+    if count == 0 {
+        println!("{}", "Nothing to purge.".yellow());
+        return Ok(());
+    }
+
+    if !force {
+        print!(
+            "{}",
+            format!(
+                "Permanently delete {} command(s) from the trash? [y/N] ",
+                count
+            )
+            .yellow()
+        );
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if !matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
+            println!("{}", "Purge cancelled.".dimmed());
+            return Ok(());
+        }
+    }
+
+    let purged = storage.update(|db| Ok::<_, anyhow::Error>(db.purge_trash(cutoff)))?;
+    println!(
+        "{} Purged {} command(s) from the trash.",
+        "✓".green().bold(),
+        purged.len().to_string().cyan()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!("30d".parse::<Age>().unwrap().0, Duration::days(30));
+        assert_eq!("2w".parse::<Age>().unwrap().0, Duration::weeks(2));
+        assert_eq!("12h".parse::<Age>().unwrap().0, Duration::hours(12));
+        for invalid in ["", "30", "d", "3x", "-1d"] {
+            assert!(invalid.parse::<Age>().is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_parse_age_out_of_range() {
+        // A valid duration, but too far back to compute a cutoff from
+        assert!("99999999999d".parse::<Age>().is_err());
+        assert!("99999999999999999999d".parse::<Age>().is_err());
+    }
+}
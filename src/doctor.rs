@@ -0,0 +1,231 @@
+//! Consistency checks of the database, for `cmd-keeper doctor`
+//!
+//! [`plan`] lists the fixes a database needs and [`apply`] makes them, so the
+//! fixes can be shown and confirmed one by one in between.
+
+use std::collections::HashSet;
+
+use chrono::Utc;
+
+use crate::models::{CommandDatabase, CommandEntry};
+
+/// A problem in the database together with how it is fixed
+///
+/// Entries are addressed by their position in the list followed by the
+/// trash, as IDs may not be unique.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fix {
+    /// `next_id` is not above the largest ID, so new commands would reuse
+    /// IDs; it is raised
+    RaiseNextId { from: u64, to: u64 },
+    /// Another entry already has the ID; this one gets a new ID
+    Renumber {
+        index: usize,
+        command: String,
+        from: u64,
+        to: u64,
+    },
+    /// The entry has no command; it is moved to the trash
+    TrashEmpty { index: usize, id: u64 },
+    /// Tags have stray whitespace or are empty; they are trimmed, and empty
+    /// or repeated ones dropped
+    TrimTags {
+        index: usize,
+        id: u64,
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+}
+
+impl Fix {
+    /// Describes the problem
+    pub fn problem(&self) -> String {
+        match self {
+            Self::RaiseNextId { from, .. } => format!(
+                "The next ID ({}) is already taken, so new commands would reuse IDs",
+                from
+            ),
+            Self::Renumber { command, from, .. } => {
+                format!("ID {} is used more than once (also by `{}`)", from, command)
+            }
+            Self::TrashEmpty { id, .. } => format!("Command {} is empty", id),
+            Self::TrimTags { id, from, .. } => {
+                format!("Command {} has untidy tags: {:?}", id, from)
+            }
+        }
+    }
+
+    /// Describes the fix
+    pub fn action(&self) -> String {
+        match self {
+            Self::RaiseNextId { to, .. } => format!("set the next ID to {}", to),
+            Self::Renumber { command, to, .. } => format!("give `{}` the new ID {}", command, to),
+            Self::TrashEmpty { .. } => "move it to the trash".to_string(),
+            Self::TrimTags { to, .. } => format!("change the tags to {:?}", to),
+        }
+    }
+}
+
+/// Lists the fixes the database needs
+pub fn plan(db: &CommandDatabase) -> Vec<Fix> {
+    let mut fixes = Vec::new();
+
+    let max_id = db.all_entries().map(|e| e.id).max().unwrap_or(0);
+    if db.next_id() <= max_id {
+        fixes.push(Fix::RaiseNextId {
+            from: db.next_id(),
+            to: max_id + 1,
+        });
+    }
+
+    // Later copies of an ID get new ones, after every ID in use
+    let mut next_id = db.next_id().max(max_id + 1);
+    let mut seen = HashSet::new();
+    for (index, entry) in db.all_entries().enumerate() {
+        if !seen.insert(entry.id) {
+            fixes.push(Fix::Renumber {
+                index,
+                command: entry.command.clone(),
+                from: entry.id,
+                to: next_id,
+            });
+            next_id += 1;
+        }
+    }
+
+    for (index, entry) in db.entries.iter().enumerate() {
+        if entry.command.trim().is_empty() {
+            fixes.push(Fix::TrashEmpty {
+                index,
+                id: entry.id,
+            });
+        }
+    }
+
+    for (index, entry) in db.all_entries().enumerate() {
+        let tidy = tidy_tags(&entry.tags);
+        if tidy != entry.tags {
+            fixes.push(Fix::TrimTags {
+                index,
+                id: entry.id,
+                from: entry.tags.clone(),
+                to: tidy,
+            });
+        }
+    }
+
+    fixes
+}
+
+/// Makes the given fixes, which must come from [`plan`] on the same
+/// database
+pub fn apply(db: &mut CommandDatabase, fixes: &[Fix]) {
+    for fix in fixes {
+        match fix {
+            Fix::RaiseNextId { to, .. } => db.set_next_id(db.next_id().max(*to)),
+            Fix::Renumber { index, to, .. } => {
+                entry_mut(db, *index).id = *to;
+                db.set_next_id(db.next_id().max(to + 1));
+            }
+            Fix::TrashEmpty { index, .. } => {
+                let entry = &mut db.entries[*index];
+                entry.deleted_at = Some(Utc::now());
+                entry.touch();
+            }
+            Fix::TrimTags { index, to, .. } => {
+                let entry = entry_mut(db, *index);
+                entry.tags = to.clone();
+                entry.touch();
+            }
+        }
+    }
+
+    // Move the emptied entries only now, so the positions above stay valid
+    let (trashed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut db.entries)
+        .into_iter()
+        .partition(|e| e.deleted_at.is_some());
+    db.entries = kept;
+    for entry in trashed {
+        db.restore(entry);
+    }
+}
+
+/// Returns the entry at a position in the list followed by the trash
+fn entry_mut(db: &mut CommandDatabase, index: usize) -> &mut CommandEntry {
+    let active = db.entries.len();
+    if index < active {
+        &mut db.entries[index]
+    } else {
+        &mut db.trash[index - active]
+    }
+}
+
+/// Trims tags and drops empty and repeated ones
+fn tidy_tags(tags: &[String]) -> Vec<String> {
+    let mut tidy: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !tidy.iter().any(|t| t == tag) {
+            tidy.push(tag.to_string());
+        }
+    }
+    tidy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_and_apply() {
+        let mut db = CommandDatabase::new();
+        db.add("ls".to_string(), String::new(), vec![" ops ".to_string()]);
+        db.add("  ".to_string(), String::new(), vec![]);
+        db.add("make".to_string(), String::new(), vec![]);
+        db.entries[2].id = 1;
+        // An empty command already in the trash is left alone
+        db.trash_by_id(2);
+        db.set_next_id(2);
+        db.entries[1]
+            .tags
+            .extend(["ci".to_string(), "".to_string(), "ci ".to_string()]);
+        db.entries
+            .push(CommandEntry::new(7, String::new(), String::new(), vec![]));
+
+        let fixes = plan(&db);
+        assert_eq!(
+            fixes,
+            vec![
+                Fix::RaiseNextId { from: 2, to: 8 },
+                Fix::Renumber {
+                    index: 1,
+                    command: "make".to_string(),
+                    from: 1,
+                    to: 8
+                },
+                Fix::TrashEmpty { index: 2, id: 7 },
+                Fix::TrimTags {
+                    index: 0,
+                    id: 1,
+                    from: vec![" ops ".to_string()],
+                    to: vec!["ops".to_string()]
+                },
+                Fix::TrimTags {
+                    index: 1,
+                    id: 1,
+                    from: vec!["ci".to_string(), "".to_string(), "ci ".to_string()],
+                    to: vec!["ci".to_string()]
+                },
+            ]
+        );
+        // Declined fixes stay; the rest leave the database consistent
+        apply(&mut db, &fixes[1..]);
+        let ids: Vec<u64> = db.entries.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![1, 8]);
+        assert_eq!(db.next_id(), 9);
+        assert_eq!(db.entries[1].tags, vec!["ci"]);
+        let trashed: Vec<u64> = db.trash.iter().map(|e| e.id).collect();
+        assert_eq!(trashed, vec![2, 7]);
+        assert!(plan(&db).is_empty());
+    }
+}
@@ -24,9 +24,74 @@ pub enum CmdKeeperError {
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// The database was written by a newer version of cmd-keeper
+    #[error(
+        "Database schema version {found} is newer than this cmd-keeper supports \
+         (up to {supported}). Please upgrade cmd-keeper."
+    )]
+    UnsupportedSchemaVersion { found: u64, supported: u32 },
+
+    /// The database could not be upgraded to the current schema
+    #[error("Failed to migrate database from schema version {from}: {reason}")]
+    Migration { from: u32, reason: String },
+
+    /// SQLite error
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
     /// Clipboard error
     #[error("Clipboard error: {0}")]
     Clipboard(String),
+
+    /// A template placeholder has neither a value nor a default
+    #[error("No value given for placeholder '{0}'")]
+    MissingPlaceholder(String),
+
+    /// The shell specification could not be parsed
+    #[error("Invalid shell: {0}")]
+    InvalidShell(String),
+
+    /// A file could not be read or written in an import/export format
+    #[error("{format} error: {reason}")]
+    Format { format: String, reason: String },
+
+    /// A project command was addressed but no project file was found
+    #[error(
+        "No project file (.cmd-keeper.json or .cmd-keeper.toml) found in this directory or its parents"
+    )]
+    NoProjectFile,
+
+    /// A profile name that can't be used as a directory name
+    #[error("Invalid profile name '{0}': use a non-empty name without slashes")]
+    InvalidProfile(String),
+
+    /// An entry changed since the journal record being undone or redone
+    #[error("Command {0} has changed since; not overwriting it")]
+    ChangedSince(u64),
+
+    /// No backup has the given timestamp
+    #[error("No backup matches '{0}'; see `cmd-keeper backup list`")]
+    BackupNotFound(String),
+
+    /// More than one backup starts with the given timestamp
+    #[error("'{0}' matches {1} backups; give more of the timestamp")]
+    AmbiguousBackup(String, usize),
+
+    /// The database file can't be parsed
+    #[error(
+        "Could not read the database {path}: {reason}\n\
+         Run `cmd-keeper doctor` to salvage what can be read, or restore a backup with \
+         `cmd-keeper backup list` and `cmd-keeper backup restore <timestamp>`."
+    )]
+    CorruptDatabase { path: String, reason: String },
+
+    /// A git command run for `sync` failed
+    #[error("git {command} failed: {reason}")]
+    Git { command: String, reason: String },
+
+    /// The shell could not be started
+    #[error("Failed to start shell '{0}': {1}")]
+    ShellSpawn(String, std::io::Error),
 }
 
 /// Result type alias for cmd-keeper operations
@@ -0,0 +1,135 @@
+//! Command execution through the user's shell
+//!
+//! Saved commands are handed to a shell as a single string (`$SHELL -c
+//! "<command>"`) so that pipes, redirects, `&&`, globs and environment
+//! assignments behave exactly as they would when typed at a prompt.
+
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+
+use crate::error::{CmdKeeperError, Result};
+
+/// Shell used when neither the entry nor `$SHELL` names one
+#[cfg(unix)]
+const FALLBACK_SHELL: &str = "sh";
+#[cfg(windows)]
+const FALLBACK_SHELL: &str = "cmd";
+
+/// Picks the shell to run a command with
+///
+/// An explicit override wins over the entry's own shell, which wins over
+/// `$SHELL`. Falls back to `sh` (`cmd` on Windows).
+pub fn resolve_shell(override_shell: Option<&str>, entry_shell: Option<&str>) -> String {
+    override_shell
+        .or(entry_shell)
+        .map(str::to_string)
+        .or_else(|| std::env::var("SHELL").ok().filter(|s| !s.trim().is_empty()))
+        .unwrap_or_else(|| FALLBACK_SHELL.to_string())
+}
+
+/// Builds a process that runs `command` through `shell`
+///
+/// The shell may carry its own arguments (e.g. `bash -i` to load aliases from
+/// `~/.bashrc`); the flag that introduces the command string is chosen from
+/// the shell's name.
+pub fn build(shell: &str, command: &str) -> Result<Command> {
+    let mut words = shell_words::split(shell)
+        .map_err(|e| CmdKeeperError::InvalidShell(format!("{}: {}", shell, e)))?;
+    if words.is_empty() {
+        return Err(CmdKeeperError::InvalidShell(shell.to_string()));
+    }
+    let program = words.remove(0);
+
+    let mut process = Command::new(&program);
+    process.args(words).arg(command_flag(&program)).arg(command);
+    Ok(process)
+}
+
+/// Returns the flag a shell takes before the command string
+fn command_flag(program: &str) -> &'static str {
+    let name = Path::new(program)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(program)
+        .to_lowercase();
+
+    match name.as_str() {
+        "cmd" => "/C",
+        "powershell" | "pwsh" => "-Command",
+        _ => "-c",
+    }
+}
+
+/// Runs a command through a shell with inherited stdin/stdout/stderr
+///
+/// Returns the exit code of the shell. A process killed by a signal reports
+/// `128 + signal`, as shells do.
+pub fn run(shell: &str, command: &str) -> Result<i32> {
+    let status = build(shell, command)?
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .map_err(|e| CmdKeeperError::ShellSpawn(shell.to_string(), e))?;
+
+    Ok(exit_code(status))
+}
+
+/// Converts an exit status into a shell-style exit code
+fn exit_code(status: ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+
+    -1
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn output(shell: &str, command: &str) -> String {
+        let out = build(shell, command).unwrap().output().unwrap();
+        String::from_utf8(out.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_pipes_and_operators() {
+        assert_eq!(output("sh", "echo hello | tr a-z A-Z"), "HELLO\n");
+        assert_eq!(output("sh", "false || echo fallback"), "fallback\n");
+        assert_eq!(output("sh", "GREETING=hi; echo $GREETING"), "hi\n");
+    }
+
+    #[test]
+    fn test_exit_code_is_passed_through() {
+        let status = build("sh", "exit 3").unwrap().status().unwrap();
+        assert_eq!(exit_code(status), 3);
+    }
+
+    #[test]
+    fn test_shell_with_arguments() {
+        assert_eq!(output("sh -e", "echo ok"), "ok\n");
+    }
+
+    #[test]
+    fn test_resolve_shell_precedence() {
+        assert_eq!(resolve_shell(Some("zsh"), Some("fish")), "zsh");
+        assert_eq!(resolve_shell(None, Some("fish")), "fish");
+    }
+
+    #[test]
+    fn test_command_flag() {
+        assert_eq!(command_flag("/bin/bash"), "-c");
+        assert_eq!(command_flag("fish"), "-c");
+        assert_eq!(command_flag("cmd.exe"), "/C");
+        assert_eq!(command_flag("pwsh"), "-Command");
+    }
+}
@@ -0,0 +1,70 @@
+//! CSV format
+//!
+//! One row per entry with the columns `id`, `command`, `description`,
+//! `tags`, `created_at` and `shell`. Tags are comma-separated within their
+//! cell. Only the `command` column is required when importing.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::Record;
+
+/// A CSV row; tags are flattened into one cell
+#[derive(Serialize, Deserialize)]
+struct Row {
+    #[serde(default)]
+    id: Option<u64>,
+    command: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    shell: Option<String>,
+}
+
+/// Writes records as CSV with a header row
+pub fn write(records: &[Record]) -> Result<String, ::csv::Error> {
+    let mut writer = ::csv::Writer::from_writer(Vec::new());
+    for record in records {
+        writer.serialize(Row {
+            id: record.id,
+            command: record.command.clone(),
+            description: record.description.clone(),
+            tags: record.tags.join(", "),
+            created_at: record.created_at,
+            shell: record.shell.clone(),
+        })?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| ::csv::Error::from(e.into_error()))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Reads records from CSV with a header row
+pub fn read(content: &str) -> Result<Vec<Record>, ::csv::Error> {
+    let mut reader = ::csv::Reader::from_reader(content.as_bytes());
+    reader
+        .deserialize::<Row>()
+        .map(|row| {
+            let row = row?;
+            Ok(Record {
+                id: row.id,
+                command: row.command,
+                description: row.description,
+                tags: row
+                    .tags
+                    .split(',')
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty())
+                    .collect(),
+                created_at: row.created_at,
+                shell: row.shell.filter(|s| !s.is_empty()),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,88 @@
+//! Markdown cheat sheet (export only)
+//!
+//! Entries are grouped under a heading per tag, alphabetically, with
+//! untagged entries last. An entry with several tags appears in each group.
+
+use std::collections::BTreeMap;
+
+use crate::models::CommandEntry;
+
+/// Heading for entries without tags
+const UNTAGGED: &str = "Untagged";
+
+/// Writes entries as a Markdown cheat sheet
+pub fn write(entries: &[CommandEntry]) -> String {
+    let mut groups: BTreeMap<String, Vec<&CommandEntry>> = BTreeMap::new();
+    let mut untagged = Vec::new();
+    for entry in entries {
+        if entry.tags.is_empty() {
+            untagged.push(entry);
+        }
+        for tag in &entry.tags {
+            groups.entry(tag.clone()).or_default().push(entry);
+        }
+    }
+
+    let mut out = String::from("# Command Cheat Sheet\n");
+    let sections = groups
+        .iter()
+        .map(|(tag, entries)| (tag.as_str(), entries))
+        .chain((!untagged.is_empty()).then_some((UNTAGGED, &untagged)));
+
+    for (heading, entries) in sections {
+        out.push_str(&format!("\n## {}\n", heading));
+        for entry in entries {
+            let title = if entry.description.is_empty() {
+                format!("#{}", entry.id)
+            } else {
+                format!("{} (#{})", entry.description, entry.id)
+            };
+            let fence = fence_for(&entry.command);
+            out.push_str(&format!(
+                "\n**{}**\n\n{}sh\n{}\n{}\n",
+                title, fence, entry.command, fence
+            ));
+        }
+    }
+
+    out
+}
+
+/// Returns a code fence longer than any backtick run in the text
+fn fence_for(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups_by_tag() {
+        let entries = vec![
+            CommandEntry::new(1, "ls".to_string(), String::new(), vec![]),
+            CommandEntry::new(
+                2,
+                "docker ps".to_string(),
+                "Containers".to_string(),
+                vec!["ops".to_string(), "docker".to_string()],
+            ),
+            CommandEntry::new(
+                3,
+                "echo ```".to_string(),
+                "Fence".to_string(),
+                vec!["docker".to_string()],
+            ),
+        ];
+
+        let md = write(&entries);
+        let docker = md.find("## docker").unwrap();
+        let ops = md.find("## ops").unwrap();
+        let untagged = md.find("## Untagged").unwrap();
+        assert!(docker < ops && ops < untagged);
+        assert_eq!(md.matches("**Containers (#2)**").count(), 2);
+        assert!(md.contains("````sh\necho ```\n````"));
+        assert!(md.contains("**#1**"));
+    }
+}
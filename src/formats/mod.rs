@@ -0,0 +1,366 @@
+//! Import and export formats
+//!
+//! Entries are exchanged as [`Record`]s, which leave out usage statistics
+//! and make everything but the command optional, so hand-written files only
+//! need a `command` per entry. [`merge`] adds imported records to a
+//! database, resolving ID conflicts and duplicates.
+
+mod csv;
+mod markdown;
+mod navi;
+mod pet;
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CmdKeeperError, Result};
+use crate::models::{CommandDatabase, CommandEntry};
+
+/// File formats for import and export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+    Csv,
+    /// Markdown cheat sheet grouped by tag (export only)
+    Md,
+    /// navi cheatsheet
+    Navi,
+    /// pet snippet file
+    Pet,
+}
+
+impl Format {
+    /// Guesses the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            "csv" => Some(Self::Csv),
+            "md" | "markdown" => Some(Self::Md),
+            "cheat" => Some(Self::Navi),
+            _ => None,
+        }
+    }
+
+    /// Name used in error messages
+    fn name(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::Yaml => "YAML",
+            Self::Toml => "TOML",
+            Self::Csv => "CSV",
+            Self::Md => "Markdown",
+            Self::Navi => "navi",
+            Self::Pet => "pet",
+        }
+    }
+
+    /// Builds a format error
+    fn error(self, reason: impl ToString) -> CmdKeeperError {
+        CmdKeeperError::Format {
+            format: self.name().to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// An entry as it appears in an exported file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    pub command: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+}
+
+impl From<&CommandEntry> for Record {
+    fn from(entry: &CommandEntry) -> Self {
+        Self {
+            id: Some(entry.id),
+            command: entry.command.clone(),
+            description: entry.description.clone(),
+            tags: entry.tags.clone(),
+            created_at: Some(entry.created_at),
+            shell: entry.shell.clone(),
+        }
+    }
+}
+
+/// Top level of JSON, YAML and TOML files
+#[derive(Serialize, Deserialize)]
+struct Document {
+    entries: Vec<Record>,
+}
+
+/// Writes entries in the given format
+pub fn export(entries: &[CommandEntry], format: Format) -> Result<String> {
+    let doc = Document {
+        entries: entries.iter().map(Record::from).collect(),
+    };
+
+    match format {
+        Format::Json => serde_json::to_string_pretty(&doc)
+            .map(|s| s + "\n")
+            .map_err(|e| format.error(e)),
+        Format::Yaml => serde_yaml::to_string(&doc).map_err(|e| format.error(e)),
+        Format::Toml => toml::to_string_pretty(&doc).map_err(|e| format.error(e)),
+        Format::Csv => csv::write(&doc.entries).map_err(|e| format.error(e)),
+        Format::Md => Ok(markdown::write(entries)),
+        Format::Navi => Ok(navi::write(&doc.entries)),
+        Format::Pet => pet::write(&doc.entries).map_err(|e| format.error(e)),
+    }
+}
+
+/// Reads records from a file's content
+pub fn import(content: &str, format: Format) -> Result<Vec<Record>> {
+    let doc: Document = match format {
+        Format::Json => serde_json::from_str(content).map_err(|e| format.error(e))?,
+        Format::Yaml => serde_yaml::from_str(content).map_err(|e| format.error(e))?,
+        Format::Toml => toml::from_str(content).map_err(|e| format.error(e))?,
+        Format::Csv => Document {
+            entries: csv::read(content).map_err(|e| format.error(e))?,
+        },
+        Format::Navi => return Ok(navi::read(content)),
+        Format::Pet => return pet::read(content).map_err(|e| format.error(e)),
+        Format::Md => {
+            return Err(format.error("Markdown cheat sheets can be exported but not imported"))
+        }
+    };
+    Ok(doc.entries)
+}
+
+/// How to treat imported records that clash with existing entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Conflict {
+    /// Leave existing entries alone and drop the clashing record
+    #[default]
+    Skip,
+    /// Replace the existing entry with the imported record
+    Overwrite,
+    /// Import every record, giving clashing ones a new ID
+    Renumber,
+}
+
+/// What [`merge`] did
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub renumbered: usize,
+    pub skipped: usize,
+}
+
+/// Adds imported records to a database
+///
+/// A record clashes with an existing entry if it has the same command (a
+/// duplicate) or, failing that, the same ID. Records keep their ID when it
+/// is free and get a new one otherwise; IDs in the trash are not free.
+pub fn merge(db: &mut CommandDatabase, records: Vec<Record>, conflict: Conflict) -> MergeSummary {
+    let mut summary = MergeSummary::default();
+
+    for record in records {
+        let duplicate = db
+            .entries
+            .iter()
+            .position(|e| e.command.trim() == record.command.trim());
+        let same_id = record
+            .id
+            .and_then(|id| db.entries.iter().position(|e| e.id == id));
+
+        let id_free = |db: &CommandDatabase, id: u64| db.all_entries().all(|e| e.id != id);
+
+        match (duplicate.or(same_id), conflict) {
+            (None, _) => {
+                let id = match record.id {
+                    Some(id) if id_free(db, id) => id,
+                    Some(_) => {
+                        summary.renumbered += 1;
+                        db.next_id()
+                    }
+                    None => db.next_id(),
+                };
+                db.insert(to_entry(record, id));
+                summary.added += 1;
+            }
+            (Some(_), Conflict::Skip) => summary.skipped += 1,
+            (Some(index), Conflict::Overwrite) => {
+                let existing = &mut db.entries[index];
+                let mut entry = to_entry(record, existing.id);
+                entry.usage = std::mem::take(&mut existing.usage);
+                *existing = entry;
+                summary.updated += 1;
+            }
+            (Some(_), Conflict::Renumber) => {
+                let id = match record.id {
+                    Some(id) if id_free(db, id) => id,
+                    _ => {
+                        summary.renumbered += 1;
+                        db.next_id()
+                    }
+                };
+                db.insert(to_entry(record, id));
+                summary.added += 1;
+            }
+        }
+    }
+
+    summary
+}
+
+/// Turns a record into an entry with the given ID
+fn to_entry(record: Record, id: u64) -> CommandEntry {
+    let mut entry = CommandEntry::new(id, record.command, record.description, record.tags);
+    if let Some(created_at) = record.created_at {
+        entry.created_at = created_at;
+    }
+    entry.shell = record.shell;
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_db() -> CommandDatabase {
+        let mut db = CommandDatabase::new();
+        db.add(
+            "git status".to_string(),
+            "Status".to_string(),
+            vec!["git".to_string()],
+        );
+        db.add(
+            "docker ps -a".to_string(),
+            "All containers, \"even\" stopped".to_string(),
+            vec!["docker".to_string(), "ops".to_string()],
+        );
+        db.find_by_id_mut(2).unwrap().shell = Some("bash -i".to_string());
+        db
+    }
+
+    fn record(id: Option<u64>, command: &str) -> Record {
+        Record {
+            id,
+            command: command.to_string(),
+            description: "imported".to_string(),
+            tags: vec![],
+            created_at: None,
+            shell: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let db = sample_db();
+        let expected: Vec<Record> = db.entries.iter().map(Record::from).collect();
+
+        for format in [Format::Json, Format::Yaml, Format::Toml, Format::Csv] {
+            let text = export(&db.entries, format).unwrap();
+            assert_eq!(import(&text, format).unwrap(), expected, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_minimal_records() {
+        let records = import("entries:\n  - command: ls -la\n", Format::Yaml).unwrap();
+        assert_eq!(records[0].command, "ls -la");
+        assert_eq!(records[0].id, None);
+
+        let records = import("command\nmake test\n", Format::Csv).unwrap();
+        assert_eq!(records[0].command, "make test");
+        assert!(records[0].tags.is_empty());
+
+        assert!(import("", Format::Md).is_err());
+    }
+
+    #[test]
+    fn test_merge_skip() {
+        let mut db = sample_db();
+        let summary = merge(
+            &mut db,
+            vec![
+                record(Some(1), "git log"),
+                record(None, "docker ps -a"),
+                record(Some(7), "make"),
+            ],
+            Conflict::Skip,
+        );
+
+        assert_eq!(summary.added, 1);
+        assert_eq!(summary.skipped, 2);
+        assert_eq!(db.find_by_id(7).unwrap().command, "make");
+        // New IDs continue after imported ones
+        assert_eq!(db.add("ls".to_string(), String::new(), vec![]), 8);
+    }
+
+    #[test]
+    fn test_merge_overwrite() {
+        let mut db = sample_db();
+        db.record_use(2, Some(0));
+        let summary = merge(
+            &mut db,
+            vec![record(None, "docker ps -a"), record(Some(1), "git log")],
+            Conflict::Overwrite,
+        );
+
+        assert_eq!(summary.updated, 2);
+        let docker = db.find_by_id(2).unwrap();
+        assert_eq!(docker.description, "imported");
+        assert_eq!(docker.usage.use_count, 1);
+        assert_eq!(db.find_by_id(1).unwrap().command, "git log");
+        assert_eq!(db.entries.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_renumber() {
+        let mut db = sample_db();
+        let summary = merge(
+            &mut db,
+            vec![record(Some(1), "git log"), record(Some(2), "docker ps -a")],
+            Conflict::Renumber,
+        );
+
+        assert_eq!(summary.added, 2);
+        assert_eq!(summary.renumbered, 2);
+        let ids: Vec<u64> = db.entries.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_merge_keeps_trashed_ids() {
+        let mut db = sample_db();
+        db.trash_by_id(2);
+        let summary = merge(&mut db, vec![record(Some(2), "htop")], Conflict::Skip);
+
+        assert_eq!(summary.renumbered, 1);
+        assert_eq!(db.find_by_id(3).unwrap().command, "htop");
+        // The trashed command comes back under its own ID
+        assert_eq!(db.restore_from_trash(2), Some(2));
+        let ids: Vec<u64> = db.entries.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(Format::from_path(Path::new("a.yml")), Some(Format::Yaml));
+        assert_eq!(Format::from_path(Path::new("a.MD")), Some(Format::Md));
+        assert_eq!(
+            Format::from_path(Path::new("git.cheat")),
+            Some(Format::Navi)
+        );
+        assert_eq!(Format::from_path(Path::new("a.txt")), None);
+    }
+}
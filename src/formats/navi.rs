@@ -0,0 +1,229 @@
+//! navi cheatsheets (`.cheat` files)
+//!
+//! A cheatsheet is split into sections by `% tag, tag` lines. Within a
+//! section, a `# description` line is followed by the command, with
+//! variables written as `<name>`. `$ name: ...` lines tell navi how to
+//! suggest values; those of the form `echo value` become placeholder
+//! defaults, others are ignored on import.
+
+use std::collections::HashMap;
+
+use super::Record;
+use crate::template;
+
+/// Writes records as a cheatsheet with one section per set of tags
+///
+/// Untagged records come first, before any `%` line.
+pub fn write(records: &[Record]) -> String {
+    let mut sections: Vec<(&[String], Vec<&Record>)> = Vec::new();
+    for record in records {
+        match sections.iter_mut().find(|(tags, _)| *tags == record.tags) {
+            Some((_, members)) => members.push(record),
+            None => sections.push((&record.tags, vec![record])),
+        }
+    }
+    sections.sort_by_key(|(tags, _)| !tags.is_empty());
+
+    let mut out = String::new();
+    for (tags, records) in sections {
+        if !tags.is_empty() {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("% {}\n", tags.join(", ")));
+        }
+
+        for record in records {
+            let mut defaults = Vec::new();
+            let command = template::map_placeholders(&record.command, |p| {
+                if let Some(default) = &p.default {
+                    defaults.push(format!("$ {}: echo {}\n", p.name, shell_quote(default)));
+                }
+                format!("<{}>", p.name)
+            });
+
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            if !record.description.is_empty() {
+                out.push_str(&format!("# {}\n", record.description));
+            }
+            out.push_str(&command);
+            out.push('\n');
+            for line in defaults {
+                out.push_str(&line);
+            }
+        }
+    }
+
+    out
+}
+
+/// Reads the commands of a cheatsheet
+pub fn read(content: &str) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut section = Section::default();
+    let mut description = String::new();
+    let mut command: Vec<&str> = Vec::new();
+
+    for line in content.lines() {
+        let is_command = !line.trim().is_empty() && !line.starts_with(['%', '#', '$', ';', '@']);
+        if is_command {
+            command.push(line);
+            continue;
+        }
+
+        if !command.is_empty() {
+            section
+                .commands
+                .push((std::mem::take(&mut description), command.join("\n")));
+            command.clear();
+        }
+
+        if let Some(tags) = line.strip_prefix('%') {
+            records.extend(section.finish());
+            section.tags = tags
+                .split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect();
+        } else if let Some(text) = line.strip_prefix('#') {
+            description = text.trim().to_string();
+        } else if let Some(variable) = line.strip_prefix('$') {
+            if let Some((name, value)) = variable.split_once(':') {
+                if let Some(value) = echoed_value(value) {
+                    section.defaults.insert(name.trim().to_string(), value);
+                }
+            }
+        }
+    }
+
+    if !command.is_empty() {
+        section.commands.push((description, command.join("\n")));
+    }
+    records.extend(section.finish());
+    records
+}
+
+/// Commands and variables collected under one `%` line
+#[derive(Default)]
+struct Section {
+    tags: Vec<String>,
+    /// Description and command of each entry, in navi's syntax
+    commands: Vec<(String, String)>,
+    defaults: HashMap<String, String>,
+}
+
+impl Section {
+    /// Turns the section's commands into records, applying the defaults
+    fn finish(&mut self) -> Vec<Record> {
+        let defaults = std::mem::take(&mut self.defaults);
+        std::mem::take(&mut self.commands)
+            .into_iter()
+            .map(|(description, command)| {
+                let mut command = command;
+                for (name, value) in &defaults {
+                    command =
+                        command.replace(&format!("<{}>", name), &format!("<{}={}>", name, value));
+                }
+                Record {
+                    id: None,
+                    command: template::from_angle_brackets(&command),
+                    description,
+                    tags: self.tags.clone(),
+                    created_at: None,
+                    shell: None,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Returns the value printed by `echo value` or `echo 'value'`, if that is
+/// all the variable's command does
+fn echoed_value(command: &str) -> Option<String> {
+    // navi options follow the command after `---`
+    let command = command.split(" --- ").next()?.trim();
+    let value = command.strip_prefix("echo ")?.trim();
+
+    if let Some(quoted) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        Some(quoted.replace("'\\''", "'"))
+    } else if let Some(quoted) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(quoted.to_string())
+    } else if value.contains(['|', ';', '$', '`', '&', '(']) {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// Wraps a value in single quotes for the shell
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_cheatsheet() {
+        let content = "\
+; comment
+% git, code
+
+# Change branch
+git checkout <branch>
+
+$ branch: git branch | awk '{print $NF}'
+
+# Clone into a directory
+git clone <url> \\
+  <dir>
+$ dir: echo 'my repo'
+
+% docker
+@ git
+# List containers
+docker ps
+";
+        let records = read(content);
+        let commands: Vec<&str> = records.iter().map(|r| r.command.as_str()).collect();
+        assert_eq!(
+            commands,
+            vec![
+                "git checkout {{branch}}",
+                "git clone {{url}} \\\n  {{dir=my repo}}",
+                "docker ps",
+            ]
+        );
+        assert_eq!(records[0].description, "Change branch");
+        assert_eq!(records[1].tags, vec!["git", "code"]);
+        assert_eq!(records[2].tags, vec!["docker"]);
+    }
+
+    #[test]
+    fn test_write_cheatsheet() {
+        let record = |command: &str, tags: &[&str]| Record {
+            id: Some(1),
+            command: command.to_string(),
+            description: "Desc".to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            created_at: None,
+            shell: None,
+        };
+        let records = vec![
+            record("ssh {{user=it's me}}@{{host}}", &["net"]),
+            record("ls", &[]),
+        ];
+
+        let text = write(&records);
+        assert_eq!(
+            text,
+            "# Desc\nls\n\n% net\n\n# Desc\nssh <user>@<host>\n$ user: echo 'it'\\''s me'\n"
+        );
+
+        let commands: Vec<String> = read(&text).into_iter().map(|r| r.command).collect();
+        assert_eq!(commands, vec!["ls", "ssh {{user=it's me}}@{{host}}"]);
+    }
+}
@@ -0,0 +1,94 @@
+//! pet snippet files (`snippet.toml`)
+//!
+//! Each `[[snippets]]` table has a `description`, a `command` and a `tag`
+//! list. pet writes variables as `<name>` or `<name=default>`; the
+//! `output` field pet keeps for a sample output is ignored.
+
+use serde::{Deserialize, Serialize};
+
+use super::Record;
+use crate::template;
+
+#[derive(Serialize, Deserialize)]
+struct Snippets {
+    #[serde(default)]
+    snippets: Vec<Snippet>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snippet {
+    #[serde(default)]
+    description: String,
+    command: String,
+    #[serde(default)]
+    tag: Vec<String>,
+}
+
+/// Writes records as a pet snippet file
+pub fn write(records: &[Record]) -> Result<String, toml::ser::Error> {
+    let snippets = records
+        .iter()
+        .map(|record| Snippet {
+            description: record.description.clone(),
+            command: template::map_placeholders(&record.command, |p| match &p.default {
+                Some(default) => format!("<{}={}>", p.name, default),
+                None => format!("<{}>", p.name),
+            }),
+            tag: record.tags.clone(),
+        })
+        .collect();
+    toml::to_string_pretty(&Snippets { snippets })
+}
+
+/// Reads the snippets of a pet snippet file
+pub fn read(content: &str) -> Result<Vec<Record>, toml::de::Error> {
+    let file: Snippets = toml::from_str(content)?;
+    Ok(file
+        .snippets
+        .into_iter()
+        .map(|snippet| Record {
+            id: None,
+            command: template::from_angle_brackets(&snippet.command),
+            description: snippet.description,
+            tags: snippet.tag,
+            created_at: None,
+            shell: None,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let content = r#"
+[[snippets]]
+  description = "Connect"
+  command = "ssh <user=root>@<host> < /dev/null"
+  tag = ["net", "ssh"]
+  output = ""
+
+[[snippets]]
+  command = "echo <greeting=|_hello_||_hi_|>"
+"#;
+        let records = read(content).unwrap();
+        assert_eq!(records[0].command, "ssh {{user=root}}@{{host}} < /dev/null");
+        assert_eq!(records[0].tags, vec!["net", "ssh"]);
+        assert_eq!(records[1].command, "echo {{greeting=hello}}");
+        assert_eq!(records[1].description, "");
+
+        let written = write(&records).unwrap();
+        assert!(written.contains("ssh <user=root>@<host> < /dev/null"));
+        let again: Vec<String> = read(&written)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.command)
+            .collect();
+        assert_eq!(
+            again,
+            vec![records[0].command.clone(), records[1].command.clone()]
+        );
+    }
+}
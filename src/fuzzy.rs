@@ -0,0 +1,115 @@
+//! Fuzzy matching and ranking of command entries
+//!
+//! A term matches an entry if it appears as a subsequence of the command,
+//! the description or a tag (so `gco` finds `git checkout`). Each field has
+//! a weight so that a hit in the command counts more than one in the
+//! description, which counts more than one in a tag. Combining terms and
+//! ranking entries is done by [`crate::query`].
+
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+
+use crate::models::CommandEntry;
+
+/// Weight of a match in the command
+const COMMAND_WEIGHT: i64 = 3;
+
+/// Weight of a match in the description
+const DESCRIPTION_WEIGHT: i64 = 2;
+
+/// Weight of a match in a tag
+const TAG_WEIGHT: i64 = 1;
+
+/// An entry matching a query, with its score
+#[derive(Debug, Clone)]
+pub struct SearchHit<'a> {
+    /// The matching entry
+    pub entry: &'a CommandEntry,
+    /// Relevance; higher is better
+    pub score: i64,
+    /// Character positions in the command that matched, sorted
+    pub command_indices: Vec<usize>,
+}
+
+/// Scores entries against queries
+pub struct Matcher {
+    inner: SkimMatcherV2,
+}
+
+impl Default for Matcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Matcher {
+    /// Creates a case-insensitive matcher
+    pub fn new() -> Self {
+        Self {
+            inner: SkimMatcherV2::default().ignore_case(),
+        }
+    }
+
+    /// Scores a single term against an entry
+    ///
+    /// Returns the best weighted field score and the matched positions in
+    /// the command, or None if no field matches.
+    pub fn score_term(&self, entry: &CommandEntry, term: &str) -> Option<(i64, Vec<usize>)> {
+        let command = self.inner.fuzzy_indices(&entry.command, term);
+        let description = self.inner.fuzzy_match(&entry.description, term);
+        let tag = entry
+            .tags
+            .iter()
+            .filter_map(|t| self.inner.fuzzy_match(t, term))
+            .max();
+
+        let best = [
+            command.as_ref().map(|(s, _)| s * COMMAND_WEIGHT),
+            description.map(|s| s * DESCRIPTION_WEIGHT),
+            tag.map(|s| s * TAG_WEIGHT),
+        ]
+        .into_iter()
+        .flatten()
+        .max()?;
+
+        Some((best, command.map(|(_, i)| i).unwrap_or_default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u64, command: &str, description: &str, tags: &[&str]) -> CommandEntry {
+        CommandEntry::new(
+            id,
+            command.to_string(),
+            description.to_string(),
+            tags.iter().map(|t| t.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_subsequence_match() {
+        let matcher = Matcher::new();
+        let e = entry(1, "git checkout main", "Switch branch", &[]);
+
+        let (score, indices) = matcher.score_term(&e, "gco").unwrap();
+        assert!(score > 0);
+        assert_eq!(indices.len(), 3);
+        assert!(matcher.score_term(&e, "xyz").is_none());
+    }
+
+    #[test]
+    fn test_fields_are_weighted() {
+        let matcher = Matcher::new();
+        let in_command = entry(1, "kubectl get pods", "List", &[]);
+        let in_description = entry(2, "ls -la", "Show pods", &[]);
+        let in_tag = entry(3, "echo", "Nothing", &["pods"]);
+
+        let score = |e: &CommandEntry| matcher.score_term(e, "pods").unwrap().0;
+        assert!(score(&in_command) > score(&in_description));
+        assert!(score(&in_description) > score(&in_tag));
+        assert!(matcher.score_term(&in_tag, "pods").unwrap().1.is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+//! Shell history parsing
+//!
+//! Reads the history files of bash, zsh and fish into plain command strings,
+//! oldest first. [`dedupe`] then keeps one copy of each command, most recent
+//! first, ready to be offered for import.
+
+use std::collections::HashSet;
+
+/// Byte zsh uses to escape ("metafy") special bytes in its history file
+const ZSH_META: u8 = 0x83;
+
+/// Parses `~/.bash_history`
+///
+/// Timestamp comments written when `HISTTIMEFORMAT` is set are skipped.
+pub fn parse_bash(content: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(content)
+        .lines()
+        .filter(|line| !is_bash_timestamp(line))
+        .map(str::to_string)
+        .filter(|line| !line.trim().is_empty())
+        .collect()
+}
+
+/// Returns true for the `#1700000000` lines bash writes before entries
+fn is_bash_timestamp(line: &str) -> bool {
+    line.strip_prefix('#')
+        .is_some_and(|ts| !ts.is_empty() && ts.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Parses a zsh history file, in the plain or the extended
+/// (`: <timestamp>:<duration>;<command>`) format
+///
+/// Lines ending in a backslash continue the entry on the next line.
+pub fn parse_zsh(content: &[u8]) -> Vec<String> {
+    let content = unmetafy(content);
+    let text = String::from_utf8_lossy(&content);
+
+    let mut commands = Vec::new();
+    let mut current: Option<String> = None;
+
+    for line in text.lines() {
+        let line = match current.take() {
+            Some(mut pending) => {
+                pending.push('\n');
+                pending.push_str(line);
+                pending
+            }
+            None => strip_zsh_header(line).to_string(),
+        };
+
+        match line.strip_suffix('\\') {
+            Some(continued) => current = Some(continued.to_string()),
+            None => commands.push(line),
+        }
+    }
+    commands.extend(current);
+
+    commands.retain(|c| !c.trim().is_empty());
+    commands
+}
+
+/// Removes the `: <timestamp>:<duration>;` prefix of an extended history line
+fn strip_zsh_header(line: &str) -> &str {
+    let Some(rest) = line.strip_prefix(": ") else {
+        return line;
+    };
+    let Some((header, command)) = rest.split_once(';') else {
+        return line;
+    };
+
+    let is_header = header
+        .split_once(':')
+        .is_some_and(|(ts, dur)| is_digits(ts) && is_digits(dur));
+    if is_header {
+        command
+    } else {
+        line
+    }
+}
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Reverses zsh's escaping of bytes >= 0x83 (each is written as 0x83
+/// followed by the byte XOR 0x20)
+fn unmetafy(content: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(content.len());
+    let mut bytes = content.iter();
+    while let Some(&b) = bytes.next() {
+        if b == ZSH_META {
+            if let Some(&next) = bytes.next() {
+                out.push(next ^ 0x20);
+            }
+        } else {
+            out.push(b);
+        }
+    }
+    out
+}
+
+/// Parses fish's `fish_history`
+///
+/// The file is YAML-like: each entry starts with `- cmd: <command>`, with
+/// newlines and backslashes in the command escaped as `\n` and `\\`.
+pub fn parse_fish(content: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(content)
+        .lines()
+        .filter_map(|line| line.strip_prefix("- cmd: "))
+        .map(unescape_fish)
+        .filter(|c| !c.trim().is_empty())
+        .collect()
+}
+
+/// Undoes the escaping of a fish history command
+fn unescape_fish(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Removes duplicates, keeping the most recent use of each command, and
+/// returns the commands most recent first
+///
+/// Leading and trailing whitespace is ignored when comparing.
+pub fn dedupe(commands: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    commands
+        .into_iter()
+        .rev()
+        .map(|c| c.trim().to_string())
+        .filter(|c| seen.insert(c.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bash() {
+        let content = b"ls -la\n#1700000000\ngit status\n\n#not a timestamp\n";
+        assert_eq!(
+            parse_bash(content),
+            vec!["ls -la", "git status", "#not a timestamp"]
+        );
+    }
+
+    #[test]
+    fn test_parse_zsh_extended_and_multiline() {
+        let content = b": 1700000000:0;ls -la\n\
+                        : 1700000001:3;for f in *; do\\\n  echo $f\\\ndone\n\
+                        plain line\n\
+                        : not-a-header;x\n";
+        assert_eq!(
+            parse_zsh(content),
+            vec![
+                "ls -la",
+                "for f in *; do\n  echo $f\ndone",
+                "plain line",
+                ": not-a-header;x",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_zsh_unmetafies() {
+        // "é" is 0xC3 0xA9; zsh writes 0xA9 as 0x83 0x89
+        let content = b": 1700000000:0;echo caf\xc3\x83\x89\n";
+        assert_eq!(parse_zsh(content), vec!["echo café"]);
+    }
+
+    #[test]
+    fn test_parse_fish() {
+        let content = b"- cmd: echo hello\n  when: 1700000000\n\
+                        - cmd: printf 'a\\\\nb'\\necho done\n  when: 1700000001\n  paths:\n    - ./x\n";
+        assert_eq!(
+            parse_fish(content),
+            vec!["echo hello", "printf 'a\\nb'\necho done"]
+        );
+    }
+
+    #[test]
+    fn test_dedupe_keeps_most_recent_first() {
+        let commands = ["ls", "git status", "ls ", "make"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(dedupe(commands), vec!["make", "ls", "git status"]);
+    }
+}
@@ -7,20 +7,32 @@
 //! - Search commands by keyword
 //! - Delete commands by ID
 //! - Copy commands to clipboard
+//! - Run commands through the user's shell
+//! - Command templates with placeholders filled at copy/run time
 //! - Interactive TUI mode (lazygit-like interface)
 
 mod cli;
 mod commands;
+mod doctor;
 mod error;
+mod executor;
+mod formats;
+mod fuzzy;
+mod history;
 mod models;
+mod output_template;
+mod query;
 mod storage;
+mod sync;
+mod tags;
+mod template;
 mod tui;
 
 use anyhow::Result;
 use clap::Parser;
 use colored::Colorize;
 
-use cli::{Cli, Commands};
+use cli::{BackupAction, Cli, Commands, ImportSource, TagsAction, TrashAction};
 use storage::Storage;
 
 fn main() {
@@ -32,6 +44,10 @@ fn main() {
 
 fn run() -> Result<()> {
     let cli = Cli::parse();
+    storage::location::select(storage::location::Selection {
+        db: cli.db,
+        profile: cli.profile,
+    });
 
     // If no command specified, launch TUI mode
     let command = cli.command.unwrap_or(Commands::Tui);
@@ -45,42 +61,164 @@ fn run() -> Result<()> {
             command,
             description,
             tags,
+            shell,
+            local,
         } => {
-            commands::add(&command, &description, tags)?;
+            commands::add(&command, &description, tags, shell, local)?;
         }
 
-        Commands::List { full } => {
-            commands::list(full)?;
+        Commands::List {
+            full,
+            tag,
+            sort,
+            output,
+            format,
+        } => {
+            commands::list(full, tag.as_deref(), sort, output, format.as_ref())?;
+        }
+
+        Commands::Search {
+            keyword,
+            full,
+            exact,
+            sort,
+            output,
+            format,
+        } => {
+            commands::search(&keyword, full, exact, sort, output, format.as_ref())?;
         }
 
-        Commands::Search { keyword, full } => {
-            commands::search(&keyword, full)?;
+        Commands::Show { id, output } => {
+            commands::show(id, output)?;
         }
 
+        Commands::Stats {
+            unused_days,
+            top,
+            output,
+        } => {
+            commands::stats(unused_days, top, output)?;
+        }
+
+        Commands::Tags { action } => match action {
+            None => commands::tags_list()?,
+            Some(TagsAction::Rename { old, new }) => commands::tags_rename(&old, &new)?,
+            Some(TagsAction::Merge { sources, into }) => commands::tags_merge(&sources, &into)?,
+            Some(TagsAction::Rm { tag }) => commands::tags_remove(&tag)?,
+        },
+
+        Commands::Trash { action } => match action {
+            None | Some(TrashAction::List) => commands::trash_list()?,
+            Some(TrashAction::Restore { id }) => commands::trash_restore(id)?,
+            Some(TrashAction::Purge { older_than, force }) => {
+                commands::trash_purge(older_than, force)?
+            }
+        },
+
         Commands::Edit {
             id,
             command,
             description,
             tags,
+            shell,
         } => {
-            commands::edit(id, command, description, tags)?;
+            commands::edit(id, command, description, tags, shell)?;
         }
 
         Commands::Delete { id, force } => {
             commands::delete(id, force)?;
         }
 
-        Commands::Copy { id } => {
-            commands::copy(id)?;
+        Commands::Copy { id, set } => {
+            commands::copy(id, &set)?;
+        }
+
+        Commands::Run { id, set, shell } => {
+            let exit_code = commands::run(id, &set, shell.as_deref())?;
+            if exit_code != 0 {
+                std::process::exit(exit_code);
+            }
+        }
+
+        Commands::Pick { query, output } => {
+            if !commands::pick(query.as_deref(), output.as_deref())? {
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Init { shell } => {
+            commands::init(shell);
+        }
+
+        Commands::Export {
+            format,
+            output,
+            tag,
+        } => {
+            commands::export(format, output.as_deref(), tag.as_deref())?;
+        }
+
+        Commands::Import { source } => match source {
+            ImportSource::File {
+                path,
+                format,
+                on_conflict,
+            } => {
+                commands::import_file(&path, format, on_conflict)?;
+            }
+            ImportSource::History { shell, file } => {
+                commands::import_history(shell, file.as_deref())?;
+            }
+        },
+
+        Commands::Migrate { to } => {
+            commands::migrate(to)?;
+        }
+
+        Commands::History { id } => {
+            commands::history(id)?;
+        }
+
+        Commands::Undo => {
+            commands::undo()?;
+        }
+
+        Commands::Redo => {
+            commands::redo()?;
+        }
+
+        Commands::Sync { remote } => {
+            commands::sync(remote.as_deref())?;
+        }
+
+        Commands::Doctor { yes } => {
+            commands::doctor(yes)?;
         }
 
+        Commands::Backup { action } => match action {
+            None | Some(BackupAction::List) => commands::backup_list()?,
+            Some(BackupAction::Restore { timestamp, force }) => {
+                commands::backup_restore(&timestamp, force)?
+            }
+        },
+
         Commands::Path => {
+            let location = storage::location::current()?;
             let storage = Storage::new()?;
             println!(
                 "{} {}",
                 "Database path:".dimmed(),
                 storage.db_path().display().to_string().cyan()
             );
+            println!("{} {}", "Backend:".dimmed(), storage.kind());
+            println!("{} {}", "Chosen because:".dimmed(), location.origin);
+            if let Some(project) = storage.project() {
+                println!(
+                    "{} {}",
+                    "Project file:".dimmed(),
+                    project.path().display().to_string().cyan()
+                );
+            }
         }
     }
 
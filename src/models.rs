@@ -2,9 +2,24 @@
 //!
 //! This module defines the core data structures used throughout the application.
 
-use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Utc};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
+use crate::fuzzy::SearchHit;
+use crate::query::Query;
+use crate::tags;
+
+/// Version of the on-disk database format written by this build
+///
+/// Bump this together with a new step in `storage::migrations` whenever the
+/// format changes.
+pub const SCHEMA_VERSION: u32 = 6;
+
 /// Represents a single saved command entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommandEntry {
@@ -19,6 +34,120 @@ pub struct CommandEntry {
     pub tags: Vec<String>,
     /// Timestamp when the entry was created
     pub created_at: DateTime<Utc>,
+    /// Timestamp of the last edit or move to or from the trash, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Timestamp when the entry was moved to the trash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Shell to run the command with (defaults to `$SHELL`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    /// How often and how recently the command was copied or run
+    #[serde(default, skip_serializing_if = "Usage::is_unused")]
+    pub usage: Usage,
+    /// File the entry was loaded from; not stored
+    #[serde(skip)]
+    pub source: Source,
+}
+
+/// Where an entry is stored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// The global database
+    #[default]
+    Global,
+    /// The project file found from the current directory (see
+    /// `storage::project`)
+    Project,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Global => write!(f, "global"),
+            Self::Project => write!(f, "project"),
+        }
+    }
+}
+
+/// Identifies an entry across the global database and the project file
+///
+/// Written as the plain ID for global entries and with a `p` prefix for
+/// project entries, e.g. `3` and `p3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntryRef {
+    pub source: Source,
+    pub id: u64,
+}
+
+impl EntryRef {
+    /// Refers to an entry of the global database
+    pub fn global(id: u64) -> Self {
+        Self {
+            source: Source::Global,
+            id,
+        }
+    }
+}
+
+impl fmt::Display for EntryRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.source {
+            Source::Global => write!(f, "{}", self.id),
+            Source::Project => write!(f, "p{}", self.id),
+        }
+    }
+}
+
+impl FromStr for EntryRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (source, digits) = match s.strip_prefix(['p', 'P']) {
+            Some(rest) => (Source::Project, rest),
+            None => (Source::Global, s),
+        };
+        let id = digits
+            .parse()
+            .map_err(|_| format!("'{}' is not an ID (e.g. 3, or p3 for a project command)", s))?;
+        Ok(Self { source, id })
+    }
+}
+
+/// Usage statistics of an entry
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Number of times the command was copied or run
+    pub use_count: u64,
+    /// When the command was last copied or run
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Exit code of the last run (copying leaves it unchanged)
+    pub last_exit_code: Option<i32>,
+    /// Number of times the command was run
+    #[serde(default)]
+    pub run_count: u64,
+    /// Number of runs that exited with a non-zero code
+    #[serde(default)]
+    pub failure_count: u64,
+}
+
+impl Usage {
+    /// Returns true if the command has never been used
+    pub fn is_unused(&self) -> bool {
+        self.use_count == 0
+    }
+}
+
+/// Order in which entries are shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortOrder {
+    /// ID order for listings, relevance for searches
+    #[default]
+    Default,
+    /// Frequently and recently used commands first
+    Frecency,
 }
 
 impl CommandEntry {
@@ -30,9 +159,77 @@ impl CommandEntry {
             description,
             tags,
             created_at: Utc::now(),
+            updated_at: None,
+            deleted_at: None,
+            shell: None,
+            usage: Usage::default(),
+            source: Source::Global,
         }
     }
 
+    /// Returns how the entry is referred to, e.g. `p3`
+    pub fn reference(&self) -> EntryRef {
+        EntryRef {
+            source: self.source,
+            id: self.id,
+        }
+    }
+
+    /// Returns true if the command, description, tags and shell are the same
+    /// and both or neither are in the trash
+    pub fn same_content(&self, other: &CommandEntry) -> bool {
+        self.command == other.command
+            && self.description == other.description
+            && self.tags == other.tags
+            && self.shell == other.shell
+            && self.deleted_at.is_some() == other.deleted_at.is_some()
+    }
+
+    /// Returns when the command, description, tags or shell last changed
+    pub fn modified_at(&self) -> DateTime<Utc> {
+        self.updated_at.unwrap_or(self.created_at)
+    }
+
+    /// Marks the entry as edited now
+    pub fn touch(&mut self) {
+        self.updated_at = Some(Utc::now());
+    }
+
+    /// Counts a copy, or a run if an exit code is given
+    pub fn record_use(&mut self, exit_code: Option<i32>) {
+        self.usage.use_count += 1;
+        self.usage.last_used_at = Some(Utc::now());
+        if let Some(code) = exit_code {
+            self.usage.last_exit_code = Some(code);
+            self.usage.run_count += 1;
+            if code != 0 {
+                self.usage.failure_count += 1;
+            }
+        }
+    }
+
+    /// Scores how frequently and recently the command was used
+    ///
+    /// Like `z`/`zoxide`, the use count is weighted by how long ago the last
+    /// use was, so a command used daily beats one used a lot last year.
+    pub fn frecency(&self, now: DateTime<Utc>) -> f64 {
+        let Some(last_used) = self.usage.last_used_at else {
+            return 0.0;
+        };
+
+        let age = now - last_used;
+        let weight = if age < Duration::hours(1) {
+            4.0
+        } else if age < Duration::days(1) {
+            2.0
+        } else if age < Duration::weeks(1) {
+            0.5
+        } else {
+            0.25
+        };
+        self.usage.use_count as f64 * weight
+    }
+
     /// Returns a comma-separated string of tags
     pub fn tags_display(&self) -> String {
         if self.tags.is_empty() {
@@ -46,21 +243,38 @@ impl CommandEntry {
 /// The entire database of saved commands
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CommandDatabase {
+    /// Version of the file format
+    pub schema_version: u32,
     /// Counter for generating unique IDs
     next_id: u64,
     /// List of all saved command entries
     pub entries: Vec<CommandEntry>,
+    /// Deleted entries that can still be restored, oldest deletion first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trash: Vec<CommandEntry>,
 }
 
 impl CommandDatabase {
     /// Creates a new empty database
     pub fn new() -> Self {
         Self {
+            schema_version: SCHEMA_VERSION,
             next_id: 1,
             entries: Vec::new(),
+            trash: Vec::new(),
         }
     }
 
+    /// Returns the ID the next added entry will get
+    pub fn next_id(&self) -> u64 {
+        self.next_id
+    }
+
+    /// Sets the ID the next added entry will get
+    pub fn set_next_id(&mut self, next_id: u64) {
+        self.next_id = next_id;
+    }
+
     /// Adds a new command entry and returns its ID
     pub fn add(&mut self, command: String, description: String, tags: Vec<String>) -> u64 {
         let id = self.next_id;
@@ -70,11 +284,25 @@ impl CommandDatabase {
         id
     }
 
+    /// Adds an entry with its ID as-is
+    ///
+    /// The caller must make sure the ID is not taken. Later IDs continue
+    /// after it.
+    pub fn insert(&mut self, entry: CommandEntry) {
+        self.next_id = self.next_id.max(entry.id + 1);
+        self.entries.push(entry);
+    }
+
     /// Finds an entry by ID
     pub fn find_by_id(&self, id: u64) -> Option<&CommandEntry> {
         self.entries.iter().find(|e| e.id == id)
     }
 
+    /// Finds an entry by ID for modification
+    pub fn find_by_id_mut(&mut self, id: u64) -> Option<&mut CommandEntry> {
+        self.entries.iter_mut().find(|e| e.id == id)
+    }
+
     /// Removes an entry by ID, returns true if found and removed
     pub fn remove_by_id(&mut self, id: u64) -> bool {
         let original_len = self.entries.len();
@@ -82,6 +310,72 @@ impl CommandDatabase {
         self.entries.len() < original_len
     }
 
+    /// Moves an entry to the trash, returns true if found
+    pub fn trash_by_id(&mut self, id: u64) -> bool {
+        let Some(index) = self.entries.iter().position(|e| e.id == id) else {
+            return false;
+        };
+        let mut entry = self.entries.remove(index);
+        entry.deleted_at = Some(Utc::now());
+        entry.touch();
+        self.trash.push(entry);
+        true
+    }
+
+    /// Moves an entry out of the trash to its place in the list, returns
+    /// its ID or None if not found
+    ///
+    /// If a listed entry has taken the ID in the meantime, the restored
+    /// entry gets a new one.
+    pub fn restore_from_trash(&mut self, id: u64) -> Option<u64> {
+        let index = self.trash.iter().position(|e| e.id == id)?;
+        let mut entry = self.trash.remove(index);
+        if self.find_by_id(id).is_some() {
+            entry.id = self.next_id;
+        }
+        entry.deleted_at = None;
+        entry.touch();
+        let id = entry.id;
+        self.restore(entry);
+        Some(id)
+    }
+
+    /// Puts an entry back in the list or the trash, by its `deleted_at`,
+    /// keeping the list in ID order
+    pub fn restore(&mut self, entry: CommandEntry) {
+        self.next_id = self.next_id.max(entry.id + 1);
+        if entry.deleted_at.is_some() {
+            self.trash.push(entry);
+        } else {
+            let index = self.entries.partition_point(|e| e.id < entry.id);
+            self.entries.insert(index, entry);
+        }
+    }
+
+    /// Permanently removes the trashed entries deleted before `cutoff`, or
+    /// all of them, and returns their IDs
+    pub fn purge_trash(&mut self, cutoff: Option<DateTime<Utc>>) -> Vec<u64> {
+        let mut purged = Vec::new();
+        self.trash.retain(|e| {
+            let keep = matches!((cutoff, e.deleted_at), (Some(cutoff), Some(at)) if at >= cutoff);
+            if !keep {
+                purged.push(e.id);
+            }
+            keep
+        });
+        purged
+    }
+
+    /// Returns the entries in the list and in the trash
+    pub fn all_entries(&self) -> impl Iterator<Item = &CommandEntry> {
+        self.entries.iter().chain(&self.trash)
+    }
+
+    /// Returns the entries in the list and in the trash, for changing them
+    pub fn all_entries_mut(&mut self) -> impl Iterator<Item = &mut CommandEntry> {
+        self.entries.iter_mut().chain(&mut self.trash)
+    }
+
     /// Updates an existing entry by ID, returns true if found and updated
     pub fn update(
         &mut self,
@@ -100,14 +394,84 @@ impl CommandDatabase {
             if let Some(t) = tags {
                 entry.tags = t;
             }
+            entry.touch();
             true
         } else {
             false
         }
     }
 
-    /// Searches entries by keyword (searches in command and description)
-    pub fn search(&self, keyword: &str) -> Vec<&CommandEntry> {
+    /// Records that an entry was copied or run, returns true if found
+    ///
+    /// `exit_code` is the exit code of a run, or None for a copy.
+    pub fn record_use(&mut self, id: u64, exit_code: Option<i32>) -> bool {
+        let Some(entry) = self.find_by_id_mut(id) else {
+            return false;
+        };
+        entry.record_use(exit_code);
+        true
+    }
+
+    /// Returns every tag with the number of entries carrying it, by name
+    pub fn tag_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for tag in self.entries.iter().flat_map(|e| &e.tags) {
+            *counts.entry(tag).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .map(|(tag, count)| (tag.to_string(), count))
+            .collect()
+    }
+
+    /// Returns true if any entry carries the tag or one below it (see
+    /// [`crate::tags`])
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|e| e.tags.iter().any(|t| tags::matches(t, tag)))
+    }
+
+    /// Replaces the tags matching any of `from` with `to` on every entry, or
+    /// removes them if `to` is None
+    ///
+    /// Tags below a replaced one move along (`cloud/aws` renamed to `infra`
+    /// turns `cloud/aws/ec2` into `infra/ec2`) or are removed with it. An
+    /// entry ends up with each tag at most once. Entries in the trash are
+    /// changed too, so restoring one doesn't bring an old tag back. Returns
+    /// the number of entries changed.
+    pub fn replace_tags(&mut self, from: &[&str], to: Option<&str>) -> usize {
+        let mut changed = 0;
+        for entry in self.all_entries_mut() {
+            let mut tags: Vec<String> = Vec::with_capacity(entry.tags.len());
+            for tag in &entry.tags {
+                let tag = match from.iter().find(|f| tags::matches(tag, f)) {
+                    Some(from) => match to {
+                        Some(to) => tags::reparent(tag, from, to).unwrap_or_else(|| tag.clone()),
+                        None => continue,
+                    },
+                    None => tag.clone(),
+                };
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            if tags != entry.tags {
+                entry.tags = tags;
+                entry.touch();
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// Runs a query, returning scores and matched positions, best match first
+    pub fn search_ranked(&self, query: &Query) -> Vec<SearchHit<'_>> {
+        query.rank(&self.entries)
+    }
+
+    /// Searches entries by substring (in command, description and tags)
+    pub fn search_exact(&self, keyword: &str) -> Vec<&CommandEntry> {
         let keyword_lower = keyword.to_lowercase();
         self.entries
             .iter()
@@ -127,6 +491,14 @@ impl CommandDatabase {
     }
 }
 
+/// Sorts items by the frecency of their entries, most used first
+///
+/// The sort is stable, so items with equal frecency keep their order.
+pub fn sort_by_frecency<T>(items: &mut [T], entry: impl Fn(&T) -> &CommandEntry) {
+    let now = Utc::now();
+    items.sort_by(|a, b| entry(b).frecency(now).total_cmp(&entry(a).frecency(now)));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,6 +517,102 @@ mod tests {
         assert_eq!(entry.command, "git status");
     }
 
+    #[test]
+    fn test_entry_ref() {
+        let global: EntryRef = "12".parse().unwrap();
+        assert_eq!(global, EntryRef::global(12));
+        let project: EntryRef = "P3".parse().unwrap();
+        assert_eq!((project.source, project.id), (Source::Project, 3));
+        assert_eq!(project.to_string(), "p3");
+        assert!("p".parse::<EntryRef>().is_err());
+        assert!("x3".parse::<EntryRef>().is_err());
+    }
+
+    #[test]
+    fn test_trash() {
+        let mut db = CommandDatabase::new();
+        db.add("ls".to_string(), String::new(), vec![]);
+        db.add("make".to_string(), String::new(), vec![]);
+        db.add("htop".to_string(), String::new(), vec![]);
+
+        assert!(db.trash_by_id(2));
+        assert!(db.trash_by_id(1));
+        assert!(!db.trash_by_id(2));
+        assert!(db.find_by_id(2).is_none());
+        assert_eq!(db.all_entries().count(), 3);
+
+        assert_eq!(db.restore_from_trash(2), Some(2));
+        let ids: Vec<u64> = db.entries.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+        assert!(db.entries[0].deleted_at.is_none());
+
+        assert!(db
+            .purge_trash(Some(Utc::now() - Duration::days(30)))
+            .is_empty());
+        assert_eq!(db.purge_trash(None), vec![1]);
+        assert!(db.trash.is_empty());
+        assert_eq!(db.add("git".to_string(), String::new(), vec![]), 4);
+
+        // A restored command whose ID was taken meanwhile gets a new one
+        db.trash_by_id(4);
+        db.entries.push(CommandEntry::new(
+            4,
+            "top".to_string(),
+            String::new(),
+            vec![],
+        ));
+        assert_eq!(db.restore_from_trash(4), Some(5));
+        assert_eq!(db.find_by_id(5).unwrap().command, "git");
+    }
+
+    #[test]
+    fn test_replace_tags() {
+        let mut db = CommandDatabase::new();
+        let tags = |names: &[&str]| names.iter().map(|t| t.to_string()).collect();
+        db.add("a".to_string(), String::new(), tags(&["K8s", "ops"]));
+        db.add("b".to_string(), String::new(), tags(&["kubernetes", "k8s"]));
+        db.add("c".to_string(), String::new(), tags(&["git"]));
+
+        db.add("e".to_string(), String::new(), tags(&["K8s"]));
+        db.trash_by_id(4);
+
+        assert_eq!(db.replace_tags(&["k8s", "kubernetes"], Some("k8s")), 3);
+        assert_eq!(db.find_by_id(1).unwrap().tags, vec!["k8s", "ops"]);
+        assert_eq!(db.find_by_id(2).unwrap().tags, vec!["k8s"]);
+        // Trashed entries follow along
+        assert_eq!(db.trash[0].tags, vec!["k8s"]);
+        assert_eq!(
+            db.tag_counts(),
+            vec![
+                ("git".to_string(), 1),
+                ("k8s".to_string(), 2),
+                ("ops".to_string(), 1)
+            ]
+        );
+
+        assert_eq!(db.replace_tags(&["OPS"], None), 1);
+        assert_eq!(db.find_by_id(1).unwrap().tags, vec!["k8s"]);
+        assert_eq!(db.replace_tags(&["missing"], Some("x")), 0);
+
+        db.add(
+            "d".to_string(),
+            String::new(),
+            tags(&["cloud/aws/ec2", "cloud/gcp"]),
+        );
+        assert!(db.has_tag("cloud/aws"));
+        assert_eq!(db.replace_tags(&["cloud/aws"], Some("aws")), 1);
+        assert_eq!(db.find_by_id(5).unwrap().tags, vec!["aws/ec2", "cloud/gcp"]);
+        assert_eq!(db.replace_tags(&["cloud"], None), 1);
+        assert_eq!(db.find_by_id(5).unwrap().tags, vec!["aws/ec2"]);
+    }
+
+    fn search<'a>(db: &'a CommandDatabase, query: &str) -> Vec<&'a CommandEntry> {
+        db.search_ranked(&Query::parse(query).unwrap())
+            .into_iter()
+            .map(|hit| hit.entry)
+            .collect()
+    }
+
     #[test]
     fn test_search() {
         let mut db = CommandDatabase::new();
@@ -156,10 +624,59 @@ mod tests {
             vec![],
         );
 
-        let results = db.search("git");
+        let results = search(&db, "git");
         assert_eq!(results.len(), 2);
     }
 
+    #[test]
+    fn test_search_is_fuzzy_and_ranked() {
+        let mut db = CommandDatabase::new();
+        db.add("ls".to_string(), "List files about git".to_string(), vec![]);
+        db.add(
+            "git checkout main".to_string(),
+            "Switch branch".to_string(),
+            vec![],
+        );
+
+        let results = search(&db, "gco");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, 2);
+
+        let results = search(&db, "git");
+        assert_eq!(results[0].id, 2);
+        assert_eq!(db.search_exact("gco").len(), 0);
+    }
+
+    #[test]
+    fn test_record_use_and_frecency() {
+        let mut db = CommandDatabase::new();
+        db.add("ls".to_string(), String::new(), vec![]);
+        db.add("git status".to_string(), String::new(), vec![]);
+        db.add("make".to_string(), String::new(), vec![]);
+
+        assert!(db.record_use(2, Some(1)));
+        assert!(db.record_use(2, None));
+        assert!(db.record_use(3, Some(0)));
+        assert!(!db.record_use(42, None));
+
+        let usage = &db.find_by_id(2).unwrap().usage;
+        assert_eq!(usage.use_count, 2);
+        assert_eq!(usage.last_exit_code, Some(1));
+        assert_eq!((usage.run_count, usage.failure_count), (1, 1));
+
+        // An old habit loses to something used right now
+        db.find_by_id_mut(3).unwrap().usage = Usage {
+            use_count: 5,
+            last_used_at: Some(Utc::now() - Duration::days(30)),
+            ..Usage::default()
+        };
+
+        let mut entries: Vec<&CommandEntry> = db.entries.iter().collect();
+        sort_by_frecency(&mut entries, |e| e);
+        let ids: Vec<u64> = entries.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![2, 3, 1]);
+    }
+
     #[test]
     fn test_remove() {
         let mut db = CommandDatabase::new();
@@ -0,0 +1,361 @@
+//! Output templates for list and search
+//!
+//! A template such as `{{id}}\t{{command|oneline}}  # {{description}}`
+//! is rendered once per entry. Fields are written as `{{field}}`,
+//! optionally followed by filters: `{{command|oneline|truncate:40}}`.
+//!
+//! Fields: `id` (`p3` for project commands), `command`, `description`,
+//! `tags` (comma-separated), `created_at`, `shell`, `use_count`,
+//! `last_used_at`, `last_exit_code`, `run_count`, `failure_count`, `source`
+//! (`global` or `project`).
+//! Unset fields render as an empty string.
+//!
+//! Filters, applied left to right:
+//! - `truncate:N`: cut to N characters, ending in `...`
+//! - `pad:N`: pad with spaces on the right to N characters
+//! - `lpad:N`: pad with spaces on the left to N characters
+//! - `oneline`: replace line breaks with spaces
+//! - `default:TEXT`: use TEXT if the value is empty
+//!
+//! `\t`, `\n` and `\\` outside of braces stand for a tab, a line break and a
+//! backslash, so templates can be passed in single quotes.
+
+use colored::Colorize;
+
+use crate::models::CommandEntry;
+
+/// Format of the creation and last use times
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// A parsed output template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTemplate {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field(Field, Vec<Filter>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Id,
+    Command,
+    Description,
+    Tags,
+    CreatedAt,
+    Shell,
+    UseCount,
+    LastUsedAt,
+    LastExitCode,
+    RunCount,
+    FailureCount,
+    Source,
+}
+
+impl Field {
+    const NAMES: [(&'static str, Field); 12] = [
+        ("id", Field::Id),
+        ("command", Field::Command),
+        ("description", Field::Description),
+        ("tags", Field::Tags),
+        ("created_at", Field::CreatedAt),
+        ("shell", Field::Shell),
+        ("use_count", Field::UseCount),
+        ("last_used_at", Field::LastUsedAt),
+        ("last_exit_code", Field::LastExitCode),
+        ("run_count", Field::RunCount),
+        ("failure_count", Field::FailureCount),
+        ("source", Field::Source),
+    ];
+
+    fn parse(name: &str) -> Result<Self, String> {
+        Self::NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, field)| *field)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::NAMES.iter().map(|(n, _)| *n).collect();
+                format!(
+                    "unknown field '{}' (expected one of {})",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+
+    fn value(self, entry: &CommandEntry) -> String {
+        let usage = &entry.usage;
+        match self {
+            Field::Id => entry.reference().to_string(),
+            Field::Command => entry.command.clone(),
+            Field::Description => entry.description.clone(),
+            Field::Tags => entry.tags.join(", "),
+            Field::CreatedAt => entry.created_at.format(TIME_FORMAT).to_string(),
+            Field::Shell => entry.shell.clone().unwrap_or_default(),
+            Field::UseCount => usage.use_count.to_string(),
+            Field::LastUsedAt => usage
+                .last_used_at
+                .map(|t| t.format(TIME_FORMAT).to_string())
+                .unwrap_or_default(),
+            Field::LastExitCode => usage
+                .last_exit_code
+                .map(|c| c.to_string())
+                .unwrap_or_default(),
+            Field::RunCount => usage.run_count.to_string(),
+            Field::FailureCount => usage.failure_count.to_string(),
+            Field::Source => entry.source.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Filter {
+    Truncate(usize),
+    Pad(usize),
+    Lpad(usize),
+    Oneline,
+    Default(String),
+}
+
+impl Filter {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let (name, arg) = match spec.split_once(':') {
+            Some((name, arg)) => (name.trim(), Some(arg)),
+            None => (spec.trim(), None),
+        };
+        let width = || {
+            arg.and_then(|a| a.trim().parse().ok())
+                .ok_or_else(|| format!("filter '{}' needs a width, e.g. {}:20", name, name))
+        };
+
+        match name {
+            "truncate" => Ok(Filter::Truncate(width()?)),
+            "pad" => Ok(Filter::Pad(width()?)),
+            "lpad" => Ok(Filter::Lpad(width()?)),
+            "oneline" => Ok(Filter::Oneline),
+            "default" => Ok(Filter::Default(arg.unwrap_or_default().to_string())),
+            _ => Err(format!(
+                "unknown filter '{}' (expected one of truncate, pad, lpad, oneline, default)",
+                name
+            )),
+        }
+    }
+
+    fn apply(&self, cell: &mut Cell) {
+        match self {
+            Filter::Truncate(width) => {
+                if cell.chars.len() > *width {
+                    cell.chars.truncate(width.saturating_sub(3));
+                    cell.marked.truncate(cell.chars.len());
+                    cell.push_str("...");
+                }
+            }
+            Filter::Pad(width) => {
+                let missing = width.saturating_sub(cell.chars.len());
+                cell.push_str(&" ".repeat(missing));
+            }
+            Filter::Lpad(width) => {
+                let missing = width.saturating_sub(cell.chars.len());
+                cell.chars.splice(0..0, std::iter::repeat_n(' ', missing));
+                cell.marked
+                    .splice(0..0, std::iter::repeat_n(false, missing));
+            }
+            Filter::Oneline => {
+                for c in &mut cell.chars {
+                    if *c == '\n' {
+                        *c = ' ';
+                    }
+                }
+            }
+            Filter::Default(text) => {
+                if cell.chars.is_empty() {
+                    cell.push_str(text);
+                }
+            }
+        }
+    }
+}
+
+/// A field value being rendered, with the characters to highlight
+struct Cell {
+    chars: Vec<char>,
+    marked: Vec<bool>,
+}
+
+impl Cell {
+    fn new(text: &str, highlight: &[usize]) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        let marked = (0..chars.len())
+            .map(|i| highlight.binary_search(&i).is_ok())
+            .collect();
+        Self { chars, marked }
+    }
+
+    fn push_str(&mut self, text: &str) {
+        for c in text.chars() {
+            self.chars.push(c);
+            self.marked.push(false);
+        }
+    }
+
+    fn write_to(&self, out: &mut String) {
+        for (c, marked) in self.chars.iter().zip(&self.marked) {
+            if *marked {
+                out.push_str(&c.to_string().yellow().bold().to_string());
+            } else {
+                out.push(*c);
+            }
+        }
+    }
+}
+
+impl OutputTemplate {
+    /// Parses a template, as given to `--format`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Text(unescape(&rest[..start])));
+            }
+            let after_open = &rest[start + 2..];
+            let end = after_open
+                .find("}}")
+                .ok_or_else(|| format!("unclosed '{{{{' at '{}'", &rest[start..]))?;
+
+            let mut specs = after_open[..end].split('|');
+            let field = Field::parse(specs.next().unwrap_or_default().trim())?;
+            let filters = specs.map(Filter::parse).collect::<Result<_, _>>()?;
+            parts.push(Part::Field(field, filters));
+
+            rest = &after_open[end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(unescape(rest)));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Renders the template for an entry
+    pub fn render(&self, entry: &CommandEntry) -> String {
+        self.render_highlighted(entry, &[])
+    }
+
+    /// Renders the template for an entry, highlighting the characters of
+    /// the command at the given (sorted) positions
+    pub fn render_highlighted(&self, entry: &CommandEntry, command_indices: &[usize]) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Field(field, filters) => {
+                    let highlight = if *field == Field::Command {
+                        command_indices
+                    } else {
+                        &[]
+                    };
+                    let mut cell = Cell::new(&field.value(entry), highlight);
+                    for filter in filters {
+                        filter.apply(&mut cell);
+                    }
+                    cell.write_to(&mut out);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Replaces `\t`, `\n` and `\\` with the characters they stand for
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> CommandEntry {
+        let mut entry = CommandEntry::new(
+            7,
+            "git log\n--oneline".to_string(),
+            "Show history".to_string(),
+            vec!["git".to_string(), "vcs".to_string()],
+        );
+        entry.usage.use_count = 3;
+        entry
+    }
+
+    fn render(template: &str) -> String {
+        OutputTemplate::parse(template).unwrap().render(&entry())
+    }
+
+    #[test]
+    fn test_fields_and_escapes() {
+        assert_eq!(
+            render(r"{{id}}\t{{command|oneline}}  # {{ description }}"),
+            "7\tgit log --oneline  # Show history"
+        );
+        assert_eq!(render("[{{tags}}] {{use_count}}x"), "[git, vcs] 3x");
+        assert_eq!(
+            render("{{shell}}|{{last_used_at}}|{{last_exit_code}}"),
+            "||"
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(render("{{description|truncate:8}}"), "Show ...");
+        assert_eq!(render("{{description|truncate:20}}"), "Show history");
+        assert_eq!(render("{{id|lpad:3}}|{{id|pad:3}}|"), "  7|7  |");
+        assert_eq!(render("{{shell|default:$SHELL}}"), "$SHELL");
+        assert_eq!(
+            render("{{command|oneline|truncate:10|pad:12}}|"),
+            "git log...  |"
+        );
+    }
+
+    #[test]
+    fn test_highlight_follows_filters() {
+        let mut cell = Cell::new("git log", &[0, 5]);
+        Filter::Truncate(6).apply(&mut cell);
+        Filter::Lpad(8).apply(&mut cell);
+
+        let chars: String = cell.chars.iter().collect();
+        assert_eq!(chars, "  git...");
+        let marked: Vec<usize> = (0..cell.marked.len()).filter(|&i| cell.marked[i]).collect();
+        assert_eq!(marked, vec![2]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = OutputTemplate::parse("{{nope}}").unwrap_err();
+        assert!(err.contains("unknown field 'nope'"), "{}", err);
+        assert!(OutputTemplate::parse("{{id|truncate}}").is_err());
+        assert!(OutputTemplate::parse("{{id|upper}}").is_err());
+        assert!(OutputTemplate::parse("{{id").is_err());
+    }
+}
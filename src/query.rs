@@ -0,0 +1,792 @@
+//! Structured search queries
+//!
+//! A query is a list of terms that must all match:
+//!
+//! - `word` - fuzzy match against command, description and tags
+//! - `"some phrase"` - substring match against command, description and tags
+//! - `tag:k8s` - the entry has this tag, or one below it (`tag:cloud`
+//!   matches `cloud/aws`)
+//! - `cmd:kubectl`, `desc:"roll back"` - substring match in one field
+//! - `created:2026-01-01`, `created:>2026-01-01`, `created:<=2026-02-01`,
+//!   `created:2026-01-01..2026-01-31` - creation date (UTC, ranges inclusive)
+//! - `-term` - the term must not match
+//! - `a OR b`, `(a OR b) c` - alternatives and grouping
+//!
+//! Queries are often pasted shell snippets, so words that only look like
+//! syntax are searched as text: `--force`, a `-la` right after a plain word
+//! (as in `ls -la`), and `word:` prefixes that aren't fields
+//! (`localhost:8080`).
+//!
+//! Entries are ranked by the fuzzy score of the plain words they match.
+
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use thiserror::Error;
+
+use crate::fuzzy::{Matcher, SearchHit};
+use crate::models::CommandEntry;
+use crate::tags;
+
+/// Field names accepted before `:`, in any case
+const FIELDS: [&str; 7] = [
+    "tag",
+    "tags",
+    "cmd",
+    "command",
+    "desc",
+    "description",
+    "created",
+];
+
+/// A query that could not be parsed
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{message} (at column {})", position + 1)]
+pub struct QueryError {
+    /// Character offset of the problem in the query
+    pub position: usize,
+    /// What went wrong
+    pub message: String,
+}
+
+impl QueryError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+
+    /// Returns the query with a caret under the problem
+    pub fn pointer(&self, query: &str) -> String {
+        format!("{}\n{}^", query, " ".repeat(self.position))
+    }
+}
+
+/// A parsed search query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    /// None matches every entry
+    expr: Option<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Term(Term),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Term {
+    /// Fuzzy match in any field
+    Fuzzy(String),
+    /// Substring match in any field
+    Phrase(String),
+    Command(String),
+    Description(String),
+    Tag(String),
+    Created(DateRange),
+}
+
+/// A half-open range of instants; `None` means unbounded
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DateRange {
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+}
+
+impl DateRange {
+    fn contains(&self, t: &DateTime<Utc>) -> bool {
+        self.start.is_none_or(|s| *t >= s) && self.end.is_none_or(|e| *t < e)
+    }
+}
+
+impl Query {
+    /// Parses a query string
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let tokens = lex(input)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: input.chars().count(),
+        };
+
+        if parser.peek().is_none() {
+            return Ok(Self { expr: None });
+        }
+
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(QueryError::new(token.pos, "unexpected ')'"));
+        }
+        Ok(Self { expr: Some(expr) })
+    }
+
+    /// Returns the words of a query made of plain words and phrases only,
+    /// joined by spaces, or None
+    ///
+    /// Every entry containing this text matches the query, so an index
+    /// lookup of it finds matches without scanning everything.
+    pub fn plain_text(&self) -> Option<String> {
+        let terms = match self.expr.as_ref()? {
+            Expr::And(items) => items.iter().collect(),
+            expr => vec![expr],
+        };
+        let words = terms
+            .into_iter()
+            .map(|term| match term {
+                Expr::Term(Term::Fuzzy(word) | Term::Phrase(word)) => Some(word.as_str()),
+                _ => None,
+            })
+            .collect::<Option<Vec<&str>>>()?;
+        Some(words.join(" "))
+    }
+
+    /// Scores an entry, returning None if it doesn't match
+    pub fn score<'a>(&self, matcher: &Matcher, entry: &'a CommandEntry) -> Option<SearchHit<'a>> {
+        let mut hit = SearchHit {
+            entry,
+            score: 0,
+            command_indices: Vec::new(),
+        };
+        if let Some(expr) = &self.expr {
+            if !eval(expr, matcher, &mut hit) {
+                return None;
+            }
+        }
+        hit.command_indices.sort_unstable();
+        hit.command_indices.dedup();
+        Some(hit)
+    }
+
+    /// Returns the matching entries, best match first
+    ///
+    /// Entries with equal scores keep their original order.
+    pub fn rank<'a>(
+        &self,
+        entries: impl IntoIterator<Item = &'a CommandEntry>,
+    ) -> Vec<SearchHit<'a>> {
+        let matcher = Matcher::new();
+        let mut hits: Vec<SearchHit<'a>> = entries
+            .into_iter()
+            .filter_map(|e| self.score(&matcher, e))
+            .collect();
+        hits.sort_by_key(|h| std::cmp::Reverse(h.score));
+        hits
+    }
+}
+
+/// Evaluates an expression, adding fuzzy scores of matched words to `hit`
+fn eval(expr: &Expr, matcher: &Matcher, hit: &mut SearchHit) -> bool {
+    match expr {
+        Expr::And(items) => items.iter().all(|e| eval(e, matcher, hit)),
+        Expr::Or(branches) => {
+            let mut any = false;
+            for branch in branches {
+                let mut branch_hit = SearchHit {
+                    entry: hit.entry,
+                    score: 0,
+                    command_indices: Vec::new(),
+                };
+                if eval(branch, matcher, &mut branch_hit) {
+                    any = true;
+                    hit.score += branch_hit.score;
+                    hit.command_indices.extend(branch_hit.command_indices);
+                }
+            }
+            any
+        }
+        Expr::Not(inner) => {
+            let mut ignored = SearchHit {
+                entry: hit.entry,
+                score: 0,
+                command_indices: Vec::new(),
+            };
+            !eval(inner, matcher, &mut ignored)
+        }
+        Expr::Term(term) => eval_term(term, matcher, hit),
+    }
+}
+
+/// Evaluates a single term
+fn eval_term(term: &Term, matcher: &Matcher, hit: &mut SearchHit) -> bool {
+    let entry = hit.entry;
+    match term {
+        Term::Fuzzy(word) => match matcher.score_term(entry, word) {
+            Some((score, indices)) => {
+                hit.score += score;
+                hit.command_indices.extend(indices);
+                true
+            }
+            None => false,
+        },
+        Term::Phrase(text) => {
+            contains(&entry.command, text)
+                || contains(&entry.description, text)
+                || entry.tags.iter().any(|t| contains(t, text))
+        }
+        Term::Command(text) => contains(&entry.command, text),
+        Term::Description(text) => contains(&entry.description, text),
+        Term::Tag(tag) => entry.tags.iter().any(|t| tags::matches(t, tag)),
+        Term::Created(range) => range.contains(&entry.created_at),
+    }
+}
+
+/// Case-insensitive substring test
+fn contains(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    LParen,
+    RParen,
+    Not,
+    Or,
+    /// A term, optionally qualified by a field
+    Word {
+        field: Option<String>,
+        value: String,
+        quoted: bool,
+        value_pos: usize,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    pos: usize,
+}
+
+/// Splits a query into tokens
+fn lex(input: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let pos = i;
+        let kind = match c {
+            '(' => {
+                i += 1;
+                TokenKind::LParen
+            }
+            ')' => {
+                i += 1;
+                TokenKind::RParen
+            }
+            '-' if chars.get(i + 1).is_some_and(|n| !n.is_whitespace())
+                && !starts_flag(&chars, i, tokens.last()) =>
+            {
+                i += 1;
+                TokenKind::Not
+            }
+            '"' => {
+                let (value, next) = read_quoted(&chars, i)?;
+                i = next;
+                TokenKind::Word {
+                    field: None,
+                    value,
+                    quoted: true,
+                    value_pos: pos + 1,
+                }
+            }
+            _ => {
+                let start = i;
+                while i < chars.len() && !is_word_boundary(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+
+                match split_field(&word) {
+                    _ if word == "OR" => TokenKind::Or,
+                    Some((field, rest)) => {
+                        let value_pos = start + field.chars().count() + 1;
+                        let (value, quoted) = if rest.is_empty() && chars.get(i) == Some(&'"') {
+                            let (value, next) = read_quoted(&chars, i)?;
+                            i = next;
+                            (value, true)
+                        } else {
+                            (rest.to_string(), false)
+                        };
+                        if value.is_empty() && !quoted {
+                            return Err(QueryError::new(
+                                value_pos,
+                                format!("missing value after '{}:'", field),
+                            ));
+                        }
+                        TokenKind::Word {
+                            field: Some(field.to_lowercase()),
+                            value,
+                            quoted,
+                            value_pos,
+                        }
+                    }
+                    _ => TokenKind::Word {
+                        field: None,
+                        value: word,
+                        quoted: false,
+                        value_pos: start,
+                    },
+                }
+            }
+        };
+        tokens.push(Token { kind, pos });
+    }
+
+    Ok(tokens)
+}
+
+/// Characters that end an unquoted word
+fn is_word_boundary(c: char) -> bool {
+    c.is_whitespace() || c == '(' || c == ')' || c == '"'
+}
+
+/// Splits `field:rest` into its parts if the prefix is a known field
+fn split_field(word: &str) -> Option<(&str, &str)> {
+    word.split_once(':')
+        .filter(|(field, _)| FIELDS.contains(&field.to_lowercase().as_str()))
+}
+
+/// Returns true if the `-` at `i` starts a command-line flag rather than a
+/// negation: `--force`, or a `-la` following a plain word as in `ls -la`
+///
+/// Fields, phrases and groups after `-` are always negated.
+fn starts_flag(chars: &[char], i: usize, previous: Option<&Token>) -> bool {
+    match chars.get(i + 1) {
+        Some('-') => true,
+        Some('"' | '(') => false,
+        _ => {
+            let after_word = matches!(
+                previous,
+                Some(Token {
+                    kind: TokenKind::Word {
+                        field: None,
+                        quoted: false,
+                        ..
+                    },
+                    ..
+                })
+            );
+            let word: String = chars[i + 1..]
+                .iter()
+                .take_while(|&&c| !is_word_boundary(c))
+                .collect();
+            after_word && split_field(&word).is_none()
+        }
+    }
+}
+
+/// Reads a quoted string starting at the opening quote
+///
+/// Returns the unescaped contents and the index after the closing quote.
+fn read_quoted(chars: &[char], open: usize) -> Result<(String, usize), QueryError> {
+    let mut value = String::new();
+    let mut i = open + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if matches!(chars.get(i + 1), Some('"' | '\\')) => {
+                value.push(chars[i + 1]);
+                i += 2;
+            }
+            '"' => return Ok((value, i + 1)),
+            c => {
+                value.push(c);
+                i += 1;
+            }
+        }
+    }
+    Err(QueryError::new(open, "unterminated quote"))
+}
+
+/// Recursive-descent parser over the tokens
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Length of the input, used to report errors at the end
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Position of the next token, or the end of the input
+    fn here(&self) -> usize {
+        self.peek().map_or(self.end, |t| t.pos)
+    }
+
+    /// or := and ("OR" and)*
+    fn parse_or(&mut self) -> Result<Expr, QueryError> {
+        let mut branches = vec![self.parse_and()?];
+        while self.peek().is_some_and(|t| t.kind == TokenKind::Or) {
+            let or = self.next().map_or(self.end, |t| t.pos);
+            if self
+                .peek()
+                .is_none_or(|t| matches!(t.kind, TokenKind::Or | TokenKind::RParen))
+            {
+                return Err(QueryError::new(or, "expected a term after OR"));
+            }
+            branches.push(self.parse_and()?);
+        }
+
+        Ok(if branches.len() == 1 {
+            branches.remove(0)
+        } else {
+            Expr::Or(branches)
+        })
+    }
+
+    /// and := unary+
+    fn parse_and(&mut self) -> Result<Expr, QueryError> {
+        let mut items = Vec::new();
+        while let Some(token) = self.peek() {
+            if matches!(token.kind, TokenKind::Or | TokenKind::RParen) {
+                break;
+            }
+            items.push(self.parse_unary()?);
+        }
+
+        match items.len() {
+            0 => {
+                let message = match self.peek().map(|t| &t.kind) {
+                    Some(TokenKind::Or) => "expected a term before OR",
+                    Some(TokenKind::RParen) => "unexpected ')'",
+                    _ => "expected a term",
+                };
+                Err(QueryError::new(self.here(), message))
+            }
+            1 => Ok(items.remove(0)),
+            _ => Ok(Expr::And(items)),
+        }
+    }
+
+    /// unary := "-" unary | "(" or ")" | term
+    fn parse_unary(&mut self) -> Result<Expr, QueryError> {
+        let Some(token) = self.next() else {
+            return Err(QueryError::new(self.end, "expected a term"));
+        };
+
+        match token.kind {
+            TokenKind::Not => {
+                if self
+                    .peek()
+                    .is_none_or(|t| matches!(t.kind, TokenKind::Or | TokenKind::RParen))
+                {
+                    return Err(QueryError::new(token.pos, "expected a term after '-'"));
+                }
+                let inner = match self.parse_unary()? {
+                    // Excluding by fuzzy match would be far too broad
+                    Expr::Term(Term::Fuzzy(word)) => Expr::Term(Term::Phrase(word)),
+                    other => other,
+                };
+                Ok(Expr::Not(Box::new(inner)))
+            }
+            TokenKind::LParen => {
+                if self.peek().is_some_and(|t| t.kind == TokenKind::RParen) {
+                    return Err(QueryError::new(token.pos, "empty group"));
+                }
+                let inner = self.parse_or()?;
+                match self.next() {
+                    Some(Token {
+                        kind: TokenKind::RParen,
+                        ..
+                    }) => Ok(inner),
+                    _ => Err(QueryError::new(token.pos, "unclosed '('")),
+                }
+            }
+            TokenKind::Word {
+                field,
+                value,
+                quoted,
+                value_pos,
+            } => Ok(Expr::Term(make_term(field, value, quoted, value_pos)?)),
+            TokenKind::RParen | TokenKind::Or => Err(QueryError::new(token.pos, "expected a term")),
+        }
+    }
+}
+
+/// Builds a term from a (possibly field-qualified) word
+fn make_term(
+    field: Option<String>,
+    value: String,
+    quoted: bool,
+    value_pos: usize,
+) -> Result<Term, QueryError> {
+    let Some(field) = field else {
+        return Ok(if quoted {
+            Term::Phrase(value)
+        } else {
+            Term::Fuzzy(value)
+        });
+    };
+
+    // The lexer only accepts the names in FIELDS
+    match field.as_str() {
+        "tag" | "tags" => Ok(Term::Tag(value)),
+        "cmd" | "command" => Ok(Term::Command(value)),
+        "desc" | "description" => Ok(Term::Description(value)),
+        _ => Ok(Term::Created(parse_date_range(&value, value_pos)?)),
+    }
+}
+
+/// Parses the value of `created:`
+fn parse_date_range(value: &str, pos: usize) -> Result<DateRange, QueryError> {
+    let day = |s: &str, offset: usize| -> Result<DateTime<Utc>, QueryError> {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+            .map_err(|_| {
+                QueryError::new(
+                    pos + offset,
+                    format!("invalid date '{}', expected YYYY-MM-DD", s),
+                )
+            })
+    };
+    let next_day = |t: DateTime<Utc>| t.checked_add_days(Days::new(1));
+
+    if let Some(rest) = value.strip_prefix(">=") {
+        return Ok(DateRange {
+            start: Some(day(rest, 2)?),
+            end: None,
+        });
+    }
+    if let Some(rest) = value.strip_prefix("<=") {
+        return Ok(DateRange {
+            start: None,
+            end: next_day(day(rest, 2)?),
+        });
+    }
+    if let Some(rest) = value.strip_prefix('>') {
+        return Ok(DateRange {
+            start: next_day(day(rest, 1)?),
+            end: None,
+        });
+    }
+    if let Some(rest) = value.strip_prefix('<') {
+        return Ok(DateRange {
+            start: None,
+            end: Some(day(rest, 1)?),
+        });
+    }
+    if let Some((from, to)) = value.split_once("..") {
+        if from.is_empty() && to.is_empty() {
+            return Err(QueryError::new(pos, "date range needs at least one end"));
+        }
+        let start = if from.is_empty() {
+            None
+        } else {
+            Some(day(from, 0)?)
+        };
+        let end = if to.is_empty() {
+            None
+        } else {
+            next_day(day(to, from.chars().count() + 2)?)
+        };
+        return Ok(DateRange { start, end });
+    }
+
+    let start = day(value, 0)?;
+    Ok(DateRange {
+        start: Some(start),
+        end: next_day(start),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        id: u64,
+        command: &str,
+        description: &str,
+        tags: &[&str],
+        created: &str,
+    ) -> CommandEntry {
+        let mut entry = CommandEntry::new(
+            id,
+            command.to_string(),
+            description.to_string(),
+            tags.iter().map(|t| t.to_string()).collect(),
+        );
+        entry.created_at = DateTime::parse_from_rfc3339(created).unwrap().to_utc();
+        entry
+    }
+
+    fn sample() -> Vec<CommandEntry> {
+        vec![
+            entry(
+                1,
+                "kubectl rollout undo deploy/web",
+                "Rollback web",
+                &["k8s", "prod"],
+                "2025-12-20T10:00:00Z",
+            ),
+            entry(
+                2,
+                "kubectl get pods",
+                "List pods",
+                &["k8s"],
+                "2026-01-05T10:00:00Z",
+            ),
+            entry(
+                3,
+                "kubectl delete pod x",
+                "Old way",
+                &["k8s", "prod", "deprecated"],
+                "2026-01-10T10:00:00Z",
+            ),
+            entry(
+                4,
+                "git checkout main",
+                "Switch to main",
+                &["git"],
+                "2026-02-01T00:00:00Z",
+            ),
+        ]
+    }
+
+    fn ids(query: &str) -> Vec<u64> {
+        let entries = sample();
+        Query::parse(query)
+            .unwrap()
+            .rank(&entries)
+            .iter()
+            .map(|h| h.entry.id)
+            .collect()
+    }
+
+    fn error(query: &str) -> QueryError {
+        Query::parse(query).unwrap_err()
+    }
+
+    #[test]
+    fn test_empty_query_matches_all() {
+        assert_eq!(ids(""), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_field_filters_and_negation() {
+        assert_eq!(ids("tag:k8s tag:prod -tag:deprecated"), vec![1]);
+        assert_eq!(ids("desc:\"rollback\""), vec![1]);
+        assert_eq!(ids("cmd:checkout"), vec![4]);
+        assert_eq!(ids("tag:k8s -pods"), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_date_ranges() {
+        assert_eq!(ids("created:>2026-01-01"), vec![2, 3, 4]);
+        assert_eq!(ids("created:>=2026-01-10"), vec![3, 4]);
+        assert_eq!(ids("created:<2026-01-05"), vec![1]);
+        assert_eq!(ids("created:<=2026-01-05"), vec![1, 2]);
+        assert_eq!(ids("created:2026-01-01..2026-01-10"), vec![2, 3]);
+        assert_eq!(ids("created:2026-02-01"), vec![4]);
+        assert_eq!(ids("created:..2025-12-31"), vec![1]);
+    }
+
+    #[test]
+    fn test_or_groups() {
+        assert_eq!(ids("tag:git OR tag:deprecated"), vec![3, 4]);
+        assert_eq!(ids("(cmd:checkout OR cmd:rollout) -tag:git"), vec![1]);
+    }
+
+    #[test]
+    fn test_fuzzy_words_rank() {
+        let found = ids("gco");
+        assert_eq!(found[0], 4);
+        assert_eq!(ids("tag:k8s pods")[0], 2);
+    }
+
+    #[test]
+    fn test_quoted_phrase_is_literal() {
+        assert_eq!(ids("\"get pods\""), vec![2]);
+        assert!(ids("\"gco\"").is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors_point_at_problem() {
+        assert_eq!(error("tag:k8s \"oops").position, 8);
+        assert_eq!(error("created:2026-13-01").position, 8);
+        assert_eq!(error("created:2026-01-01..nope").position, 20);
+        assert_eq!(error("(tag:k8s").position, 0);
+        assert_eq!(error("tag:k8s )").position, 8);
+        assert_eq!(error("git OR").position, 4);
+        assert_eq!(error("OR git").position, 0);
+        assert_eq!(error("tag: x").position, 4);
+        assert_eq!(error("()").position, 0);
+    }
+
+    #[test]
+    fn test_pointer() {
+        let err = error("(tag:k8s");
+        assert_eq!(err.pointer("(tag:k8s"), "(tag:k8s\n^");
+    }
+
+    #[test]
+    fn test_unqualified_colon_words() {
+        // Only known fields are fields; anything else is text
+        assert!(Query::parse("127.0.0.1:8080").is_ok());
+        assert!(Query::parse("\"localhost:8080\"").is_ok());
+        assert!(Query::parse("tga:k8s").is_ok());
+    }
+
+    #[test]
+    fn test_plain_text() {
+        let text = |query: &str| Query::parse(query).unwrap().plain_text();
+        assert_eq!(text("git push"), Some("git push".to_string()));
+        assert_eq!(
+            text("\"get pods\" ls -la"),
+            Some("get pods ls -la".to_string())
+        );
+        assert_eq!(text(""), None);
+        assert_eq!(text("tag:k8s pods"), None);
+        assert_eq!(text("-pods"), None);
+        assert_eq!(text("a OR b"), None);
+    }
+
+    #[test]
+    fn test_shell_snippets_are_text() {
+        let entries = vec![
+            entry(
+                1,
+                "curl http://localhost:8080",
+                "",
+                &[],
+                "2026-01-01T00:00:00Z",
+            ),
+            entry(2, "ls -la", "", &[], "2026-01-01T00:00:00Z"),
+            entry(3, "git push --force", "", &["git"], "2026-01-01T00:00:00Z"),
+        ];
+        let ids = |query: &str| -> Vec<u64> {
+            Query::parse(query)
+                .unwrap()
+                .rank(&entries)
+                .iter()
+                .map(|h| h.entry.id)
+                .collect()
+        };
+
+        assert_eq!(ids("ls -la"), vec![2]);
+        assert_eq!(ids("--force"), vec![3]);
+        assert_eq!(ids("git push --force"), vec![3]);
+        assert_eq!(ids("localhost:8080"), vec![1]);
+        assert_eq!(ids("http://localhost:8080"), vec![1]);
+        // A leading dash, or one after a field, still negates
+        assert_eq!(ids("-la"), vec![1, 3]);
+        assert_eq!(ids("tag:git -force"), Vec::<u64>::new());
+        assert_eq!(ids("push -tag:git"), Vec::<u64>::new());
+    }
+}
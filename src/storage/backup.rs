@@ -0,0 +1,233 @@
+//! Rotating backups of the database file
+//!
+//! Before every change, the file as it was is copied to a `backups` directory
+//! next to it, named after the database and the time, e.g.
+//! `backups/commands-20261017T194800.123Z.json`. Afterwards the backups are
+//! rotated: the [`KEEP_RECENT`] newest are kept, and so is the first backup
+//! of each of the last [`KEEP_DAILY`] days with backups.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use super::sqlite::SqliteBackend;
+use super::{json, Backend, BackendKind};
+use crate::error::{CmdKeeperError, Result};
+use crate::models::CommandDatabase;
+
+/// Number of most recent backups kept
+pub const KEEP_RECENT: usize = 10;
+
+/// Number of days whose first backup is kept
+pub const KEEP_DAILY: usize = 7;
+
+/// Directory holding the backups, next to the database
+const BACKUP_DIR: &str = "backups";
+
+/// Format of the timestamp in backup file names
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+/// A copy of the database file
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// When the copy was taken
+    pub taken_at: DateTime<Utc>,
+}
+
+impl Backup {
+    /// Returns the timestamp identifying the backup, e.g. `20261017T194800.123Z`
+    pub fn timestamp(&self) -> String {
+        self.taken_at.format(TIMESTAMP_FORMAT).to_string()
+    }
+
+    /// Returns the size of the file in bytes
+    pub fn size(&self) -> Result<u64> {
+        Ok(fs::metadata(&self.path)?.len())
+    }
+
+    /// Reads the database stored in the backup
+    pub fn load(&self) -> Result<CommandDatabase> {
+        match BackendKind::from_path(&self.path) {
+            BackendKind::Json => Ok(json::parse(&fs::read_to_string(&self.path)?)?.0),
+            BackendKind::Sqlite => SqliteBackend::new(self.path.clone()).load(),
+        }
+    }
+}
+
+/// The backups of a database
+pub struct Backups {
+    dir: PathBuf,
+    /// File stem of the database, e.g. `commands`
+    stem: String,
+    /// Extension of the database including the dot, e.g. `.json`
+    extension: String,
+}
+
+impl Backups {
+    /// Returns the backups of the database at `db_path`
+    pub fn for_database(db_path: &Path) -> Self {
+        let stem = db_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "commands".to_string());
+        let extension = db_path
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+        Self {
+            dir: super::parent_dir(db_path).join(BACKUP_DIR),
+            stem,
+            extension,
+        }
+    }
+
+    /// Lists the backups, newest first
+    pub fn list(&self) -> Result<Vec<Backup>> {
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut backups = Vec::new();
+        for file in dir {
+            let path = file?.path();
+            let taken_at = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(&format!("{}-", self.stem)))
+                .and_then(|rest| rest.strip_suffix(&self.extension))
+                .and_then(|stamp| NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT).ok());
+            if let Some(taken_at) = taken_at {
+                backups.push(Backup {
+                    path,
+                    taken_at: taken_at.and_utc(),
+                });
+            }
+        }
+        backups.sort_by_key(|b| std::cmp::Reverse(b.taken_at));
+        Ok(backups)
+    }
+
+    /// Finds the backup whose timestamp starts with `timestamp`
+    ///
+    /// Fails unless exactly one backup matches.
+    pub fn find(&self, timestamp: &str) -> Result<Backup> {
+        let matching: Vec<Backup> = self
+            .list()?
+            .into_iter()
+            .filter(|b| !timestamp.is_empty() && b.timestamp().starts_with(timestamp))
+            .collect();
+        match <[Backup; 1]>::try_from(matching) {
+            Ok([backup]) => Ok(backup),
+            Err(matching) if matching.is_empty() => {
+                Err(CmdKeeperError::BackupNotFound(timestamp.to_string()))
+            }
+            Err(matching) => Err(CmdKeeperError::AmbiguousBackup(
+                timestamp.to_string(),
+                matching.len(),
+            )),
+        }
+    }
+
+    /// Copies the database file to a new backup and rotates the backups
+    ///
+    /// Does nothing if the file doesn't exist yet or a backup was already
+    /// taken within the same millisecond.
+    pub fn take(&self, db_path: &Path) -> Result<()> {
+        if !db_path.exists() {
+            return Ok(());
+        }
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!(
+            "{}-{}{}",
+            self.stem,
+            Utc::now().format(TIMESTAMP_FORMAT),
+            self.extension
+        ));
+        if !path.exists() {
+            fs::copy(db_path, path)?;
+        }
+
+        for backup in expired(&self.list()?) {
+            fs::remove_file(&backup.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the backups rotation removes from a list sorted newest first
+fn expired(backups: &[Backup]) -> Vec<&Backup> {
+    // The first backup of each day, newest day first
+    let mut daily: Vec<(NaiveDate, usize)> = Vec::new();
+    for (i, backup) in backups.iter().enumerate() {
+        let day = backup.taken_at.date_naive();
+        match daily.last_mut() {
+            Some((last, first)) if *last == day => *first = i,
+            _ => daily.push((day, i)),
+        }
+    }
+    let kept_daily: Vec<usize> = daily.iter().take(KEEP_DAILY).map(|&(_, i)| i).collect();
+
+    backups
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i >= KEEP_RECENT && !kept_daily.contains(i))
+        .map(|(_, backup)| backup)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rotation_keeps_recent_and_daily() {
+        // Four backups a day for ten days, newest first
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 8, 0, 0).unwrap();
+        let backups: Vec<Backup> = (0..40)
+            .rev()
+            .map(|i| Backup {
+                path: PathBuf::from(i.to_string()),
+                taken_at: start + Duration::days(i / 4) + Duration::hours(i % 4),
+            })
+            .collect();
+
+        let expired: Vec<usize> = expired(&backups)
+            .iter()
+            .map(|b| backups.iter().position(|o| o.path == b.path).unwrap())
+            .collect();
+        // The 10 newest stay, as does the 08:00 backup of the 7 newest days
+        let kept: Vec<usize> = (0..40).filter(|i| !expired.contains(i)).collect();
+        assert_eq!(kept, vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 11, 15, 19, 23, 27]);
+    }
+
+    #[test]
+    fn test_take_list_and_find() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("commands.json");
+        let backups = Backups::for_database(&db_path);
+
+        backups.take(&db_path).unwrap();
+        assert!(backups.list().unwrap().is_empty());
+
+        let mut db = CommandDatabase::new();
+        db.add("ls".to_string(), String::new(), vec![]);
+        fs::write(&db_path, serde_json::to_string(&db).unwrap()).unwrap();
+        backups.take(&db_path).unwrap();
+        // Other files in the directory are not backups
+        fs::write(dir.path().join("backups/notes.txt"), "").unwrap();
+
+        let list = backups.list().unwrap();
+        assert_eq!(list.len(), 1);
+        let found = backups.find(&list[0].timestamp()[..8]).unwrap();
+        assert_eq!(found.load().unwrap().entries[0].command, "ls");
+        assert!(matches!(
+            backups.find("1999"),
+            Err(CmdKeeperError::BackupNotFound(_))
+        ));
+    }
+}
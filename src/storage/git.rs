@@ -0,0 +1,199 @@
+//! Git repository holding the database, for `cmd-keeper sync`
+//!
+//! Runs the `git` executable in the directory of the database. Only the
+//! database file is ever added, so lock files and backups next to it stay
+//! out of the history.
+//!
+//! A repository set up by `sync` is marked with the `cmd-keeper.sync` config
+//! key; every save to a marked repository is committed right away (see
+//! [`super::Storage::save`]).
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use crate::error::{CmdKeeperError, Result};
+
+/// Name of the remote used for syncing
+pub const REMOTE: &str = "origin";
+
+/// Config key marking a repository as managed by cmd-keeper
+const SYNC_KEY: &str = "cmd-keeper.sync";
+
+/// A git repository in the database directory
+pub struct Repo {
+    dir: PathBuf,
+}
+
+impl Repo {
+    /// Opens the repository if `dir` is the top of one
+    pub fn open(dir: &Path) -> Option<Self> {
+        dir.join(".git").exists().then(|| Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// Opens the repository if `dir` holds one that `sync` set up
+    pub fn open_synced(dir: &Path) -> Option<Self> {
+        let repo = Self::open(dir)?;
+        let marked = repo.config(SYNC_KEY).ok().flatten();
+        (marked.as_deref() == Some("true")).then_some(repo)
+    }
+
+    /// Creates a repository in `dir`, on a `main` branch
+    ///
+    /// Without a configured identity, commits are made as `cmd-keeper`.
+    pub fn init(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let repo = Self {
+            dir: dir.to_path_buf(),
+        };
+        repo.run(&["init", "--quiet"])?;
+        repo.run(&["symbolic-ref", "HEAD", "refs/heads/main"])?;
+        // Keep lock files, backups, journals, temporary files and profiles out
+        // of `git status`
+        std::fs::write(
+            dir.join(".git/info/exclude"),
+            "*.lock\n*.bak\nbackups/\n*.journal.jsonl\n.*.tmp-*\nprofiles/\n",
+        )?;
+        if repo.config("user.email")?.is_none() {
+            repo.run(&["config", "user.name", "cmd-keeper"])?;
+            repo.run(&["config", "user.email", "cmd-keeper@localhost"])?;
+        }
+        Ok(repo)
+    }
+
+    /// Marks the repository so that every save is committed
+    pub fn mark_synced(&self) -> Result<()> {
+        self.run(&["config", SYNC_KEY, "true"])?;
+        Ok(())
+    }
+
+    /// Returns the URL of the sync remote, if set
+    pub fn remote_url(&self) -> Result<Option<String>> {
+        self.config(&format!("remote.{}.url", REMOTE))
+    }
+
+    /// Adds the sync remote, or points it at another URL
+    pub fn set_remote(&self, url: &str) -> Result<()> {
+        if self.remote_url()?.is_some() {
+            self.run(&["remote", "set-url", REMOTE, url])?;
+        } else {
+            self.run(&["remote", "add", REMOTE, url])?;
+        }
+        Ok(())
+    }
+
+    /// Commits the file if it changed, returning true if a commit was made
+    pub fn commit_file(&self, file: &str, message: &str) -> Result<bool> {
+        if !self.dir.join(file).exists() {
+            return Ok(false);
+        }
+        self.run(&["add", "--", file])?;
+        // Exits with 1 when nothing is staged
+        if self.succeeds(&["diff", "--cached", "--quiet"])? {
+            return Ok(false);
+        }
+        self.run(&["commit", "--quiet", "--no-verify", "-m", message])?;
+        Ok(true)
+    }
+
+    /// Returns the current branch
+    pub fn branch(&self) -> Result<String> {
+        self.run(&["symbolic-ref", "--short", "HEAD"])
+    }
+
+    /// Returns the commit HEAD points to, or None before the first commit
+    pub fn head(&self) -> Result<Option<String>> {
+        self.rev_parse("HEAD")
+    }
+
+    /// Fetches the branch from the remote and returns its commit, or None
+    /// if the remote doesn't have it yet
+    pub fn fetch(&self, branch: &str) -> Result<Option<String>> {
+        self.run(&["fetch", "--quiet", REMOTE])?;
+        self.rev_parse(&format!("refs/remotes/{}/{}", REMOTE, branch))
+    }
+
+    /// Pushes the current branch to the remote
+    pub fn push(&self, branch: &str) -> Result<()> {
+        let refspec = format!("HEAD:refs/heads/{}", branch);
+        self.run(&["push", "--quiet", REMOTE, &refspec])?;
+        Ok(())
+    }
+
+    /// Returns true if commit `a` is an ancestor of (or equal to) `b`
+    pub fn is_ancestor(&self, a: &str, b: &str) -> Result<bool> {
+        self.succeeds(&["merge-base", "--is-ancestor", a, b])
+    }
+
+    /// Returns the best common ancestor of two commits, if they share history
+    pub fn merge_base(&self, a: &str, b: &str) -> Result<Option<String>> {
+        self.query(&["merge-base", a, b])
+    }
+
+    /// Reads a file as of a commit, or None if it didn't exist there
+    pub fn show(&self, commit: &str, file: &str) -> Result<Option<String>> {
+        self.query(&["show", &format!("{}:{}", commit, file)])
+    }
+
+    /// Moves the branch and the working tree to a commit
+    pub fn reset_hard(&self, commit: &str) -> Result<()> {
+        self.run(&["reset", "--quiet", "--hard", commit])?;
+        Ok(())
+    }
+
+    /// Moves the branch to a commit, keeping the working tree and index
+    pub fn reset_soft(&self, commit: &str) -> Result<()> {
+        self.run(&["reset", "--quiet", "--soft", commit])?;
+        Ok(())
+    }
+
+    /// Reads a config value, or None if unset
+    fn config(&self, key: &str) -> Result<Option<String>> {
+        self.query(&["config", "--get", key])
+    }
+
+    /// Resolves a revision, or None if it doesn't exist
+    fn rev_parse(&self, rev: &str) -> Result<Option<String>> {
+        self.query(&["rev-parse", "--verify", "--quiet", rev])
+    }
+
+    /// Runs git and returns its output, or None if it exits unsuccessfully
+    fn query(&self, args: &[&str]) -> Result<Option<String>> {
+        let output = self.output(args)?;
+        Ok(output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string()))
+    }
+
+    /// Runs git and returns whether it exited successfully
+    fn succeeds(&self, args: &[&str]) -> Result<bool> {
+        Ok(self.output(args)?.status.success())
+    }
+
+    /// Runs git, failing unless it exits successfully, and returns its output
+    fn run(&self, args: &[&str]) -> Result<String> {
+        let output = self.output(args)?;
+        if !output.status.success() {
+            return Err(CmdKeeperError::Git {
+                command: args[0].to_string(),
+                reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Runs git in the repository
+    fn output(&self, args: &[&str]) -> Result<Output> {
+        Command::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            .output()
+            .map_err(|e| CmdKeeperError::Git {
+                command: args[0].to_string(),
+                reason: e.to_string(),
+            })
+    }
+}
@@ -0,0 +1,336 @@
+//! Change journal of the database, for history, undo and redo
+//!
+//! Every change made through [`super::Storage::update`] appends a line to
+//! `<name>.journal.jsonl` next to the database, holding the changed entries
+//! as they were before and after. Usage statistics are not journaled.
+//!
+//! The file is only ever appended to: undoing a change appends a record that
+//! reverses it, and redoing appends one that applies it again. Walking the
+//! records in order gives the changes that can be undone and redone, like
+//! the undo stack of an editor.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CmdKeeperError, Result};
+use crate::models::{CommandDatabase, CommandEntry};
+
+/// A set of changes made together, e.g. by one command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    /// Number of the record, starting at 1
+    pub seq: u64,
+    /// When the changes were made
+    pub at: DateTime<Utc>,
+    /// Whether the changes were made directly or by undo or redo
+    #[serde(default)]
+    pub kind: Kind,
+    /// The changed entries
+    pub changes: Vec<Change>,
+}
+
+/// How the changes of a record came about
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// Made directly
+    #[default]
+    Change,
+    /// Reverting the record with the given number
+    Undo(u64),
+    /// Applying the record with the given number again
+    Redo(u64),
+}
+
+/// One entry before and after a change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Change {
+    pub id: u64,
+    /// None if the entry was added
+    pub before: Option<CommandEntry>,
+    /// None if the entry was deleted
+    pub after: Option<CommandEntry>,
+}
+
+impl Change {
+    /// Describes the change, e.g. `deleted`
+    pub fn action(&self) -> &'static str {
+        match (&self.before, &self.after) {
+            (None, _) => "added",
+            (Some(before), None) if before.deleted_at.is_some() => "purged",
+            (Some(_), None) => "deleted",
+            (Some(before), Some(after)) => match (before.deleted_at, after.deleted_at) {
+                (None, Some(_)) => "deleted",
+                (Some(_), None) => "restored",
+                _ => "edited",
+            },
+        }
+    }
+
+    /// Returns the change that undoes this one
+    fn reversed(&self) -> Change {
+        Change {
+            id: self.id,
+            before: self.after.clone(),
+            after: self.before.clone(),
+        }
+    }
+}
+
+impl Record {
+    /// Describes the record, e.g. `deleted command 5`
+    pub fn summary(&self) -> String {
+        match self.changes.as_slice() {
+            [change] => format!("{} command {}", change.action(), change.id),
+            changes => format!("changed {} commands", changes.len()),
+        }
+    }
+}
+
+/// The journal file of a database
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    /// Returns the journal of the database at `db_path`
+    ///
+    /// The name only depends on the file stem, so the journal stays with
+    /// the database across `migrate`.
+    pub fn for_database(db_path: &Path) -> Self {
+        let stem = db_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "commands".to_string());
+        Self {
+            path: db_path.with_file_name(format!("{}.journal.jsonl", stem)),
+        }
+    }
+
+    /// Reads all records, oldest first
+    pub fn read(&self) -> Result<Vec<Record>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| Ok(serde_json::from_str(line)?))
+            .collect()
+    }
+
+    /// Appends a record of the changes, if there are any
+    pub fn append(&self, kind: Kind, changes: Vec<Change>) -> Result<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let seq = self.read()?.last().map_or(1, |r| r.seq + 1);
+        let record = Record {
+            seq,
+            at: Utc::now(),
+            kind,
+            changes,
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        Ok(())
+    }
+}
+
+/// Lists the entries whose content changed from `before` to `after`,
+/// including moves in and out of the trash
+pub fn diff(before: &CommandDatabase, after: &CommandDatabase) -> Vec<Change> {
+    let mut changes: Vec<Change> = before
+        .all_entries()
+        .filter_map(|old| match find(after, old.id) {
+            Some(new) if new.same_content(old) => None,
+            new => Some(Change {
+                id: old.id,
+                before: Some(old.clone()),
+                after: new.cloned(),
+            }),
+        })
+        .collect();
+    changes.extend(
+        after
+            .all_entries()
+            .filter(|new| find(before, new.id).is_none())
+            .map(|new| Change {
+                id: new.id,
+                before: None,
+                after: Some(new.clone()),
+            }),
+    );
+    changes
+}
+
+/// Finds an entry in the list or the trash
+fn find(db: &CommandDatabase, id: u64) -> Option<&CommandEntry> {
+    db.all_entries().find(|e| e.id == id)
+}
+
+/// Returns the record undo would revert and the one redo would apply again
+pub fn undo_redo_targets(records: &[Record]) -> (Option<&Record>, Option<&Record>) {
+    let mut undo: Vec<u64> = Vec::new();
+    let mut redo: Vec<u64> = Vec::new();
+    for record in records {
+        match record.kind {
+            Kind::Change => {
+                undo.push(record.seq);
+                redo.clear();
+            }
+            Kind::Undo(seq) => {
+                undo.retain(|&s| s != seq);
+                redo.push(seq);
+            }
+            Kind::Redo(seq) => {
+                redo.retain(|&s| s != seq);
+                undo.push(seq);
+            }
+        }
+    }
+    let find = |seq: Option<&u64>| seq.and_then(|seq| records.iter().find(|r| r.seq == *seq));
+    (find(undo.last()), find(redo.last()))
+}
+
+/// Returns the changes that revert a record
+pub fn reverse(record: &Record) -> Vec<Change> {
+    record.changes.iter().rev().map(Change::reversed).collect()
+}
+
+/// Applies changes to the database
+///
+/// Fails without changing anything if an entry is no longer as the change
+/// expects, e.g. because it was edited since. Usage statistics of existing
+/// entries are kept.
+pub fn apply(db: &mut CommandDatabase, changes: &[Change]) -> Result<()> {
+    for change in changes {
+        let unchanged = match (find(db, change.id), &change.before) {
+            (None, None) => true,
+            (Some(current), Some(before)) => current.same_content(before),
+            _ => false,
+        };
+        if !unchanged {
+            return Err(CmdKeeperError::ChangedSince(change.id));
+        }
+    }
+
+    for change in changes {
+        let usage = find(db, change.id).map(|e| e.usage.clone());
+        db.entries.retain(|e| e.id != change.id);
+        db.trash.retain(|e| e.id != change.id);
+        if let Some(after) = &change.after {
+            let mut entry = after.clone();
+            if let Some(usage) = usage {
+                entry.usage = usage;
+                entry.touch();
+            }
+            db.restore(entry);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_diff_apply_and_reverse() {
+        let mut before = CommandDatabase::new();
+        before.add("ls".to_string(), String::new(), vec![]);
+        before.add("make".to_string(), String::new(), vec![]);
+        let mut after = before.clone();
+        after.update(1, Some("ls -la".to_string()), None, None);
+        after.remove_by_id(2);
+        after.add("htop".to_string(), String::new(), vec![]);
+        after.record_use(1, None);
+
+        let changes = diff(&before, &after);
+        let actions: Vec<(u64, &str)> = changes.iter().map(|c| (c.id, c.action())).collect();
+        assert_eq!(actions, vec![(1, "edited"), (2, "deleted"), (3, "added")]);
+
+        let record = Record {
+            seq: 1,
+            at: Utc::now(),
+            kind: Kind::Change,
+            changes,
+        };
+        let mut db = after.clone();
+        apply(&mut db, &reverse(&record)).unwrap();
+        let commands: Vec<&str> = db.entries.iter().map(|e| e.command.as_str()).collect();
+        assert_eq!(commands, vec!["ls", "make"]);
+        assert_eq!(db.find_by_id(1).unwrap().usage.use_count, 1);
+
+        // Reverting again finds the entries changed
+        assert!(matches!(
+            apply(&mut db, &reverse(&record)),
+            Err(CmdKeeperError::ChangedSince(_))
+        ));
+        assert!(apply(&mut db, &record.changes).is_ok());
+
+        // Trashing and restoring are changes too
+        let before = db.clone();
+        db.trash_by_id(1);
+        let changes = diff(&before, &db);
+        assert_eq!(changes[0].action(), "deleted");
+        let record = Record {
+            seq: 2,
+            at: Utc::now(),
+            kind: Kind::Change,
+            changes,
+        };
+        apply(&mut db, &reverse(&record)).unwrap();
+        assert!(db.trash.is_empty());
+        assert_eq!(db.find_by_id(1).unwrap().command, "ls -la");
+    }
+
+    #[test]
+    fn test_undo_redo_targets() {
+        let dir = tempdir().unwrap();
+        let journal = Journal::for_database(&dir.path().join("commands.json"));
+        let change = |id| {
+            vec![Change {
+                id,
+                before: None,
+                after: Some(CommandEntry::new(
+                    id,
+                    "ls".to_string(),
+                    String::new(),
+                    vec![],
+                )),
+            }]
+        };
+        let targets = |journal: &Journal| {
+            let records = journal.read().unwrap();
+            let (undo, redo) = undo_redo_targets(&records);
+            (undo.map(|r| r.seq), redo.map(|r| r.seq))
+        };
+
+        assert_eq!(targets(&journal), (None, None));
+        journal.append(Kind::Change, change(1)).unwrap();
+        journal.append(Kind::Change, change(2)).unwrap();
+        journal.append(Kind::Change, vec![]).unwrap();
+        assert_eq!(targets(&journal), (Some(2), None));
+
+        journal.append(Kind::Undo(2), change(2)).unwrap();
+        journal.append(Kind::Undo(1), change(1)).unwrap();
+        assert_eq!(targets(&journal), (None, Some(1)));
+        journal.append(Kind::Redo(1), change(1)).unwrap();
+        assert_eq!(targets(&journal), (Some(1), Some(2)));
+
+        // A new change drops what could be redone
+        journal.append(Kind::Change, change(3)).unwrap();
+        assert_eq!(targets(&journal), (Some(6), None));
+    }
+}
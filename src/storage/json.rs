@@ -0,0 +1,105 @@
+//! JSON file backend (the default)
+//!
+//! The whole database lives in a single pretty-printed JSON file. Saves are
+//! atomic: the database is written to a temporary file, flushed to disk and
+//! renamed over the old file, so a crash never leaves a truncated database
+//! behind.
+//!
+//! Files written by older versions are upgraded on load (see
+//! [`super::migrations`]); a copy of the original is kept as
+//! `commands.json.v<N>.bak`.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+
+use super::{ensure_parent_dir, file_name, migrations, Backend};
+use crate::error::{CmdKeeperError, Result};
+use crate::models::{CommandDatabase, SCHEMA_VERSION};
+
+/// Parses a database file, upgrading it to the current schema
+///
+/// Returns the database and the schema version the file had.
+pub fn parse(content: &str) -> Result<(CommandDatabase, u32)> {
+    let mut doc: serde_json::Value = serde_json::from_str(content)?;
+    let original_version = migrations::migrate(&mut doc)?;
+    Ok((serde_json::from_value(doc)?, original_version))
+}
+
+/// Stores the database as a JSON file
+pub struct JsonBackend {
+    /// Path to the JSON file
+    path: PathBuf,
+}
+
+impl JsonBackend {
+    /// Creates a backend for the given file
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Copies the database file aside before its first save in a new schema
+    ///
+    /// An existing backup for the same version is never overwritten.
+    fn backup_before_migration(&self, version: u32) -> Result<()> {
+        let backup_path =
+            self.path
+                .with_file_name(format!("{}.v{}.bak", file_name(&self.path), version));
+        if !backup_path.exists() {
+            fs::copy(&self.path, backup_path)?;
+        }
+        Ok(())
+    }
+}
+
+impl Backend for JsonBackend {
+    fn load(&self) -> Result<CommandDatabase> {
+        if !self.path.exists() {
+            return Ok(CommandDatabase::new());
+        }
+
+        let content = fs::read_to_string(&self.path)?;
+        let (db, original_version) = parse(&content).map_err(|e| match e {
+            CmdKeeperError::Json(e) => CmdKeeperError::CorruptDatabase {
+                path: self.path.display().to_string(),
+                reason: e.to_string(),
+            },
+            e => e,
+        })?;
+        if original_version != SCHEMA_VERSION {
+            // Keep the original around before the upgraded file replaces it
+            self.backup_before_migration(original_version)?;
+        }
+
+        Ok(db)
+    }
+
+    fn save(&self, db: &CommandDatabase) -> Result<()> {
+        let dir = ensure_parent_dir(&self.path)?;
+
+        let content = serde_json::to_string_pretty(db)?;
+        let tmp_path = dir.join(format!(
+            ".{}.tmp-{}",
+            file_name(&self.path),
+            std::process::id()
+        ));
+
+        let write_result = (|| -> Result<()> {
+            let mut file = File::create(&tmp_path)?;
+            file.write_all(content.as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, &self.path)?;
+            Ok(())
+        })();
+        if write_result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        write_result?;
+
+        // Make the rename itself durable
+        #[cfg(unix)]
+        File::open(&dir)?.sync_all()?;
+
+        Ok(())
+    }
+}
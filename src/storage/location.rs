@@ -0,0 +1,186 @@
+//! Choosing which database to use
+//!
+//! In order of precedence, the database is:
+//! 1. the file given with `--db`
+//! 2. the profile given with `--profile`, kept in
+//!    `~/.config/cmd-keeper/profiles/<name>/`
+//! 3. the file in the `CMD_KEEPER_DB` environment variable
+//! 4. the default database in `~/.config/cmd-keeper/`
+//!
+//! Profiles and the default location use `commands.db` (SQLite) if it
+//! exists there and `commands.json` otherwise; explicit paths are used
+//! as-is.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::{Storage, DB_FILENAME, SQLITE_FILENAME};
+use crate::error::{CmdKeeperError, Result};
+
+/// Environment variable naming the database file
+pub const DB_ENV: &str = "CMD_KEEPER_DB";
+
+/// Directory of the profiles under the app directory
+const PROFILES_DIR: &str = "profiles";
+
+/// Database chosen on the command line, applied to every [`super::Storage::new`]
+static SELECTED: OnceLock<Selection> = OnceLock::new();
+
+/// The `--db` and `--profile` flags
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    pub db: Option<PathBuf>,
+    pub profile: Option<String>,
+}
+
+/// Why a database was chosen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// The `--db` flag
+    Flag,
+    /// The `--profile` flag
+    Profile(String),
+    /// The `CMD_KEEPER_DB` environment variable
+    Env,
+    /// Nothing was given
+    Default,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Flag => write!(f, "given with --db"),
+            Self::Profile(name) => write!(f, "profile '{}' selected with --profile", name),
+            Self::Env => write!(f, "set in the {} environment variable", DB_ENV),
+            Self::Default => write!(f, "default location (no --db, --profile or {})", DB_ENV),
+        }
+    }
+}
+
+/// A resolved database file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub origin: Origin,
+}
+
+/// Sets the database for the rest of the process; only the first call has
+/// an effect
+pub fn select(selection: Selection) {
+    let _ = SELECTED.set(selection);
+}
+
+/// Resolves the database to use from the flags given to [`select`] and the
+/// environment
+pub fn current() -> Result<Location> {
+    let selection = SELECTED.get().cloned().unwrap_or_default();
+    let env = std::env::var_os(DB_ENV).filter(|value| !value.is_empty());
+    resolve(&selection, env.map(PathBuf::from), Storage::app_dir)
+}
+
+/// Applies the order of precedence described in the module documentation
+///
+/// The app directory is only looked up when a profile or the default is used.
+fn resolve(
+    selection: &Selection,
+    env: Option<PathBuf>,
+    app_dir: impl FnOnce() -> Result<PathBuf>,
+) -> Result<Location> {
+    if let Some(path) = &selection.db {
+        return Ok(Location {
+            path: path.clone(),
+            origin: Origin::Flag,
+        });
+    }
+    if let Some(name) = &selection.profile {
+        validate_profile(name)?;
+        return Ok(Location {
+            path: in_dir(&app_dir()?.join(PROFILES_DIR).join(name)),
+            origin: Origin::Profile(name.clone()),
+        });
+    }
+    if let Some(path) = env {
+        return Ok(Location {
+            path,
+            origin: Origin::Env,
+        });
+    }
+    Ok(Location {
+        path: in_dir(&app_dir()?),
+        origin: Origin::Default,
+    })
+}
+
+/// Picks the database file in a directory, preferring SQLite if present
+fn in_dir(dir: &Path) -> PathBuf {
+    let sqlite_path = dir.join(SQLITE_FILENAME);
+    if sqlite_path.exists() {
+        sqlite_path
+    } else {
+        dir.join(DB_FILENAME)
+    }
+}
+
+/// Checks that a profile name can be used as a directory name
+fn validate_profile(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\'])
+        && !name.chars().any(char::is_control);
+    if valid {
+        Ok(())
+    } else {
+        Err(CmdKeeperError::InvalidProfile(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_precedence() {
+        let dir = tempdir().unwrap();
+        let app_dir = || Ok(dir.path().to_path_buf());
+        let env = Some(PathBuf::from("/tmp/env.json"));
+        let flags = |db: Option<&str>, profile: Option<&str>| Selection {
+            db: db.map(PathBuf::from),
+            profile: profile.map(str::to_string),
+        };
+
+        let location = resolve(&flags(Some("a.db"), None), env.clone(), app_dir).unwrap();
+        assert_eq!(location.path, PathBuf::from("a.db"));
+        assert_eq!(location.origin, Origin::Flag);
+
+        let location = resolve(&flags(None, Some("work")), env.clone(), app_dir).unwrap();
+        assert_eq!(
+            location.path,
+            dir.path().join("profiles/work").join(DB_FILENAME)
+        );
+        assert_eq!(location.origin, Origin::Profile("work".to_string()));
+
+        let location = resolve(&flags(None, None), env, app_dir).unwrap();
+        assert_eq!(location.origin, Origin::Env);
+
+        std::fs::write(dir.path().join(SQLITE_FILENAME), "").unwrap();
+        let location = resolve(&flags(None, None), None, app_dir).unwrap();
+        assert_eq!(location.path, dir.path().join(SQLITE_FILENAME));
+        assert_eq!(location.origin, Origin::Default);
+    }
+
+    #[test]
+    fn test_invalid_profile_names() {
+        let dir = tempdir().unwrap();
+        let app_dir = || Ok(dir.path().to_path_buf());
+        for name in ["", "..", "a/b", "a\\b"] {
+            let selection = Selection {
+                db: None,
+                profile: Some(name.to_string()),
+            };
+            assert!(resolve(&selection, None, app_dir).is_err(), "{:?}", name);
+        }
+    }
+}
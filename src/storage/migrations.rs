@@ -0,0 +1,167 @@
+//! Schema migrations for the database file
+//!
+//! Each step upgrades the raw JSON from one schema version to the next, so a
+//! file of any older version is brought up to date by running the steps in
+//! order. Files written before versioning was introduced have no
+//! `schema_version` field and are treated as version 1.
+
+use serde_json::{Map, Value};
+
+use crate::error::{CmdKeeperError, Result};
+use crate::models::SCHEMA_VERSION;
+
+/// Version assumed for files without a `schema_version` field
+const UNVERSIONED: u32 = 1;
+
+/// A single upgrade step from version `n` to `n + 1`
+type Step = fn(&mut Map<String, Value>) -> std::result::Result<(), String>;
+
+/// Upgrade steps; `STEPS[i]` upgrades from version `i + 1` to `i + 2`
+const STEPS: &[Step] = &[v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6];
+
+/// Returns the schema version of a raw database document
+fn version_of(doc: &Value) -> Result<u32> {
+    match doc.get("schema_version") {
+        None => Ok(UNVERSIONED),
+        Some(v) => {
+            let found = v
+                .as_u64()
+                .filter(|&n| n >= u64::from(UNVERSIONED))
+                .ok_or_else(|| CmdKeeperError::Migration {
+                    from: 0,
+                    reason: format!("invalid schema_version {}", v),
+                })?;
+            if found > u64::from(SCHEMA_VERSION) {
+                return Err(CmdKeeperError::UnsupportedSchemaVersion {
+                    found,
+                    supported: SCHEMA_VERSION,
+                });
+            }
+            Ok(found as u32)
+        }
+    }
+}
+
+/// Upgrades a raw database document to the current schema version
+///
+/// Returns the version the document had before migrating.
+pub fn migrate(doc: &mut Value) -> Result<u32> {
+    let original = version_of(doc)?;
+
+    let Some(map) = doc.as_object_mut() else {
+        return Err(CmdKeeperError::Migration {
+            from: original,
+            reason: "database is not a JSON object".to_string(),
+        });
+    };
+
+    for version in original..SCHEMA_VERSION {
+        let step = STEPS[(version - UNVERSIONED) as usize];
+        step(map).map_err(|reason| CmdKeeperError::Migration {
+            from: version,
+            reason,
+        })?;
+        map.insert("schema_version".to_string(), Value::from(version + 1));
+    }
+
+    Ok(original)
+}
+
+/// v1 -> v2: introduces `schema_version`
+///
+/// Version 1 files could lack `tags` on entries and, if edited by hand,
+/// `next_id`. Both are filled in so that every v2 file is complete.
+fn v1_to_v2(db: &mut Map<String, Value>) -> std::result::Result<(), String> {
+    let entries = db
+        .entry("entries")
+        .or_insert_with(|| Value::Array(Vec::new()))
+        .as_array_mut()
+        .ok_or("`entries` is not an array")?;
+
+    let mut max_id = 0;
+    for entry in entries.iter_mut() {
+        let entry = entry.as_object_mut().ok_or("entry is not an object")?;
+        entry
+            .entry("tags")
+            .or_insert_with(|| Value::Array(Vec::new()));
+        max_id = max_id.max(entry.get("id").and_then(Value::as_u64).unwrap_or(0));
+    }
+
+    db.entry("next_id")
+        .or_insert_with(|| Value::from(max_id + 1));
+    Ok(())
+}
+
+/// v2 -> v3: introduces per-entry `usage` statistics
+///
+/// Missing statistics mean the command was never used, so there is nothing
+/// to fill in; the version bump keeps older builds from dropping them.
+fn v2_to_v3(_db: &mut Map<String, Value>) -> std::result::Result<(), String> {
+    Ok(())
+}
+
+/// v3 -> v4: adds run and failure counts to `usage`
+///
+/// Runs before this version were not counted, so the counts start at zero.
+fn v3_to_v4(_db: &mut Map<String, Value>) -> std::result::Result<(), String> {
+    Ok(())
+}
+
+/// v4 -> v5: adds `updated_at` to entries, used to merge edits on sync
+///
+/// Entries without it are treated as unchanged since they were created.
+fn v4_to_v5(_db: &mut Map<String, Value>) -> std::result::Result<(), String> {
+    Ok(())
+}
+
+/// v5 -> v6: adds the `trash` of deleted entries
+///
+/// Entries deleted before this version are gone, so the trash starts empty.
+fn v5_to_v6(_db: &mut Map<String, Value>) -> std::result::Result<(), String> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_migrate_unversioned_file() {
+        let mut doc = json!({
+            "entries": [
+                {"id": 4, "command": "ls", "description": "list", "created_at": "2024-01-15T10:30:00Z"}
+            ]
+        });
+
+        assert_eq!(migrate(&mut doc).unwrap(), 1);
+        assert_eq!(doc["schema_version"], json!(SCHEMA_VERSION));
+        assert_eq!(doc["next_id"], json!(5));
+        assert_eq!(doc["entries"][0]["tags"], json!([]));
+    }
+
+    #[test]
+    fn test_current_version_is_unchanged() {
+        let mut doc = json!({"schema_version": SCHEMA_VERSION, "next_id": 1, "entries": []});
+        let before = doc.clone();
+
+        assert_eq!(migrate(&mut doc).unwrap(), SCHEMA_VERSION);
+        assert_eq!(doc, before);
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let mut doc = json!({"schema_version": SCHEMA_VERSION + 1, "next_id": 1, "entries": []});
+
+        let err = migrate(&mut doc).unwrap_err();
+        assert!(matches!(
+            err,
+            CmdKeeperError::UnsupportedSchemaVersion { .. }
+        ));
+    }
+
+    #[test]
+    fn test_every_version_has_a_step() {
+        assert_eq!(STEPS.len() as u32, SCHEMA_VERSION - UNVERSIONED);
+    }
+}
@@ -0,0 +1,831 @@
+//! Storage layer for cmd-keeper
+//!
+//! Handles loading and saving the command database to the filesystem.
+//!
+//! The database can live in a JSON file (the default) or in a SQLite file;
+//! both implement [`Backend`] and the one to use is chosen from the file
+//! extension. Read-modify-write cycles from different processes are
+//! serialized with an advisory lock on a sidecar `.lock` file.
+//!
+//! Next to the global database, [`Storage`] picks up the project file found
+//! from the current directory (see [`project`]). Lookups cover both; writes
+//! go to the one the entry belongs to.
+//!
+//! Which global database is used can be changed with `--db`, `--profile` or
+//! `CMD_KEEPER_DB` (see [`location`]).
+//!
+//! Changes made through [`Storage::update`] are recorded in a journal next
+//! to the database (see [`journal`]), which backs history, undo and redo.
+//!
+//! Every save first copies the previous file to a rotating backup (see
+//! [`backup`]).
+//!
+//! Once `cmd-keeper sync` has made the database directory a git repository
+//! (see [`git`]), every save of a JSON database is committed.
+//!
+//! Updates that only change usage statistics, like copying or running a
+//! command, are written without a backup or a commit, so they don't push
+//! real changes out of the rotation.
+//!
+//! With SQLite, adding a command and recording a use write just that entry
+//! (see [`Storage::add`]), so they don't grow with the collection; they
+//! take no backup either, as that would copy the whole file.
+
+pub mod backup;
+pub mod git;
+pub mod journal;
+mod json;
+pub mod location;
+mod migrations;
+pub mod project;
+pub mod salvage;
+mod sqlite;
+
+use std::cell::Cell;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use clap::ValueEnum;
+
+use crate::error::{CmdKeeperError, Result};
+use crate::models::{CommandDatabase, CommandEntry, EntryRef, Source};
+use crate::tags;
+
+use backup::Backups;
+use journal::{Change, Journal, Kind, Record};
+use json::JsonBackend;
+use project::ProjectFile;
+use sqlite::SqliteBackend;
+
+/// Default filename for the JSON database
+const DB_FILENAME: &str = "commands.json";
+
+/// Default filename for the SQLite database
+const SQLITE_FILENAME: &str = "commands.db";
+
+/// Default directory name under config
+const APP_DIR: &str = "cmd-keeper";
+
+/// A place the command database can be stored
+///
+/// Only `load` and `save` are required. The lookups and single-entry writes
+/// have defaults that load the whole database; backends with indexes
+/// override them.
+pub trait Backend {
+    /// Loads the whole database, returning an empty one if nothing is stored
+    fn load(&self) -> Result<CommandDatabase>;
+
+    /// Stores the database, replacing what was there
+    fn save(&self, db: &CommandDatabase) -> Result<()>;
+
+    /// Returns the ID the next new entry gets
+    fn next_id(&self) -> Result<u64> {
+        Ok(self.load()?.next_id())
+    }
+
+    /// Stores one listed entry, adding it or replacing the one with its ID
+    fn save_entry(&self, entry: &CommandEntry) -> Result<()> {
+        let mut db = self.load()?;
+        match db.find_by_id_mut(entry.id) {
+            Some(existing) => *existing = entry.clone(),
+            None => db.insert(entry.clone()),
+        }
+        self.save(&db)
+    }
+
+    /// Finds an entry by ID
+    fn find_by_id(&self, id: u64) -> Result<Option<CommandEntry>> {
+        Ok(self.load()?.find_by_id(id).cloned())
+    }
+
+    /// Returns the entries carrying a tag or one below it (case-insensitive)
+    fn find_by_tag(&self, tag: &str) -> Result<Vec<CommandEntry>> {
+        Ok(self
+            .load()?
+            .entries
+            .into_iter()
+            .filter(|e| e.tags.iter().any(|t| tags::matches(t, tag)))
+            .collect())
+    }
+
+    /// Returns the entries whose command, description or tags contain the
+    /// text (case-insensitive)
+    fn search_text(&self, text: &str) -> Result<Vec<CommandEntry>> {
+        Ok(self
+            .load()?
+            .search_exact(text)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+}
+
+/// Available storage backends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BackendKind {
+    /// A single JSON file
+    Json,
+    /// A SQLite database with indexed lookups and full-text search
+    Sqlite,
+}
+
+impl BackendKind {
+    /// Picks the backend for a file from its extension
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("db" | "sqlite" | "sqlite3") => Self::Sqlite,
+            _ => Self::Json,
+        }
+    }
+
+    /// Default file name for this backend
+    pub fn default_file_name(self) -> &'static str {
+        match self {
+            Self::Json => DB_FILENAME,
+            Self::Sqlite => SQLITE_FILENAME,
+        }
+    }
+}
+
+impl std::fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json => write!(f, "json"),
+            Self::Sqlite => write!(f, "sqlite"),
+        }
+    }
+}
+
+/// Identifies a version of the database file on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl FileStamp {
+    /// Reads the stamp of a file, or None if it doesn't exist
+    fn of(path: &Path) -> Result<Option<Self>> {
+        match fs::metadata(path) {
+            Ok(meta) => Ok(Some(Self {
+                modified: meta.modified()?,
+                len: meta.len(),
+            })),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Advisory lock on the database, released when dropped
+pub struct StorageLock {
+    _file: File,
+}
+
+/// Storage handler for the command database
+pub struct Storage {
+    /// Path to the database file
+    db_path: PathBuf,
+    /// Backend reading and writing the file
+    backend: Box<dyn Backend>,
+    /// Stamp of the file as last loaded or saved by this instance
+    last_stamp: Cell<Option<FileStamp>>,
+    /// Project file merged with the database, if any
+    project: Option<ProjectFile>,
+}
+
+impl Storage {
+    /// Creates a new Storage instance for the selected database
+    ///
+    /// Unless another one is selected (see [`location`]), the database is
+    /// stored at `~/.config/cmd-keeper/commands.json` on Linux/macOS or the
+    /// equivalent config directory on other platforms. If a `commands.db`
+    /// SQLite database exists there (see `cmd-keeper migrate`), it is used
+    /// instead.
+    ///
+    /// The project file closest to the current directory, if there is one,
+    /// is merged in.
+    pub fn new() -> Result<Self> {
+        let storage = Self::with_path(location::current()?.path);
+        let project = std::env::current_dir()
+            .ok()
+            .and_then(|dir| ProjectFile::discover(&dir));
+        Ok(match project {
+            Some(project) => storage.with_project(project),
+            None => storage,
+        })
+    }
+
+    /// Returns the directory holding cmd-keeper's data
+    pub fn app_dir() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir().ok_or(CmdKeeperError::ConfigDirNotFound)?;
+        Ok(config_dir.join(APP_DIR))
+    }
+
+    /// Creates a Storage instance with a custom path
+    ///
+    /// The backend is chosen from the extension: `.db`, `.sqlite` and
+    /// `.sqlite3` use SQLite, anything else JSON.
+    pub fn with_path(path: PathBuf) -> Self {
+        let backend: Box<dyn Backend> = match BackendKind::from_path(&path) {
+            BackendKind::Json => Box::new(JsonBackend::new(path.clone())),
+            BackendKind::Sqlite => Box::new(SqliteBackend::new(path.clone())),
+        };
+
+        Self {
+            db_path: path,
+            backend,
+            last_stamp: Cell::new(None),
+            project: None,
+        }
+    }
+
+    /// Merges the given project file in
+    pub fn with_project(mut self, project: ProjectFile) -> Self {
+        self.project = Some(project);
+        self
+    }
+
+    /// Returns the project file merged in, if any
+    pub fn project(&self) -> Option<&ProjectFile> {
+        self.project.as_ref()
+    }
+
+    /// Returns the project file, starting a `.cmd-keeper.json` in the
+    /// current directory if none was found
+    pub fn project_or_create(&mut self) -> Result<&ProjectFile> {
+        if self.project.is_none() {
+            let path = std::env::current_dir()?.join(project::FILE_NAMES[0]);
+            self.project = Some(ProjectFile::new(path));
+        }
+        Ok(self.project.as_ref().expect("project file was just set"))
+    }
+
+    /// Returns the path to the database file
+    pub fn db_path(&self) -> &PathBuf {
+        &self.db_path
+    }
+
+    /// Returns the kind of backend in use
+    pub fn kind(&self) -> BackendKind {
+        BackendKind::from_path(&self.db_path)
+    }
+
+    /// Loads the global command database from disk
+    ///
+    /// If the file doesn't exist, returns an empty database.
+    pub fn load(&self) -> Result<CommandDatabase> {
+        // Take the stamp first so a concurrent write shows up as a change later
+        let stamp = FileStamp::of(&self.db_path)?;
+        let db = self.backend.load()?;
+        self.last_stamp.set(stamp);
+        Ok(db)
+    }
+
+    /// Loads the global database like [`Storage::load`], but without
+    /// counting it as seen by [`Storage::changed_on_disk`]
+    ///
+    /// For lookups by a caller that keeps an older copy around, which must
+    /// still be refreshed when another process changed the file.
+    pub fn peek(&self) -> Result<CommandDatabase> {
+        self.backend.load()
+    }
+
+    /// Saves the command database to disk
+    ///
+    /// Creates the parent directory if it doesn't exist, and backs up the
+    /// previous file. Callers that loaded the database first should hold the
+    /// lock (see [`Storage::update`]).
+    pub fn save(&self, db: &CommandDatabase) -> Result<()> {
+        self.backups().take(&self.db_path)?;
+        self.write(db)?;
+        if let Some(repo) = self.sync_repo() {
+            // The change is saved either way; `sync` commits whatever is left
+            let _ = repo.commit_file(&file_name(&self.db_path), "Update commands");
+        }
+        Ok(())
+    }
+
+    /// Saves the database without a backup or a sync commit, for changes to
+    /// usage statistics only
+    fn write(&self, db: &CommandDatabase) -> Result<()> {
+        self.backend.save(db)?;
+        self.last_stamp.set(FileStamp::of(&self.db_path)?);
+        Ok(())
+    }
+
+    /// Returns the git repository set up by `sync` for a JSON database
+    fn sync_repo(&self) -> Option<git::Repo> {
+        if self.kind() != BackendKind::Json {
+            return None;
+        }
+        git::Repo::open_synced(&parent_dir(&self.db_path))
+    }
+
+    /// Loads the global database with the project entries appended
+    ///
+    /// The result is for reading only; changes are saved through
+    /// [`Storage::update_in`].
+    pub fn load_merged(&self) -> Result<CommandDatabase> {
+        let mut db = self.load()?;
+        db.entries.extend(self.load_project()?.entries);
+        Ok(db)
+    }
+
+    /// Loads the project entries, or none without a project file
+    fn load_project(&self) -> Result<CommandDatabase> {
+        match &self.project {
+            Some(project) => project.load(),
+            None => Ok(CommandDatabase::new()),
+        }
+    }
+
+    /// Finds an entry in the global database or the project file
+    pub fn find(&self, entry: EntryRef) -> Result<Option<CommandEntry>> {
+        match entry.source {
+            Source::Global => self.backend.find_by_id(entry.id),
+            Source::Project => {
+                let project = self.project.as_ref().ok_or(CmdKeeperError::NoProjectFile)?;
+                Ok(project.load()?.find_by_id(entry.id).cloned())
+            }
+        }
+    }
+
+    /// Returns the entries carrying a tag or one below it (case-insensitive),
+    /// global ones first
+    pub fn find_by_tag(&self, tag: &str) -> Result<Vec<CommandEntry>> {
+        let mut entries = self.backend.find_by_tag(tag)?;
+        entries.extend(
+            self.load_project()?
+                .entries
+                .into_iter()
+                .filter(|e| e.tags.iter().any(|t| tags::matches(t, tag))),
+        );
+        Ok(entries)
+    }
+
+    /// Returns the entries containing the text (case-insensitive), global
+    /// ones first
+    pub fn search_text(&self, text: &str) -> Result<Vec<CommandEntry>> {
+        let mut entries = self.backend.search_text(text)?;
+        entries.extend(self.load_project()?.search_exact(text).into_iter().cloned());
+        Ok(entries)
+    }
+
+    /// Takes the advisory lock on the database, blocking until it is free
+    pub fn lock(&self) -> Result<StorageLock> {
+        ensure_parent_dir(&self.db_path)?;
+
+        let lock_path = self
+            .db_path
+            .with_file_name(format!("{}.lock", file_name(&self.db_path)));
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(lock_path)?;
+        file.lock()?;
+        Ok(StorageLock { _file: file })
+    }
+
+    /// Loads, modifies and saves the database while holding the lock
+    ///
+    /// The database is re-read from disk under the lock, so changes made by
+    /// other processes are never overwritten. Nothing is saved if `f` fails.
+    /// If only usage statistics changed, no backup or sync commit is made.
+    pub fn update<T, E>(
+        &self,
+        f: impl FnOnce(&mut CommandDatabase) -> std::result::Result<T, E>,
+    ) -> std::result::Result<T, E>
+    where
+        E: From<CmdKeeperError>,
+    {
+        let _lock = self.lock()?;
+        let mut db = self.load()?;
+        let before = db.clone();
+        let result = f(&mut db)?;
+        let changes = journal::diff(&before, &db);
+        if changes.is_empty() && db.next_id() == before.next_id() {
+            self.write(&db)?;
+        } else {
+            self.save(&db)?;
+        }
+        self.journal().append(Kind::Change, changes)?;
+        Ok(result)
+    }
+
+    /// Returns the change journal of the global database
+    pub fn journal(&self) -> Journal {
+        Journal::for_database(&self.db_path)
+    }
+
+    /// Returns the backups of the global database
+    pub fn backups(&self) -> Backups {
+        Backups::for_database(&self.db_path)
+    }
+
+    /// Replaces the database as a whole while holding the lock, e.g. with a
+    /// backup
+    ///
+    /// Works even if the current file can't be read; it is backed up like on
+    /// every save. The change is journaled if the current file was readable.
+    pub fn replace(&self, db: &CommandDatabase) -> Result<()> {
+        let _lock = self.lock()?;
+        let before = self.load();
+        self.save(db)?;
+        if let Ok(before) = before {
+            self.journal()
+                .append(Kind::Change, journal::diff(&before, db))?;
+        }
+        Ok(())
+    }
+
+    /// Reverts the last change that wasn't undone, returning it, or None if
+    /// there is nothing to undo
+    pub fn undo(&self) -> Result<Option<Record>> {
+        self.step_journal(true)
+    }
+
+    /// Applies the last undone change again, returning it, or None if there
+    /// is nothing to redo
+    pub fn redo(&self) -> Result<Option<Record>> {
+        self.step_journal(false)
+    }
+
+    /// Undoes or redoes a journal record while holding the lock
+    fn step_journal(&self, undo: bool) -> Result<Option<Record>> {
+        let _lock = self.lock()?;
+        let journal = self.journal();
+        let records = journal.read()?;
+        let (undo_target, redo_target) = journal::undo_redo_targets(&records);
+        let (target, changes, kind) = match (undo, undo_target, redo_target) {
+            (true, Some(target), _) => (target, journal::reverse(target), Kind::Undo(target.seq)),
+            (false, _, Some(target)) => (target, target.changes.clone(), Kind::Redo(target.seq)),
+            _ => return Ok(None),
+        };
+
+        let mut db = self.load()?;
+        journal::apply(&mut db, &changes)?;
+        self.save(&db)?;
+        journal.append(kind, changes)?;
+        Ok(Some(target.clone()))
+    }
+
+    /// Like [`Storage::update`], but on the global database or the project
+    /// file
+    ///
+    /// The project file is not locked, as it is edited by hand as well.
+    pub fn update_in<T, E>(
+        &self,
+        source: Source,
+        f: impl FnOnce(&mut CommandDatabase) -> std::result::Result<T, E>,
+    ) -> std::result::Result<T, E>
+    where
+        E: From<CmdKeeperError>,
+    {
+        match source {
+            Source::Global => self.update(f),
+            Source::Project => {
+                let project = self.project.as_ref().ok_or(CmdKeeperError::NoProjectFile)?;
+                let mut db = project.load()?;
+                let result = f(&mut db)?;
+                project.save(&db)?;
+                Ok(result)
+            }
+        }
+    }
+
+    /// Adds an entry under the next free ID to the global database or the
+    /// project file, returning where it went
+    ///
+    /// The ID `entry` comes with is replaced. Backends that write entries
+    /// one by one store just this entry, journaled like any other change.
+    pub fn add(&self, source: Source, mut entry: CommandEntry) -> Result<EntryRef> {
+        if source == Source::Global && self.is_indexed() {
+            let _lock = self.lock()?;
+            entry.id = self.backend.next_id()?;
+            self.backend.save_entry(&entry)?;
+            let id = entry.id;
+            self.journal().append(
+                Kind::Change,
+                vec![Change {
+                    id,
+                    before: None,
+                    after: Some(entry),
+                }],
+            )?;
+            return Ok(EntryRef { source, id });
+        }
+
+        let id = self.update_in(source, |db| {
+            entry.id = db.next_id();
+            let id = entry.id;
+            db.insert(entry);
+            Ok::<_, CmdKeeperError>(id)
+        })?;
+        Ok(EntryRef { source, id })
+    }
+
+    /// Returns true if the backend has indexes for lookups and writes
+    /// single entries without rewriting the database
+    pub fn is_indexed(&self) -> bool {
+        self.kind() == BackendKind::Sqlite
+    }
+
+    /// Records that an entry was copied or run (see
+    /// [`CommandEntry::record_use`])
+    ///
+    /// Project entries keep no usage statistics, so nothing is recorded for
+    /// them.
+    pub fn record_use(&self, entry: EntryRef, exit_code: Option<i32>) -> Result<()> {
+        if entry.source == Source::Project {
+            return Ok(());
+        }
+        if self.is_indexed() {
+            let _lock = self.lock()?;
+            if let Some(mut found) = self.backend.find_by_id(entry.id)? {
+                found.record_use(exit_code);
+                self.backend.save_entry(&found)?;
+            }
+            return Ok(());
+        }
+        self.update(|db| {
+            db.record_use(entry.id, exit_code);
+            Ok(())
+        })
+    }
+
+    /// Returns true if the database or the project file changed on disk
+    /// since this instance last loaded or saved it
+    pub fn changed_on_disk(&self) -> Result<bool> {
+        let project_changed = match &self.project {
+            Some(project) => project.changed_on_disk()?,
+            None => false,
+        };
+        Ok(project_changed || FileStamp::of(&self.db_path)? != self.last_stamp.get())
+    }
+}
+
+/// Returns the file name of a path as a string
+pub fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| DB_FILENAME.to_string())
+}
+
+/// Parses the content of a JSON database file, upgrading older schemas
+pub fn parse_json(content: &str) -> Result<CommandDatabase> {
+    Ok(json::parse(content)?.0)
+}
+
+/// Returns the directory holding a file
+pub fn parent_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Creates the directory holding a file and returns it
+fn ensure_parent_dir(path: &Path) -> Result<PathBuf> {
+    let dir = parent_dir(path);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SCHEMA_VERSION;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_nonexistent() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.json");
+        let storage = Storage::with_path(path);
+
+        let db = storage.load().unwrap();
+        assert!(db.entries.is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.json");
+        let storage = Storage::with_path(path);
+
+        let mut db = CommandDatabase::new();
+        db.add("test cmd".to_string(), "test desc".to_string(), vec![]);
+
+        storage.save(&db).unwrap();
+
+        let loaded_db = storage.load().unwrap();
+        assert_eq!(loaded_db.entries.len(), 1);
+        assert_eq!(loaded_db.entries[0].command, "test cmd");
+    }
+
+    #[test]
+    fn test_load_migrates_and_keeps_backup() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.json");
+        let original = r#"{"next_id": 2, "entries": [
+            {"id": 1, "command": "ls", "description": "list", "created_at": "2024-01-15T10:30:00Z"}
+        ]}"#;
+        fs::write(&path, original).unwrap();
+
+        let storage = Storage::with_path(path.clone());
+        let db = storage.load().unwrap();
+        assert_eq!(db.schema_version, SCHEMA_VERSION);
+        assert_eq!(db.entries[0].command, "ls");
+
+        let backup = dir.path().join("test.json.v1.bak");
+        assert_eq!(fs::read_to_string(backup).unwrap(), original);
+
+        storage.save(&db).unwrap();
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["schema_version"], SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_load_rejects_newer_schema() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.json");
+        fs::write(
+            &path,
+            r#"{"schema_version": 999, "next_id": 1, "entries": []}"#,
+        )
+        .unwrap();
+
+        let err = Storage::with_path(path).load().unwrap_err();
+        assert!(matches!(
+            err,
+            CmdKeeperError::UnsupportedSchemaVersion { found: 999, .. }
+        ));
+    }
+
+    #[test]
+    fn test_save_leaves_no_temp_files() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_path(dir.path().join("test.json"));
+
+        storage.save(&CommandDatabase::new()).unwrap();
+        storage.save(&CommandDatabase::new()).unwrap();
+
+        let mut names: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["backups".to_string(), "test.json".to_string()]);
+    }
+
+    #[test]
+    fn test_recording_use_takes_no_backup() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_path(dir.path().join("test.json"));
+        for command in ["ls", "make"] {
+            storage
+                .update(|db| {
+                    db.add(command.to_string(), String::new(), vec![]);
+                    Ok::<_, CmdKeeperError>(())
+                })
+                .unwrap();
+        }
+        let backups = storage.backups().list().unwrap();
+        assert_eq!(backups.len(), 1);
+
+        storage.record_use(EntryRef::global(1), Some(0)).unwrap();
+        assert_eq!(storage.load().unwrap().entries[0].usage.use_count, 1);
+        let paths: Vec<PathBuf> = storage
+            .backups()
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|b| b.path)
+            .collect();
+        assert_eq!(paths, vec![backups[0].path.clone()]);
+    }
+
+    #[test]
+    fn test_sqlite_adds_single_entries() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_path(dir.path().join("test.db"));
+        for command in ["ls", "make"] {
+            let entry = CommandEntry::new(0, command.to_string(), String::new(), vec![]);
+            storage.add(Source::Global, entry).unwrap();
+        }
+        storage.record_use(EntryRef::global(2), Some(1)).unwrap();
+
+        let db = storage.load().unwrap();
+        assert_eq!(db.find_by_id(2).unwrap().command, "make");
+        assert_eq!(db.find_by_id(2).unwrap().usage.failure_count, 1);
+        assert_eq!(db.next_id(), 3);
+        // Each add is journaled on its own, without copying the file
+        let records = storage.journal().read().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].changes.len(), 1);
+        assert_eq!(records[1].changes[0].id, 2);
+        assert!(storage.backups().list().unwrap().is_empty());
+
+        storage.undo().unwrap();
+        assert!(storage.load().unwrap().find_by_id(2).is_none());
+    }
+
+    #[test]
+    fn test_concurrent_updates_are_not_lost() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.json");
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let storage = Storage::with_path(path);
+                    storage
+                        .update(|db| {
+                            db.add(format!("cmd {}", i), String::new(), vec![]);
+                            Ok::<_, CmdKeeperError>(())
+                        })
+                        .unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let db = Storage::with_path(path).load().unwrap();
+        assert_eq!(db.entries.len(), 8);
+    }
+
+    #[test]
+    fn test_project_entries_are_merged_and_routed() {
+        let dir = tempdir().unwrap();
+        let project_path = dir.path().join(".cmd-keeper.json");
+        fs::write(
+            &project_path,
+            r#"{"entries": [{"command": "make deploy", "tags": ["ops"]}]}"#,
+        )
+        .unwrap();
+        let storage = Storage::with_path(dir.path().join("commands.json"))
+            .with_project(ProjectFile::new(project_path.clone()));
+        storage
+            .update(|db| {
+                db.add("ls".to_string(), String::new(), vec!["ops".to_string()]);
+                Ok::<_, CmdKeeperError>(())
+            })
+            .unwrap();
+
+        let refs: Vec<String> = storage
+            .load_merged()
+            .unwrap()
+            .entries
+            .iter()
+            .map(|e| e.reference().to_string())
+            .collect();
+        assert_eq!(refs, vec!["1", "p1"]);
+        assert_eq!(storage.find_by_tag("ops").unwrap().len(), 2);
+        let project_ref: EntryRef = "p1".parse().unwrap();
+        assert_eq!(
+            storage.find(project_ref).unwrap().unwrap().command,
+            "make deploy"
+        );
+
+        // Using a project entry leaves the file alone; removing it edits it
+        let before = fs::read_to_string(&project_path).unwrap();
+        storage.record_use(project_ref, Some(0)).unwrap();
+        assert_eq!(fs::read_to_string(&project_path).unwrap(), before);
+        storage
+            .update_in(Source::Project, |db| {
+                db.remove_by_id(1);
+                Ok::<_, CmdKeeperError>(())
+            })
+            .unwrap();
+        assert!(storage.find(project_ref).unwrap().is_none());
+        assert_eq!(storage.load().unwrap().entries.len(), 1);
+    }
+
+    #[test]
+    fn test_changed_on_disk() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.json");
+        let tui = Storage::with_path(path.clone());
+        let cli = Storage::with_path(path);
+
+        tui.save(&CommandDatabase::new()).unwrap();
+        assert!(!tui.changed_on_disk().unwrap());
+
+        cli.update(|db| {
+            db.add("ls".to_string(), String::new(), vec![]);
+            Ok::<_, CmdKeeperError>(())
+        })
+        .unwrap();
+        assert!(tui.changed_on_disk().unwrap());
+
+        tui.load().unwrap();
+        assert!(!tui.changed_on_disk().unwrap());
+    }
+}
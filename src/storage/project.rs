@@ -0,0 +1,161 @@
+//! Project-local command files
+//!
+//! A repository can keep its own commands in a `.cmd-keeper.json` or
+//! `.cmd-keeper.toml` file, found by walking up from the current directory.
+//! The file uses the export format (see [`crate::formats`]), so it can be
+//! written by hand and committed:
+//!
+//! ```toml
+//! [[entries]]
+//! command = "cargo xtask dist"
+//! description = "Build the release archives"
+//! tags = ["build"]
+//! ```
+//!
+//! Entries without an ID are numbered when the file is loaded and keep that
+//! number once cmd-keeper saves the file. Usage statistics are not kept, so
+//! copying or running a project command never touches the file.
+
+use std::cell::Cell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::FileStamp;
+use crate::error::Result;
+use crate::formats::{self, Conflict, Format};
+use crate::models::{CommandDatabase, Source};
+
+/// Names of project files, in order of preference within a directory
+pub const FILE_NAMES: [&str; 2] = [".cmd-keeper.json", ".cmd-keeper.toml"];
+
+/// A project file
+pub struct ProjectFile {
+    path: PathBuf,
+    /// Stamp of the file as last loaded or saved by this instance
+    last_stamp: Cell<Option<FileStamp>>,
+}
+
+impl ProjectFile {
+    /// Creates a handle for the file at `path`, which need not exist yet
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            last_stamp: Cell::new(None),
+        }
+    }
+
+    /// Finds the project file closest to `start`, looking in it and then in
+    /// each parent directory
+    pub fn discover(start: &Path) -> Option<Self> {
+        start
+            .ancestors()
+            .flat_map(|dir| FILE_NAMES.iter().map(move |name| dir.join(name)))
+            .find(|path| path.is_file())
+            .map(Self::new)
+    }
+
+    /// Returns the path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// TOML for `.toml` files, JSON otherwise
+    fn format(&self) -> Format {
+        match Format::from_path(&self.path) {
+            Some(Format::Toml) => Format::Toml,
+            _ => Format::Json,
+        }
+    }
+
+    /// Loads the entries of the file, marked as [`Source::Project`]
+    ///
+    /// Returns an empty database if the file doesn't exist.
+    pub fn load(&self) -> Result<CommandDatabase> {
+        let stamp = FileStamp::of(&self.path)?;
+        let mut db = CommandDatabase::new();
+        let content = match stamp {
+            Some(_) => fs::read_to_string(&self.path)?,
+            None => String::new(),
+        };
+        // A freshly created, empty file holds no entries yet
+        if !content.trim().is_empty() {
+            let records = formats::import(&content, self.format())?;
+            formats::merge(&mut db, records, Conflict::Renumber);
+        }
+        for entry in &mut db.entries {
+            entry.source = Source::Project;
+        }
+        self.last_stamp.set(stamp);
+        Ok(db)
+    }
+
+    /// Writes the entries to the file, replacing it
+    pub fn save(&self, db: &CommandDatabase) -> Result<()> {
+        fs::write(&self.path, formats::export(&db.entries, self.format())?)?;
+        self.last_stamp.set(FileStamp::of(&self.path)?);
+        Ok(())
+    }
+
+    /// Returns true if the file changed on disk since this instance last
+    /// loaded or saved it
+    pub fn changed_on_disk(&self) -> Result<bool> {
+        Ok(FileStamp::of(&self.path)? != self.last_stamp.get())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_discover_walks_up() {
+        let dir = tempdir().unwrap();
+        let nested = dir.path().join("crates/app/src");
+        fs::create_dir_all(&nested).unwrap();
+        assert!(ProjectFile::discover(&nested).is_none());
+
+        fs::write(dir.path().join(".cmd-keeper.toml"), "").unwrap();
+        let found = ProjectFile::discover(&nested).unwrap();
+        assert_eq!(found.path(), dir.path().join(".cmd-keeper.toml"));
+
+        // JSON wins within a directory, and the closest directory wins
+        fs::write(dir.path().join(".cmd-keeper.json"), "").unwrap();
+        let found = ProjectFile::discover(&nested).unwrap();
+        assert_eq!(found.path(), dir.path().join(".cmd-keeper.json"));
+        fs::write(nested.join(".cmd-keeper.toml"), "").unwrap();
+        let found = ProjectFile::discover(&nested).unwrap();
+        assert_eq!(found.path(), nested.join(".cmd-keeper.toml"));
+    }
+
+    #[test]
+    fn test_hand_written_file_gets_ids() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(".cmd-keeper.toml");
+        fs::write(
+            &path,
+            "[[entries]]\ncommand = \"make\"\n\n[[entries]]\nid = 5\ncommand = \"make test\"\n",
+        )
+        .unwrap();
+
+        let project = ProjectFile::new(path.clone());
+        let mut db = project.load().unwrap();
+        let ids: Vec<(u64, Source)> = db.entries.iter().map(|e| (e.id, e.source)).collect();
+        assert_eq!(ids, vec![(1, Source::Project), (5, Source::Project)]);
+
+        db.add("make lint".to_string(), String::new(), vec![]);
+        project.save(&db).unwrap();
+        assert!(!project.changed_on_disk().unwrap());
+
+        let reloaded = ProjectFile::new(path).load().unwrap();
+        let commands: Vec<(u64, &str)> = reloaded
+            .entries
+            .iter()
+            .map(|e| (e.id, e.command.as_str()))
+            .collect();
+        assert_eq!(
+            commands,
+            vec![(1, "make"), (5, "make test"), (6, "make lint")]
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Recovering what is readable from a damaged JSON database, for
+//! `cmd-keeper doctor`
+//!
+//! Entries are read one by one, so a single bad entry only loses that entry.
+//! If the file isn't valid JSON at all (e.g. it was cut off), the complete
+//! entry objects are picked out of the text.
+
+use serde_json::{json, Map, Value};
+
+use super::migrations;
+use crate::models::{CommandDatabase, CommandEntry};
+
+/// What could be read from a damaged file
+#[derive(Debug)]
+pub struct Salvaged {
+    pub db: CommandDatabase,
+    /// What was lost, e.g. `entry 3 (ID 7): missing field `command``
+    pub lost: Vec<String>,
+}
+
+/// Reads every entry that can be read from the content of a JSON database
+///
+/// `next_id` is kept as found, even if it is too low (see
+/// [`crate::doctor`]).
+pub fn salvage(content: &str) -> Salvaged {
+    let mut lost = Vec::new();
+    let doc = serde_json::from_str::<Value>(content).unwrap_or_else(|e| {
+        lost.push(format!(
+            "the file is not valid JSON ({}); only complete entries were recovered",
+            e
+        ));
+        scan(content)
+    });
+    if !doc.is_object() {
+        lost.push("the file does not hold a database".to_string());
+    }
+
+    let version = doc.get("schema_version");
+    let mut db = CommandDatabase::new();
+    db.entries = salvage_list(&doc, "entries", version, &mut lost);
+    db.trash = salvage_list(&doc, "trash", version, &mut lost);
+    let max_id = db.all_entries().map(|e| e.id).max().unwrap_or(0);
+    db.set_next_id(
+        doc.get("next_id")
+            .and_then(Value::as_u64)
+            .unwrap_or(max_id + 1),
+    );
+    Salvaged { db, lost }
+}
+
+/// Reads the entries of one list, noting the ones that can't be read
+fn salvage_list(
+    doc: &Value,
+    key: &str,
+    version: Option<&Value>,
+    lost: &mut Vec<String>,
+) -> Vec<CommandEntry> {
+    let Some(items) = doc.get(key) else {
+        return Vec::new();
+    };
+    let Some(items) = items.as_array() else {
+        lost.push(format!("`{}` is not a list", key));
+        return Vec::new();
+    };
+
+    let noun = if key == "trash" {
+        "trashed entry"
+    } else {
+        "entry"
+    };
+    items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| match salvage_entry(item, version) {
+            Ok(entry) => Some(entry),
+            Err(reason) => {
+                let id = item
+                    .get("id")
+                    .and_then(Value::as_u64)
+                    .map(|id| format!(" (ID {})", id))
+                    .unwrap_or_default();
+                lost.push(format!("{} {}{}: {}", noun, i + 1, id, reason));
+                None
+            }
+        })
+        .collect()
+}
+
+/// Reads one entry, upgrading it from the file's schema version
+fn salvage_entry(item: &Value, version: Option<&Value>) -> Result<CommandEntry, String> {
+    if !item.is_object() {
+        return Err("not a valid entry".to_string());
+    }
+    let mut doc = json!({ "entries": [item] });
+    if let Some(version) = version {
+        doc["schema_version"] = version.clone();
+    }
+    migrations::migrate(&mut doc).map_err(|e| e.to_string())?;
+    serde_json::from_value(doc["entries"][0].take()).map_err(|e| e.to_string())
+}
+
+/// Picks the parts of a database out of text that isn't valid JSON
+///
+/// Entries that can't be parsed become `null`, so they are reported lost.
+fn scan(content: &str) -> Value {
+    let mut doc = Map::new();
+    for key in ["schema_version", "next_id"] {
+        let number = value_start(content, key).and_then(|start| {
+            let digits: String = content[start..]
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect();
+            digits.parse::<u64>().ok()
+        });
+        if let Some(number) = number {
+            doc.insert(key.to_string(), Value::from(number));
+        }
+    }
+    for key in ["entries", "trash"] {
+        if let Some(start) = value_start(content, key) {
+            doc.insert(key.to_string(), Value::Array(objects(&content[start..])));
+        }
+    }
+    Value::Object(doc)
+}
+
+/// Returns where the value of `"key":` starts, skipping whitespace
+fn value_start(content: &str, key: &str) -> Option<usize> {
+    let quoted = format!("\"{}\"", key);
+    let mut from = 0;
+    while let Some(found) = content[from..].find(&quoted) {
+        let at = from + found;
+        from = at + quoted.len();
+        // A key inside a string would have escaped quotes
+        if content[..at].ends_with('\\') {
+            continue;
+        }
+        let rest = content[from..].trim_start();
+        if let Some(value) = rest.strip_prefix(':') {
+            let value = value.trim_start();
+            return Some(content.len() - value.len());
+        }
+    }
+    None
+}
+
+/// Parses the complete objects of the array at the start of `text`,
+/// stopping at its end or at anything else
+fn objects(text: &str) -> Vec<Value> {
+    let bytes = text.as_bytes();
+    if bytes.first() != Some(&b'[') {
+        return Vec::new();
+    }
+
+    let mut objects = Vec::new();
+    let mut i = 1;
+    loop {
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b',') {
+            i += 1;
+        }
+        if bytes.get(i) != Some(&b'{') {
+            break;
+        }
+        let Some(end) = closing_brace(bytes, i) else {
+            break;
+        };
+        objects.push(serde_json::from_str(&text[i..=end]).unwrap_or(Value::Null));
+        i = end + 1;
+    }
+    objects
+}
+
+/// Returns the index of the `}` closing the `{` at `start`, skipping
+/// strings
+fn closing_brace(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, &byte) in bytes.iter().enumerate().skip(start) {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(salvaged: &Salvaged) -> Vec<(u64, &str)> {
+        salvaged
+            .db
+            .entries
+            .iter()
+            .map(|e| (e.id, e.command.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_salvage_drops_bad_entries() {
+        let content = r#"{"schema_version": 6, "next_id": 4, "entries": [
+            {"id": 1, "command": "ls", "description": "", "tags": [], "created_at": "2024-01-15T10:30:00Z"},
+            {"id": 2, "description": "no command", "tags": [], "created_at": "2024-01-15T10:30:00Z"},
+            {"id": 3, "command": "make", "description": "", "tags": [], "created_at": "2024-01-15T10:30:00Z"}
+        ]}"#;
+
+        let salvaged = salvage(content);
+        assert_eq!(commands(&salvaged), vec![(1, "ls"), (3, "make")]);
+        assert_eq!(salvaged.db.next_id(), 4);
+        assert_eq!(salvaged.lost.len(), 1);
+        assert!(salvaged.lost[0].starts_with("entry 2 (ID 2): missing field"));
+    }
+
+    #[test]
+    fn test_salvage_truncated_file() {
+        let content = r#"{"schema_version": 6, "next_id": 4, "entries": [
+            {"id": 1, "command": "echo \"}{\"", "description": "", "tags": [], "created_at": "2024-01-15T10:30:00Z"},
+            {"id": 2, "command": "ls", "description": "", "tags": [], "created_at": "2024-01-15T10:30:00Z"},
+            {"id": 3, "command": "make", "descr"#;
+
+        let salvaged = salvage(content);
+        assert_eq!(commands(&salvaged), vec![(1, "echo \"}{\""), (2, "ls")]);
+        assert_eq!(salvaged.db.next_id(), 4);
+        assert!(salvaged.lost[0].contains("not valid JSON"));
+    }
+}
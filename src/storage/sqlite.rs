@@ -0,0 +1,422 @@
+//! SQLite backend for large collections
+//!
+//! Each entry is stored as a row holding its full JSON representation, with
+//! the command and description copied into columns for full-text search and
+//! the tags copied into an indexed side table. Saving only writes the rows
+//! that actually changed, and single entries can be written without loading
+//! the rest, so adding one entry to a large collection touches one row
+//! instead of rewriting everything.
+//!
+//! Trashed entries live in a table of their own, so they never show up in
+//! lookups.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+use super::{ensure_parent_dir, Backend};
+use crate::error::{CmdKeeperError, Result};
+use crate::models::{CommandDatabase, CommandEntry, SCHEMA_VERSION};
+use crate::tags;
+
+/// Tables, indexes and triggers, created on first open
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS meta (
+    key   TEXT PRIMARY KEY,
+    value INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS entries (
+    id          INTEGER PRIMARY KEY,
+    command     TEXT NOT NULL,
+    description TEXT NOT NULL,
+    data        TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS trash (
+    id   INTEGER PRIMARY KEY,
+    data TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS entry_tags (
+    entry_id INTEGER NOT NULL,
+    tag      TEXT NOT NULL COLLATE NOCASE,
+    PRIMARY KEY (entry_id, tag)
+);
+CREATE INDEX IF NOT EXISTS entry_tags_tag ON entry_tags (tag);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS entries_fts USING fts5 (
+    command, description,
+    content = 'entries', content_rowid = 'id', tokenize = 'trigram'
+);
+
+CREATE TRIGGER IF NOT EXISTS entries_ai AFTER INSERT ON entries BEGIN
+    INSERT INTO entries_fts (rowid, command, description)
+    VALUES (new.id, new.command, new.description);
+END;
+CREATE TRIGGER IF NOT EXISTS entries_ad AFTER DELETE ON entries BEGIN
+    INSERT INTO entries_fts (entries_fts, rowid, command, description)
+    VALUES ('delete', old.id, old.command, old.description);
+END;
+CREATE TRIGGER IF NOT EXISTS entries_au AFTER UPDATE ON entries BEGIN
+    INSERT INTO entries_fts (entries_fts, rowid, command, description)
+    VALUES ('delete', old.id, old.command, old.description);
+    INSERT INTO entries_fts (rowid, command, description)
+    VALUES (new.id, new.command, new.description);
+END;
+";
+
+/// Shortest query the trigram index can answer
+const MIN_FTS_CHARS: usize = 3;
+
+/// Stores the database in a SQLite file
+pub struct SqliteBackend {
+    /// Path to the SQLite file
+    path: PathBuf,
+}
+
+impl SqliteBackend {
+    /// Creates a backend for the given file
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Opens the database, creating the schema if needed
+    fn open(&self) -> Result<Connection> {
+        ensure_parent_dir(&self.path)?;
+        let conn = Connection::open(&self.path)?;
+        conn.execute_batch(SCHEMA)?;
+
+        if let Some(found) = meta(&conn, "schema_version")? {
+            if found > u64::from(SCHEMA_VERSION) {
+                return Err(CmdKeeperError::UnsupportedSchemaVersion {
+                    found,
+                    supported: SCHEMA_VERSION,
+                });
+            }
+        }
+        Ok(conn)
+    }
+
+    /// Runs a query returning entry JSON and decodes the entries
+    fn query_entries(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<CommandEntry>> {
+        let conn = self.open()?;
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| row.get::<_, String>(0))?;
+
+        let mut entries = Vec::new();
+        for data in rows {
+            entries.push(serde_json::from_str(&data?)?);
+        }
+        Ok(entries)
+    }
+}
+
+/// Reads a value from the meta table
+fn meta(conn: &Connection, key: &str) -> Result<Option<u64>> {
+    Ok(conn
+        .query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| {
+            row.get::<_, i64>(0)
+        })
+        .optional()?
+        .map(|v| v as u64))
+}
+
+/// Writes the row and tags of a listed entry, replacing the old ones
+fn write_entry(tx: &Transaction, entry: &CommandEntry, data: &str) -> Result<()> {
+    tx.execute(
+        "INSERT INTO entries (id, command, description, data) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (id) DO UPDATE SET
+             command = excluded.command,
+             description = excluded.description,
+             data = excluded.data",
+        params![entry.id as i64, entry.command, entry.description, data],
+    )?;
+    tx.execute(
+        "DELETE FROM entry_tags WHERE entry_id = ?1",
+        [entry.id as i64],
+    )?;
+    for tag in &entry.tags {
+        tx.execute(
+            "INSERT OR IGNORE INTO entry_tags (entry_id, tag) VALUES (?1, ?2)",
+            params![entry.id as i64, tag],
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes the schema version and the next ID
+fn write_meta(tx: &Transaction, next_id: u64) -> Result<()> {
+    for (key, value) in [
+        ("schema_version", u64::from(SCHEMA_VERSION)),
+        ("next_id", next_id),
+    ] {
+        tx.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, ?2)
+             ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+            params![key, value as i64],
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns the ID after the stored next ID and every ID in use
+fn next_free_id(conn: &Connection) -> Result<u64> {
+    let max_id: Option<i64> = conn.query_row(
+        "SELECT MAX(id) FROM (SELECT id FROM entries UNION ALL SELECT id FROM trash)",
+        [],
+        |row| row.get(0),
+    )?;
+    let after_max = max_id.map_or(1, |id| id as u64 + 1);
+    Ok(meta(conn, "next_id")?.unwrap_or(1).max(after_max))
+}
+
+/// Escapes `%`, `_` and `\` for use in a LIKE pattern with `ESCAPE '\'`
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+impl Backend for SqliteBackend {
+    fn load(&self) -> Result<CommandDatabase> {
+        let conn = self.open()?;
+        let next_id = meta(&conn, "next_id")?;
+
+        let mut db = CommandDatabase::new();
+        db.entries = self.query_entries("SELECT data FROM entries ORDER BY id", [])?;
+        db.trash = self.query_entries(
+            "SELECT data FROM trash ORDER BY json_extract(data, '$.deleted_at'), id",
+            [],
+        )?;
+        let max_id = db.all_entries().map(|e| e.id).max().unwrap_or(0);
+        db.set_next_id(next_id.unwrap_or(1).max(max_id + 1));
+        Ok(db)
+    }
+
+    fn save(&self, db: &CommandDatabase) -> Result<()> {
+        let mut conn = self.open()?;
+        let tx = conn.transaction()?;
+
+        let existing: HashMap<u64, String> = {
+            let mut stmt = tx.prepare("SELECT id, data FROM entries")?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, i64>(0)? as u64, row.get::<_, String>(1)?))
+            })?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        for entry in &db.entries {
+            let data = serde_json::to_string(entry)?;
+            if existing.get(&entry.id) != Some(&data) {
+                write_entry(&tx, entry, &data)?;
+            }
+        }
+
+        for id in existing.keys() {
+            if db.find_by_id(*id).is_none() {
+                tx.execute("DELETE FROM entries WHERE id = ?1", [*id as i64])?;
+                tx.execute("DELETE FROM entry_tags WHERE entry_id = ?1", [*id as i64])?;
+            }
+        }
+
+        // The trash is small and rarely changes, so it is rewritten whole
+        tx.execute("DELETE FROM trash", [])?;
+        for entry in &db.trash {
+            tx.execute(
+                "INSERT INTO trash (id, data) VALUES (?1, ?2)",
+                params![entry.id as i64, serde_json::to_string(entry)?],
+            )?;
+        }
+
+        write_meta(&tx, db.next_id())?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn next_id(&self) -> Result<u64> {
+        next_free_id(&self.open()?)
+    }
+
+    fn save_entry(&self, entry: &CommandEntry) -> Result<()> {
+        let mut conn = self.open()?;
+        let tx = conn.transaction()?;
+        let next_id = next_free_id(&tx)?.max(entry.id + 1);
+        write_entry(&tx, entry, &serde_json::to_string(entry)?)?;
+        write_meta(&tx, next_id)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn find_by_id(&self, id: u64) -> Result<Option<CommandEntry>> {
+        Ok(self
+            .query_entries("SELECT data FROM entries WHERE id = ?1", [id as i64])?
+            .pop())
+    }
+
+    fn find_by_tag(&self, tag: &str) -> Result<Vec<CommandEntry>> {
+        // The tag itself and every tag below it
+        let tag = tag.trim_end_matches(tags::SEPARATOR);
+        let descendants = format!("{}{}%", escape_like(tag), tags::SEPARATOR);
+        self.query_entries(
+            "SELECT data FROM entries WHERE id IN (
+                 SELECT entry_id FROM entry_tags WHERE tag = ?1 OR tag LIKE ?2 ESCAPE '\\'
+             )
+             ORDER BY id",
+            params![tag, descendants],
+        )
+    }
+
+    fn search_text(&self, text: &str) -> Result<Vec<CommandEntry>> {
+        let tag_pattern = format!("%{}%", escape_like(text));
+
+        if text.chars().count() >= MIN_FTS_CHARS {
+            let phrase = format!("\"{}\"", text.replace('"', "\"\""));
+            self.query_entries(
+                "SELECT data FROM entries WHERE id IN (
+                     SELECT rowid FROM entries_fts WHERE entries_fts MATCH ?1
+                     UNION
+                     SELECT entry_id FROM entry_tags WHERE tag LIKE ?2 ESCAPE '\\'
+                 )
+                 ORDER BY id",
+                params![phrase, tag_pattern],
+            )
+        } else {
+            // Too short for the trigram index: fall back to a scan
+            self.query_entries(
+                "SELECT data FROM entries WHERE
+                     command LIKE ?1 ESCAPE '\\'
+                     OR description LIKE ?1 ESCAPE '\\'
+                     OR id IN (SELECT entry_id FROM entry_tags WHERE tag LIKE ?1 ESCAPE '\\')
+                 ORDER BY id",
+                [tag_pattern],
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sample_db() -> CommandDatabase {
+        let mut db = CommandDatabase::new();
+        db.add(
+            "git checkout main".to_string(),
+            "Switch to main".to_string(),
+            vec!["git".to_string()],
+        );
+        db.add(
+            "kubectl get pods".to_string(),
+            "List pods".to_string(),
+            vec!["k8s".to_string(), "prod".to_string()],
+        );
+        db.add("ls -la".to_string(), "List files".to_string(), vec![]);
+        db
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempdir().unwrap();
+        let backend = SqliteBackend::new(dir.path().join("test.db"));
+
+        let mut db = sample_db();
+        backend.save(&db).unwrap();
+
+        let loaded = backend.load().unwrap();
+        assert_eq!(loaded.entries.len(), 3);
+        assert_eq!(loaded.entries[1].tags, vec!["k8s", "prod"]);
+
+        // IDs keep counting from where they left off after a reload
+        db.remove_by_id(3);
+        backend.save(&db).unwrap();
+        let mut loaded = backend.load().unwrap();
+        assert_eq!(loaded.entries.len(), 2);
+        assert_eq!(loaded.add("new".to_string(), String::new(), vec![]), 4);
+
+        // Trashed entries are kept apart from the lookups
+        loaded.trash_by_id(2);
+        backend.save(&loaded).unwrap();
+        let loaded = backend.load().unwrap();
+        assert_eq!(loaded.trash.len(), 1);
+        assert!(loaded.trash[0].deleted_at.is_some());
+        assert!(backend.find_by_id(2).unwrap().is_none());
+        assert!(backend.find_by_tag("k8s").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_indexed_lookups() {
+        let dir = tempdir().unwrap();
+        let backend = SqliteBackend::new(dir.path().join("test.db"));
+        let mut db = sample_db();
+        backend.save(&db).unwrap();
+
+        assert_eq!(
+            backend.find_by_id(2).unwrap().unwrap().command,
+            "kubectl get pods"
+        );
+        assert!(backend.find_by_id(42).unwrap().is_none());
+
+        let tagged = backend.find_by_tag("K8S").unwrap();
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].id, 2);
+
+        let ids = |hits: Vec<CommandEntry>| hits.iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(ids(backend.search_text("checkout").unwrap()), vec![1]);
+        assert_eq!(ids(backend.search_text("LIST").unwrap()), vec![2, 3]);
+        assert_eq!(ids(backend.search_text("ls").unwrap()), vec![3]);
+        assert_eq!(ids(backend.search_text("prod").unwrap()), vec![2]);
+
+        // The full-text index follows updates
+        db.update(1, Some("git switch main".to_string()), None, None);
+        backend.save(&db).unwrap();
+        assert!(backend.search_text("checkout").unwrap().is_empty());
+        assert_eq!(ids(backend.search_text("switch").unwrap()), vec![1]);
+    }
+
+    #[test]
+    fn test_save_entry() {
+        let dir = tempdir().unwrap();
+        let backend = SqliteBackend::new(dir.path().join("test.db"));
+        let mut db = sample_db();
+        db.trash_by_id(3);
+        backend.save(&db).unwrap();
+        assert_eq!(backend.next_id().unwrap(), 4);
+
+        let mut entry = CommandEntry::new(4, "make test".to_string(), String::new(), vec![]);
+        backend.save_entry(&entry).unwrap();
+        assert_eq!(backend.next_id().unwrap(), 5);
+        entry.tags = vec!["build".to_string()];
+        backend.save_entry(&entry).unwrap();
+
+        let loaded = backend.load().unwrap();
+        let ids: Vec<u64> = loaded.entries.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![1, 2, 4]);
+        assert_eq!(loaded.trash.len(), 1);
+        assert_eq!(backend.find_by_tag("build").unwrap()[0].id, 4);
+        assert_eq!(backend.search_text("make").unwrap()[0].id, 4);
+    }
+
+    #[test]
+    fn test_rejects_newer_schema() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let backend = SqliteBackend::new(path.clone());
+        backend.save(&CommandDatabase::new()).unwrap();
+
+        Connection::open(&path)
+            .unwrap()
+            .execute(
+                "UPDATE meta SET value = 999 WHERE key = 'schema_version'",
+                [],
+            )
+            .unwrap();
+
+        assert!(matches!(
+            backend.load().unwrap_err(),
+            CmdKeeperError::UnsupportedSchemaVersion { found: 999, .. }
+        ));
+    }
+}
@@ -0,0 +1,330 @@
+//! Entry-level three-way merge of databases, for `cmd-keeper sync`
+//!
+//! Two copies of the database that diverged from a common base are merged
+//! entry by entry, matched by ID, instead of line by line:
+//!
+//! - an entry changed on one side only takes that side's version
+//! - an entry edited on both sides merges field by field; a field changed
+//!   on both sides keeps the edit with the later `updated_at`
+//! - a deleted entry stays deleted unless the other side edited it; moving
+//!   to and from the trash merges like an edit
+//! - usage counts add up what each side recorded since the base
+//! - entries added on both sides under the same ID keep the remote one at
+//!   that ID and move the local one to a new ID, unless both have the same
+//!   command, in which case they are the same entry
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::models::{CommandDatabase, CommandEntry, Usage};
+
+/// What a merge changed in the local database
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    /// Entries that came from the remote side
+    pub added: usize,
+    /// Entries that took the remote version
+    pub updated: usize,
+    /// Entries the remote side deleted
+    pub removed: usize,
+    /// Entries edited on both sides, resolved by `updated_at`
+    pub conflicts: usize,
+    /// Local entries moved to a new ID, as (old, new)
+    pub renumbered: Vec<(u64, u64)>,
+}
+
+impl MergeReport {
+    /// Returns true if the local database changed
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Merges the local and remote databases that diverged from `base`
+///
+/// Use an empty base for databases without common history.
+pub fn merge(
+    base: &CommandDatabase,
+    local: &CommandDatabase,
+    remote: &CommandDatabase,
+) -> (CommandDatabase, MergeReport) {
+    let index = |db: &CommandDatabase| -> BTreeMap<u64, CommandEntry> {
+        db.all_entries().map(|e| (e.id, e.clone())).collect()
+    };
+    let (base_entries, local_entries, remote_entries) = (index(base), index(local), index(remote));
+    let ids: BTreeSet<u64> = local_entries
+        .keys()
+        .chain(remote_entries.keys())
+        .copied()
+        .collect();
+    let remote_added: BTreeSet<&str> = remote_entries
+        .values()
+        .filter(|e| !base_entries.contains_key(&e.id))
+        .map(|e| e.command.as_str())
+        .collect();
+
+    let mut report = MergeReport::default();
+    let mut merged = CommandDatabase::new();
+    let mut moved = Vec::new();
+    for id in ids {
+        let base = base_entries.get(&id);
+        match (local_entries.get(&id), remote_entries.get(&id)) {
+            (Some(l), Some(r)) if base.is_none() && l.command != r.command => {
+                merged.restore(r.clone());
+                report.added += 1;
+                // Added on this side as well unless the command came over anyway
+                if !remote_added.contains(l.command.as_str()) {
+                    moved.push(l.clone());
+                }
+            }
+            (Some(l), Some(r)) => {
+                let entry = merge_entry(base, l, r, &mut report);
+                if !entry.same_content(l) {
+                    report.updated += 1;
+                }
+                merged.restore(entry);
+            }
+            (Some(l), None) => match base {
+                // Deleted remotely; a local edit brings it back
+                Some(b) if b.same_content(l) => report.removed += 1,
+                Some(_) => merged.restore(l.clone()),
+                None if remote_added.contains(l.command.as_str()) => {}
+                None => merged.restore(l.clone()),
+            },
+            (None, Some(r)) => match base {
+                // Deleted locally; a remote edit brings it back
+                Some(b) if b.same_content(r) => {}
+                _ => {
+                    merged.restore(r.clone());
+                    report.added += 1;
+                }
+            },
+            (None, None) => {}
+        }
+    }
+
+    let next_id = local.next_id().max(remote.next_id()).max(merged.next_id());
+    merged.set_next_id(next_id);
+    for mut entry in moved {
+        let old = entry.id;
+        entry.id = merged.next_id();
+        report.renumbered.push((old, entry.id));
+        merged.restore(entry);
+    }
+
+    (merged, report)
+}
+
+/// Merges the two versions of an entry present on both sides
+///
+/// Each field takes the side that changed it; a field changed differently
+/// on both sides takes the side edited last.
+fn merge_entry(
+    base: Option<&CommandEntry>,
+    local: &CommandEntry,
+    remote: &CommandEntry,
+    report: &mut MergeReport,
+) -> CommandEntry {
+    let remote_newer = remote.modified_at() > local.modified_at();
+    let mut conflict = false;
+    let mut entry = local.clone();
+    if take_remote(
+        base.map(|b| &b.command),
+        &local.command,
+        &remote.command,
+        remote_newer,
+        &mut conflict,
+    ) {
+        entry.command = remote.command.clone();
+    }
+    if take_remote(
+        base.map(|b| &b.description),
+        &local.description,
+        &remote.description,
+        remote_newer,
+        &mut conflict,
+    ) {
+        entry.description = remote.description.clone();
+    }
+    if take_remote(
+        base.map(|b| &b.tags),
+        &local.tags,
+        &remote.tags,
+        remote_newer,
+        &mut conflict,
+    ) {
+        entry.tags = remote.tags.clone();
+    }
+    if take_remote(
+        base.map(|b| &b.shell),
+        &local.shell,
+        &remote.shell,
+        remote_newer,
+        &mut conflict,
+    ) {
+        entry.shell = remote.shell.clone();
+    }
+    let trashed = |e: &CommandEntry| e.deleted_at.is_some();
+    if take_remote(
+        base.map(trashed).as_ref(),
+        &trashed(local),
+        &trashed(remote),
+        remote_newer,
+        &mut conflict,
+    ) {
+        entry.deleted_at = remote.deleted_at;
+    }
+    if conflict {
+        report.conflicts += 1;
+    }
+    entry.created_at = local.created_at.min(remote.created_at);
+    entry.updated_at = local.updated_at.max(remote.updated_at);
+    entry.usage = merge_usage(
+        base.map(|b| &b.usage).unwrap_or(&Usage::default()),
+        &local.usage,
+        &remote.usage,
+    );
+    entry
+}
+
+/// Returns true if a field should take the remote value, flagging a
+/// conflict if both sides changed it
+fn take_remote<T: PartialEq>(
+    base: Option<&T>,
+    local: &T,
+    remote: &T,
+    remote_newer: bool,
+    conflict: &mut bool,
+) -> bool {
+    if local == remote {
+        return false;
+    }
+    match base {
+        Some(base) if base == local => true,
+        Some(base) if base == remote => false,
+        _ => {
+            *conflict = true;
+            remote_newer
+        }
+    }
+}
+
+/// Adds up the uses each side recorded since the base
+fn merge_usage(base: &Usage, local: &Usage, remote: &Usage) -> Usage {
+    let sum = |field: fn(&Usage) -> u64| (field(local) + field(remote)).saturating_sub(field(base));
+    let latest = if remote.last_used_at > local.last_used_at {
+        remote
+    } else {
+        local
+    };
+    Usage {
+        use_count: sum(|u| u.use_count),
+        last_used_at: latest.last_used_at,
+        last_exit_code: latest.last_exit_code,
+        run_count: sum(|u| u.run_count),
+        failure_count: sum(|u| u.failure_count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn db(commands: &[&str]) -> CommandDatabase {
+        let mut db = CommandDatabase::new();
+        for command in commands {
+            db.add(command.to_string(), String::new(), vec![]);
+        }
+        db
+    }
+
+    fn commands(db: &CommandDatabase) -> Vec<(u64, &str)> {
+        db.entries
+            .iter()
+            .map(|e| (e.id, e.command.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_edits_and_deletes() {
+        let base = db(&["ls", "make", "git status", "cargo test"]);
+        let mut local = base.clone();
+        let mut remote = base.clone();
+
+        // Edited on one side each, deleted on one side, both edited
+        local.update(1, None, Some("list".to_string()), None);
+        remote.update(2, Some("make all".to_string()), None, None);
+        remote.remove_by_id(3);
+        local.update(4, Some("cargo test --all".to_string()), None, None);
+        remote.update(4, Some("cargo nextest run".to_string()), None, None);
+        remote.find_by_id_mut(4).unwrap().updated_at =
+            Some(local.find_by_id(4).unwrap().modified_at() + Duration::seconds(1));
+
+        let (merged, report) = merge(&base, &local, &remote);
+        assert_eq!(
+            commands(&merged),
+            vec![(1, "ls"), (2, "make all"), (4, "cargo nextest run")]
+        );
+        assert_eq!(merged.find_by_id(1).unwrap().description, "list");
+        assert_eq!(report.updated, 2);
+        assert_eq!(report.removed, 1);
+        assert_eq!(report.conflicts, 1);
+
+        // Different fields edited on both sides are both kept
+        let mut other = base.clone();
+        other.update(1, Some("ls -la".to_string()), None, None);
+        let (merged, report) = merge(&base, &local, &other);
+        let entry = merged.find_by_id(1).unwrap();
+        assert_eq!(
+            (entry.command.as_str(), entry.description.as_str()),
+            ("ls -la", "list")
+        );
+        assert_eq!(report.conflicts, 0);
+
+        // A local edit wins over a remote delete
+        local.update(3, None, Some("status".to_string()), None);
+        let (merged, _) = merge(&base, &local, &remote);
+        assert!(merged.find_by_id(3).is_some());
+    }
+
+    #[test]
+    fn test_merge_renumbers_concurrent_additions() {
+        let base = db(&["ls"]);
+        let mut local = base.clone();
+        let mut remote = base.clone();
+        local.add("make".to_string(), String::new(), vec![]);
+        remote.add("docker ps".to_string(), String::new(), vec![]);
+        remote.add("make".to_string(), String::new(), vec![]);
+
+        let (merged, report) = merge(&base, &local, &remote);
+        assert_eq!(
+            commands(&merged),
+            vec![(1, "ls"), (2, "docker ps"), (3, "make")]
+        );
+        assert!(report.renumbered.is_empty());
+
+        local.add("htop".to_string(), String::new(), vec![]);
+        let (merged, report) = merge(&base, &local, &remote);
+        assert_eq!(report.renumbered, vec![(3, 4)]);
+        assert_eq!(merged.find_by_id(4).unwrap().command, "htop");
+        assert_eq!(merged.next_id(), 5);
+    }
+
+    #[test]
+    fn test_merge_adds_up_usage() {
+        let mut base = db(&["ls"]);
+        base.record_use(1, None);
+        let mut local = base.clone();
+        let mut remote = base.clone();
+        local.record_use(1, Some(0));
+        remote.record_use(1, Some(1));
+        remote.record_use(1, None);
+
+        let (merged, report) = merge(&base, &local, &remote);
+        let usage = &merged.find_by_id(1).unwrap().usage;
+        assert_eq!(usage.use_count, 4);
+        assert_eq!(usage.run_count, 2);
+        assert_eq!(usage.failure_count, 1);
+        assert_eq!(report.updated, 0);
+    }
+}
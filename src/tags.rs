@@ -0,0 +1,156 @@
+//! Hierarchical tags
+//!
+//! Tags can be paths such as `cloud/aws/ec2`. Filtering by a tag matches
+//! the tag itself and everything below it, so `cloud/aws` includes
+//! `cloud/aws/ec2`. Like all tag comparisons, this ignores case.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::models::CommandEntry;
+
+/// Separates the levels of a hierarchical tag
+pub const SEPARATOR: char = '/';
+
+/// Returns true if `tag` is `filter` or lies below it
+pub fn matches(tag: &str, filter: &str) -> bool {
+    let filter = filter.trim_end_matches(SEPARATOR);
+    if tag.eq_ignore_ascii_case(filter) {
+        return true;
+    }
+    tag.get(..filter.len())
+        .is_some_and(|head| head.eq_ignore_ascii_case(filter))
+        && tag[filter.len()..].starts_with(SEPARATOR)
+}
+
+/// Moves a tag from below `from` to below `to`, e.g. `cloud/aws/ec2` from
+/// `cloud` to `infra` becomes `infra/aws/ec2`
+///
+/// Returns None if the tag doesn't match `from`.
+pub fn reparent(tag: &str, from: &str, to: &str) -> Option<String> {
+    if !matches(tag, from) {
+        return None;
+    }
+    let rest = &tag[from.trim_end_matches(SEPARATOR).len()..];
+    Some(format!("{}{}", to.trim_end_matches(SEPARATOR), rest))
+}
+
+/// A level of the tag tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagNode {
+    /// Last segment of the path, e.g. `ec2`
+    pub name: String,
+    /// Full tag, e.g. `cloud/aws/ec2`
+    pub path: String,
+    /// Number of entries carrying this tag or one below it
+    pub count: usize,
+    pub children: Vec<TagNode>,
+}
+
+/// Node of the tree while it is built; children are keyed by lowercase
+/// name so that `AWS` and `aws` end up in one node
+#[derive(Default)]
+struct Builder {
+    name: String,
+    path: String,
+    ids: HashSet<u64>,
+    children: BTreeMap<String, Builder>,
+}
+
+impl Builder {
+    fn finish(self) -> TagNode {
+        TagNode {
+            name: self.name,
+            path: self.path,
+            count: self.ids.len(),
+            children: self.children.into_values().map(Builder::finish).collect(),
+        }
+    }
+}
+
+/// Builds the tag tree of the entries, sorted by name at every level
+///
+/// Each tag is added with all its ancestors, so `cloud/aws/ec2` alone
+/// yields `cloud`, `cloud/aws` and `cloud/aws/ec2`.
+pub fn tree(entries: &[CommandEntry]) -> Vec<TagNode> {
+    let mut root = Builder::default();
+
+    for entry in entries {
+        for tag in &entry.tags {
+            let mut node = &mut root;
+            let mut path = String::new();
+            for segment in tag.split(SEPARATOR).filter(|s| !s.is_empty()) {
+                if !path.is_empty() {
+                    path.push(SEPARATOR);
+                }
+                path.push_str(segment);
+
+                node = node
+                    .children
+                    .entry(segment.to_lowercase())
+                    .or_insert_with(|| Builder {
+                        name: segment.to_string(),
+                        path: path.clone(),
+                        ..Builder::default()
+                    });
+                node.ids.insert(entry.id);
+            }
+        }
+    }
+
+    root.finish().children
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("cloud/aws/ec2", "cloud/aws"));
+        assert!(matches("cloud/aws", "Cloud/AWS/"));
+        assert!(matches("cloud", "cloud"));
+        assert!(!matches("cloud/awsome", "cloud/aws"));
+        assert!(!matches("cloud", "cloud/aws"));
+        assert!(!matches("é", "e"));
+    }
+
+    #[test]
+    fn test_reparent() {
+        assert_eq!(
+            reparent("cloud/aws/ec2", "cloud", "infra").as_deref(),
+            Some("infra/aws/ec2")
+        );
+        assert_eq!(reparent("K8s", "k8s", "k8s").as_deref(), Some("k8s"));
+        assert_eq!(reparent("git", "cloud", "infra"), None);
+    }
+
+    #[test]
+    fn test_tree() {
+        let entry = |id, tags: &[&str]| {
+            CommandEntry::new(
+                id,
+                String::new(),
+                String::new(),
+                tags.iter().map(|t| t.to_string()).collect(),
+            )
+        };
+        let entries = vec![
+            entry(1, &["cloud/aws/ec2", "cloud/aws/s3"]),
+            entry(2, &["cloud/AWS"]),
+            entry(3, &["git"]),
+        ];
+
+        let tree = tree(&entries);
+        let names: Vec<&str> = tree.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["cloud", "git"]);
+
+        let aws = &tree[0].children[0];
+        assert_eq!((aws.path.as_str(), aws.count), ("cloud/aws", 2));
+        let leaves: Vec<(&str, usize)> = aws
+            .children
+            .iter()
+            .map(|n| (n.path.as_str(), n.count))
+            .collect();
+        assert_eq!(leaves, vec![("cloud/aws/ec2", 1), ("cloud/aws/s3", 1)]);
+    }
+}
@@ -0,0 +1,331 @@
+//! Command templates with placeholders
+//!
+//! Saved commands may contain placeholders that are filled in each time the
+//! command is copied or executed:
+//!
+//! - `{{name}}` - a required value
+//! - `{{name=default}}` - a value with a default
+//! - `{{name|description}}` - a value with a hint shown when prompting
+//! - `{{name=default|description}}` - both
+//!
+//! Placeholder names must start with a letter or `_` and may contain letters,
+//! digits, `_` and `-`. Anything else between braces (for example the Go
+//! template in `docker inspect -f '{{.State.Status}}'`) is left untouched.
+
+use std::collections::HashMap;
+
+use crate::error::{CmdKeeperError, Result};
+
+/// A placeholder found in a command template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    /// Name used to refer to the value (e.g. with `--set name=value`)
+    pub name: String,
+    /// Value used when none is given
+    pub default: Option<String>,
+    /// Hint shown when asking for the value
+    pub description: Option<String>,
+}
+
+/// A piece of a parsed template
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment<'a> {
+    /// Literal text copied as-is
+    Text(&'a str),
+    /// A placeholder to be substituted
+    Placeholder(Placeholder),
+}
+
+/// Splits a template into literal text and placeholders
+fn parse(template: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let after_open = &rest[start + 2..];
+        let Some(end) = after_open.find("}}") else {
+            break;
+        };
+
+        match parse_placeholder(&after_open[..end]) {
+            Some(placeholder) => {
+                if start > 0 {
+                    segments.push(Segment::Text(&rest[..start]));
+                }
+                segments.push(Segment::Placeholder(placeholder));
+                rest = &after_open[end + 2..];
+            }
+            None => {
+                // Not a placeholder: keep the braces as literal text
+                segments.push(Segment::Text(&rest[..start + 2]));
+                rest = after_open;
+            }
+        }
+    }
+
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    segments
+}
+
+/// Parses the inside of `{{ ... }}`, returning None if it is not a placeholder
+fn parse_placeholder(inner: &str) -> Option<Placeholder> {
+    let (spec, description) = match inner.split_once('|') {
+        Some((spec, desc)) => (spec, Some(desc.trim().to_string())),
+        None => (inner, None),
+    };
+    let (name, default) = match spec.split_once('=') {
+        Some((name, default)) => (name.trim(), Some(default.trim().to_string())),
+        None => (spec.trim(), None),
+    };
+
+    if !is_valid_name(name) {
+        return None;
+    }
+
+    Some(Placeholder {
+        name: name.to_string(),
+        default,
+        description: description.filter(|d| !d.is_empty()),
+    })
+}
+
+/// Returns true if the string is a valid placeholder name
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Returns the placeholders of a template in order of first appearance
+///
+/// A name that appears several times is returned once; the first occurrence
+/// that carries a default or description provides it.
+pub fn placeholders(template: &str) -> Vec<Placeholder> {
+    let mut result: Vec<Placeholder> = Vec::new();
+
+    for segment in parse(template) {
+        let Segment::Placeholder(placeholder) = segment else {
+            continue;
+        };
+        match result.iter_mut().find(|p| p.name == placeholder.name) {
+            Some(existing) => {
+                if existing.default.is_none() {
+                    existing.default = placeholder.default;
+                }
+                if existing.description.is_none() {
+                    existing.description = placeholder.description;
+                }
+            }
+            None => result.push(placeholder),
+        }
+    }
+
+    result
+}
+
+/// Returns true if the template contains at least one placeholder
+pub fn has_placeholders(template: &str) -> bool {
+    parse(template)
+        .iter()
+        .any(|s| matches!(s, Segment::Placeholder(_)))
+}
+
+/// Substitutes placeholder values into a template
+///
+/// Placeholders without a value fall back to their default. A placeholder
+/// with neither is an error.
+pub fn render(template: &str, values: &HashMap<String, String>) -> Result<String> {
+    let defaults: HashMap<String, Option<String>> = placeholders(template)
+        .into_iter()
+        .map(|p| (p.name, p.default))
+        .collect();
+
+    let mut output = String::with_capacity(template.len());
+    for segment in parse(template) {
+        match segment {
+            Segment::Text(text) => output.push_str(text),
+            Segment::Placeholder(placeholder) => {
+                let value = values
+                    .get(&placeholder.name)
+                    .or_else(|| defaults.get(&placeholder.name).and_then(Option::as_ref))
+                    .ok_or_else(|| CmdKeeperError::MissingPlaceholder(placeholder.name.clone()))?;
+                output.push_str(value);
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// Rewrites every placeholder with `f`, keeping the rest of the template
+///
+/// Used to convert placeholders to the syntax of other tools.
+pub fn map_placeholders(template: &str, mut f: impl FnMut(&Placeholder) -> String) -> String {
+    let mut output = String::with_capacity(template.len());
+    for segment in parse(template) {
+        match segment {
+            Segment::Text(text) => output.push_str(text),
+            Segment::Placeholder(placeholder) => output.push_str(&f(&placeholder)),
+        }
+    }
+    output
+}
+
+/// Converts `<name>` and `<name=default>` placeholders, as used by navi and
+/// pet, to `{{name}}` and `{{name=default}}`
+///
+/// A pet choice list (`<name=|_a_||_b_|>`) becomes a default of its first
+/// option. Anything else in angle brackets, such as a redirection, is left
+/// alone.
+pub fn from_angle_brackets(command: &str) -> String {
+    let mut output = String::with_capacity(command.len());
+    let mut rest = command;
+
+    while let Some(start) = rest.find('<') {
+        output.push_str(&rest[..start]);
+        let after_open = &rest[start + 1..];
+
+        let placeholder = after_open
+            .find('>')
+            .map(|end| (&after_open[..end], end))
+            .filter(|(inner, _)| !inner.contains(['<', '\n']))
+            .and_then(|(inner, end)| {
+                let (name, default) = match inner.split_once('=') {
+                    Some((name, default)) => (name, Some(first_choice(default))),
+                    None => (inner, None),
+                };
+                is_valid_name(name).then(|| {
+                    // A default containing our own delimiters can't be kept
+                    let default = default.filter(|d| !d.contains('|') && !d.contains("}}"));
+                    let braces = match default {
+                        Some(default) => format!("{{{{{}={}}}}}", name, default),
+                        None => format!("{{{{{}}}}}", name),
+                    };
+                    (braces, end)
+                })
+            });
+
+        match placeholder {
+            Some((braces, end)) => {
+                output.push_str(&braces);
+                rest = &after_open[end + 1..];
+            }
+            None => {
+                output.push('<');
+                rest = after_open;
+            }
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Returns the first option of a pet choice list (`|_a_||_b_|`), or the
+/// text itself if it isn't one
+fn first_choice(default: &str) -> &str {
+    default
+        .strip_prefix("|_")
+        .and_then(|rest| rest.split_once("_|"))
+        .map_or(default, |(first, _)| first)
+}
+
+/// Parses a `NAME=VALUE` assignment as given to `--set`
+pub fn parse_assignment(s: &str) -> std::result::Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got '{}'", s))?;
+    let name = name.trim();
+    if !is_valid_name(name) {
+        return Err(format!("invalid placeholder name '{}'", name));
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let found = placeholders("kubectl logs -n {{namespace=default|Namespace}} {{pod}} {{pod}}");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].name, "namespace");
+        assert_eq!(found[0].default.as_deref(), Some("default"));
+        assert_eq!(found[0].description.as_deref(), Some("Namespace"));
+        assert_eq!(found[1].name, "pod");
+        assert_eq!(found[1].default, None);
+    }
+
+    #[test]
+    fn test_angle_bracket_conversion() {
+        assert_eq!(
+            from_angle_brackets("git checkout <branch> && cat <file=README.md> < in.txt"),
+            "git checkout {{branch}} && cat {{file=README.md}} < in.txt"
+        );
+        assert_eq!(
+            from_angle_brackets("ssh <host=|_a_||_b_|>"),
+            "ssh {{host=a}}"
+        );
+        assert_eq!(from_angle_brackets("echo <1> <a b>"), "echo <1> <a b>");
+
+        let angled = map_placeholders("ssh {{user=root|User}}@{{host}}", |p| match &p.default {
+            Some(default) => format!("<{}={}>", p.name, default),
+            None => format!("<{}>", p.name),
+        });
+        assert_eq!(angled, "ssh <user=root>@<host>");
+    }
+
+    #[test]
+    fn test_render() {
+        let rendered = render(
+            "kubectl logs -n {{namespace=default}} {{pod}}",
+            &values(&[("pod", "web-1")]),
+        )
+        .unwrap();
+        assert_eq!(rendered, "kubectl logs -n default web-1");
+    }
+
+    #[test]
+    fn test_render_missing_value() {
+        let err = render("ssh {{host}}", &HashMap::new()).unwrap_err();
+        assert!(matches!(err, CmdKeeperError::MissingPlaceholder(name) if name == "host"));
+    }
+
+    #[test]
+    fn test_non_placeholder_braces_are_kept() {
+        let template = "docker inspect -f '{{.State.Status}}' {{container}}";
+        let found = placeholders(template);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "container");
+
+        let rendered = render(template, &values(&[("container", "db")])).unwrap();
+        assert_eq!(rendered, "docker inspect -f '{{.State.Status}}' db");
+    }
+
+    #[test]
+    fn test_parse_assignment() {
+        assert_eq!(
+            parse_assignment("pod=web-1").unwrap(),
+            ("pod".to_string(), "web-1".to_string())
+        );
+        assert_eq!(
+            parse_assignment("query=a=b").unwrap(),
+            ("query".to_string(), "a=b".to_string())
+        );
+        assert!(parse_assignment("pod").is_err());
+        assert!(parse_assignment("1x=y").is_err());
+    }
+}
@@ -2,11 +2,18 @@
 //!
 //! This module contains the application state and update logic for the TUI.
 
+use std::collections::{HashMap, HashSet};
+
 use anyhow::Result;
 use tui_textarea::TextArea;
 
-use crate::models::{CommandDatabase, CommandEntry};
+use crate::error::CmdKeeperError;
+use crate::models::{self, CommandDatabase, CommandEntry, EntryRef, SortOrder, Source};
+use crate::query::Query;
+use crate::storage::journal::Change;
 use crate::storage::Storage;
+use crate::tags::{self, TagNode};
+use crate::template::{self, Placeholder};
 
 /// Application mode (state machine)
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -19,6 +26,16 @@ pub enum Mode {
     Editing(InputField),
     /// Confirming deletion
     ConfirmDelete,
+    /// Filling in placeholder values before copying or executing
+    FillingPlaceholders,
+    /// Typing a filter query
+    Filtering,
+    /// Browsing, renaming and removing tags
+    ManagingTags,
+    /// Browsing the tag tree next to the list
+    BrowsingTagTree,
+    /// Browsing the trash of the global database
+    BrowsingTrash,
 }
 
 /// Which field is being edited in Adding/Editing mode
@@ -29,6 +46,288 @@ pub enum InputField {
     Tags,
 }
 
+/// What to do with a command once its placeholders are filled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PendingAction {
+    Copy,
+    Execute {
+        shell: Option<String>,
+    },
+    /// Hand the command back to the shell (picker mode)
+    Insert,
+}
+
+/// A fully rendered command waiting to be executed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingCommand {
+    /// ID of the entry the command comes from
+    pub entry_id: EntryRef,
+    /// Command string with placeholders filled
+    pub command: String,
+    /// Shell configured on the entry, if any
+    pub shell: Option<String>,
+}
+
+/// An entry shown in the (possibly filtered) list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisibleEntry {
+    /// Index into `db.entries`
+    pub index: usize,
+    /// Character positions in the command matched by the filter
+    pub command_indices: Vec<usize>,
+}
+
+/// Form for entering placeholder values
+pub struct PlaceholderForm<'a> {
+    /// Action to perform with the rendered command
+    pub action: PendingAction,
+    /// ID of the entry the template comes from
+    pub entry_id: EntryRef,
+    /// The command template being filled
+    pub template: String,
+    /// Placeholders found in the template
+    pub placeholders: Vec<Placeholder>,
+    /// One text area per placeholder
+    pub inputs: Vec<TextArea<'a>>,
+    /// Index of the focused input
+    pub focused: usize,
+}
+
+impl<'a> PlaceholderForm<'a> {
+    /// Creates a form for the given template
+    fn new(action: PendingAction, entry_id: EntryRef, template: String) -> Self {
+        let placeholders = template::placeholders(&template);
+        let inputs = placeholders
+            .iter()
+            .map(|p| {
+                let mut input = TextArea::default();
+                if let Some(default) = &p.default {
+                    input.set_placeholder_text(format!("default: {}", default));
+                }
+                input.set_cursor_line_style(ratatui::style::Style::default());
+                input
+            })
+            .collect();
+
+        Self {
+            action,
+            entry_id,
+            template,
+            placeholders,
+            inputs,
+            focused: 0,
+        }
+    }
+
+    /// Renders the template with the entered values
+    ///
+    /// Empty inputs fall back to the placeholder's default.
+    fn render(&self) -> crate::error::Result<String> {
+        let values: HashMap<String, String> = self
+            .placeholders
+            .iter()
+            .zip(&self.inputs)
+            .map(|(p, input)| (p.name.clone(), input.lines().join("\n")))
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+        template::render(&self.template, &values)
+    }
+}
+
+/// State of the tag management view, which covers the global database only
+pub struct TagManager<'a> {
+    /// Every tag with its number of entries, by name
+    pub tags: Vec<(String, usize)>,
+    /// Index of the selected tag
+    pub selected: usize,
+    /// New name being typed for the selected tag, while renaming
+    pub rename_input: Option<TextArea<'a>>,
+    /// Whether removing the selected tag awaits confirmation
+    pub confirm_remove: bool,
+}
+
+impl TagManager<'_> {
+    fn new(db: &CommandDatabase) -> Self {
+        Self {
+            tags: db.tag_counts(),
+            selected: 0,
+            rename_input: None,
+            confirm_remove: false,
+        }
+    }
+
+    /// Returns the name of the selected tag
+    pub fn selected_tag(&self) -> Option<&str> {
+        self.tags.get(self.selected).map(|(tag, _)| tag.as_str())
+    }
+
+    /// Reloads the tags, selecting `keep` if it still exists
+    fn reload(&mut self, db: &CommandDatabase, keep: Option<&str>) {
+        self.tags = db.tag_counts();
+        self.selected = keep
+            .and_then(|keep| self.tags.iter().position(|(tag, _)| tag == keep))
+            .unwrap_or(self.selected)
+            .min(self.tags.len().saturating_sub(1));
+        self.rename_input = None;
+        self.confirm_remove = false;
+    }
+}
+
+/// State of the trash view
+pub struct TrashView {
+    /// Entries in the trash, most recently deleted first
+    pub entries: Vec<CommandEntry>,
+    /// Index of the selected entry
+    pub selected: usize,
+}
+
+impl TrashView {
+    fn new(db: &CommandDatabase) -> Self {
+        let mut entries = db.trash.clone();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.deleted_at));
+        Self {
+            entries,
+            selected: 0,
+        }
+    }
+
+    /// Returns the selected entry
+    pub fn selected_entry(&self) -> Option<&CommandEntry> {
+        self.entries.get(self.selected)
+    }
+}
+
+/// State of the tag tree sidebar
+pub struct TagTree {
+    /// Top-level tags, each with the tags below it
+    pub roots: Vec<TagNode>,
+    /// Lowercase paths of the collapsed tags
+    collapsed: HashSet<String>,
+    /// Index of the selected row; row 0 stands for all commands
+    pub selected: usize,
+    /// Tag the list is narrowed to, with the tags below it
+    pub scope: Option<String>,
+}
+
+/// A visible row of the tag tree
+pub struct TreeRow<'t> {
+    /// None for the row standing for all commands
+    pub node: Option<&'t TagNode>,
+    pub depth: usize,
+    pub collapsed: bool,
+}
+
+impl TagTree {
+    fn new(db: &CommandDatabase) -> Self {
+        Self {
+            roots: tags::tree(&db.entries),
+            collapsed: HashSet::new(),
+            selected: 0,
+            scope: None,
+        }
+    }
+
+    /// Returns the rows to show, skipping the tags below collapsed ones
+    pub fn rows(&self) -> Vec<TreeRow<'_>> {
+        fn push<'t>(tree: &TagTree, node: &'t TagNode, depth: usize, rows: &mut Vec<TreeRow<'t>>) {
+            let collapsed = tree.collapsed.contains(&node.path.to_lowercase());
+            rows.push(TreeRow {
+                node: Some(node),
+                depth,
+                collapsed,
+            });
+            if !collapsed {
+                for child in &node.children {
+                    push(tree, child, depth + 1, rows);
+                }
+            }
+        }
+
+        let mut rows = vec![TreeRow {
+            node: None,
+            depth: 0,
+            collapsed: false,
+        }];
+        for root in &self.roots {
+            push(self, root, 0, &mut rows);
+        }
+        rows
+    }
+
+    /// Returns the selected tag, or None if all commands are selected
+    pub fn selected_node(&self) -> Option<&TagNode> {
+        self.rows().get(self.selected).and_then(|row| row.node)
+    }
+
+    fn move_by(&mut self, down: bool) {
+        if down {
+            self.selected = (self.selected + 1).min(self.rows().len() - 1);
+        } else {
+            self.selected = self.selected.saturating_sub(1);
+        }
+    }
+
+    /// Expands the selected tag
+    fn expand(&mut self) {
+        if let Some(path) = self.selected_node().map(|n| n.path.to_lowercase()) {
+            self.collapsed.remove(&path);
+        }
+    }
+
+    /// Collapses the selected tag, or selects its parent if it has no
+    /// children or is already collapsed
+    fn collapse(&mut self) {
+        let Some(node) = self.selected_node() else {
+            return;
+        };
+        let path = node.path.to_lowercase();
+        if !node.children.is_empty() && !self.collapsed.contains(&path) {
+            self.collapsed.insert(path);
+            return;
+        }
+
+        if let Some((parent, _)) = path.rsplit_once(tags::SEPARATOR) {
+            if let Some(index) = self.rows().iter().position(|row| {
+                row.node
+                    .is_some_and(|n| n.path.eq_ignore_ascii_case(parent))
+            }) {
+                self.selected = index;
+            }
+        }
+    }
+
+    /// Rebuilds the tree after the database changed, keeping the selected
+    /// tag selected and dropping the scope if no entry carries it anymore
+    fn reload(&mut self, db: &CommandDatabase) {
+        let selected = self.selected_node().map(|n| n.path.to_lowercase());
+        self.roots = tags::tree(&db.entries);
+        if self
+            .scope
+            .as_deref()
+            .is_some_and(|scope| !db.has_tag(scope))
+        {
+            self.scope = None;
+        }
+
+        let rows = self.rows();
+        self.selected = selected
+            .and_then(|path| {
+                rows.iter()
+                    .position(|row| row.node.is_some_and(|n| n.path.eq_ignore_ascii_case(&path)))
+            })
+            .unwrap_or(self.selected)
+            .min(rows.len() - 1);
+    }
+}
+
+/// Creates an empty filter input
+fn empty_filter_input<'a>() -> TextArea<'a> {
+    let mut input = TextArea::default();
+    input.set_placeholder_text("e.g. docker tag:prod -tag:old");
+    input.set_cursor_line_style(ratatui::style::Style::default());
+    input
+}
+
 /// Message type for state updates (like Redux actions)
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -57,9 +356,44 @@ pub enum Message {
     CancelDelete,
     ConfirmDelete,
 
+    // Placeholders
+    ConfirmFill,
+    CancelFill,
+
+    // Filter
+    StartFilter,
+    FilterChanged,
+    ConfirmFilter,
+    ClearFilter,
+
+    // Tag management
+    OpenTags,
+    CloseTags,
+    StartRenameTag,
+    ConfirmRenameTag,
+    StartRemoveTag,
+    ConfirmRemoveTag,
+    CancelTagAction,
+    FilterByTag,
+
+    // Tag tree
+    FocusTagTree,
+    FocusList,
+    ExpandTag,
+    CollapseTag,
+    SelectTagScope,
+
+    // Trash
+    OpenTrash,
+    CloseTrash,
+    RestoreFromTrash,
+
     // Actions
     CopyToClipboard,
     ExecuteCommand,
+    ToggleSort,
+    Undo,
+    Redo,
 
     // Exit
     Quit,
@@ -76,32 +410,66 @@ pub struct App<'a> {
     /// Storage handler
     storage: Storage,
 
-    /// Currently selected index in the list
+    /// Currently selected index in the visible (filtered) list
     pub selected_index: usize,
 
+    /// Entries shown in the list, best filter match first
+    pub visible: Vec<VisibleEntry>,
+
+    /// Filter query input
+    pub filter_input: TextArea<'a>,
+
+    /// Why the filter query can't be parsed, if it can't
+    pub filter_error: Option<String>,
+
+    /// Order of the list
+    pub sort_order: SortOrder,
+
     /// Text areas for adding/editing command
     pub command_input: TextArea<'a>,
     pub description_input: TextArea<'a>,
     pub tags_input: TextArea<'a>,
 
     /// ID of the entry being edited (only valid in Editing mode)
-    pub editing_id: Option<u64>,
+    pub editing_id: Option<EntryRef>,
 
     /// Status message to display
     pub status_message: Option<String>,
 
+    /// Placeholder form (only valid in FillingPlaceholders mode)
+    pub placeholder_form: Option<PlaceholderForm<'a>>,
+
+    /// Tag management view (only valid in ManagingTags mode)
+    pub tag_manager: Option<TagManager<'a>>,
+
+    /// Tag tree shown next to the list
+    pub tag_tree: TagTree,
+
+    /// Trash view (only valid in BrowsingTrash mode)
+    pub trash: Option<TrashView>,
+
     /// Whether the app should quit
     pub should_quit: bool,
 
-    /// Flag indicating a command execution is requested
-    pub execute_requested: bool,
+    /// Command whose execution is requested, with placeholders filled
+    pub pending_command: Option<PendingCommand>,
+
+    /// Whether the app runs as a picker for the shell integration
+    pub picker: bool,
+
+    /// Command chosen in picker mode, with placeholders filled
+    pub picked: Option<String>,
 }
 
 impl<'a> App<'a> {
     /// Creates a new App instance
     pub fn new() -> Result<Self> {
-        let storage = Storage::new()?;
-        let db = storage.load()?;
+        Self::with_storage(Storage::new()?)
+    }
+
+    /// Creates an App backed by the given storage
+    pub fn with_storage(storage: Storage) -> Result<Self> {
+        let db = storage.load_merged()?;
 
         let mut command_input = TextArea::default();
         command_input.set_placeholder_text("Enter command...");
@@ -115,39 +483,112 @@ impl<'a> App<'a> {
         tags_input.set_placeholder_text("Enter tags (comma-separated)...");
         tags_input.set_cursor_line_style(ratatui::style::Style::default());
 
-        Ok(Self {
+        let tag_tree = TagTree::new(&db);
+        let mut app = Self {
             mode: Mode::Normal,
             db,
             storage,
             selected_index: 0,
+            visible: Vec::new(),
+            filter_input: empty_filter_input(),
+            filter_error: None,
+            sort_order: SortOrder::Default,
             command_input,
             description_input,
             tags_input,
             editing_id: None,
             status_message: None,
+            placeholder_form: None,
+            tag_manager: None,
+            tag_tree,
+            trash: None,
             should_quit: false,
-            execute_requested: false,
-        })
+            pending_command: None,
+            picker: false,
+            picked: None,
+        };
+        app.apply_filter();
+        Ok(app)
+    }
+
+    /// Creates an App that picks a command for the shell, starting in
+    /// filter mode with the given query
+    pub fn picker(query: Option<&str>) -> Result<Self> {
+        Self::picker_with_storage(Storage::new()?, query)
+    }
+
+    /// Creates a picker backed by the given storage
+    pub fn picker_with_storage(storage: Storage, query: Option<&str>) -> Result<Self> {
+        let mut app = Self::with_storage(storage)?;
+        app.picker = true;
+        app.mode = Mode::Filtering;
+        if let Some(query) = query {
+            app.filter_input.insert_str(query);
+            app.update(Message::FilterChanged)?;
+        }
+        Ok(app)
     }
 
     /// Returns the currently selected command entry
     pub fn selected_entry(&self) -> Option<&CommandEntry> {
-        self.db.entries.get(self.selected_index)
+        self.visible
+            .get(self.selected_index)
+            .and_then(|v| self.db.entries.get(v.index))
     }
 
-    /// Returns the command string of the selected entry
-    pub fn selected_command(&self) -> Option<String> {
-        self.selected_entry().map(|e| e.command.clone())
+    /// Returns the path of the project file merged in, if any
+    pub fn project_path(&self) -> Option<&std::path::Path> {
+        self.storage.project().map(|p| p.path())
     }
 
-    /// Returns the number of entries
+    /// Returns the number of entries in the visible list
     pub fn entry_count(&self) -> usize {
-        self.db.entries.len()
+        self.visible.len()
+    }
+
+    /// Returns the current filter text
+    pub fn filter_text(&self) -> String {
+        self.filter_input.lines().join(" ")
+    }
+
+    /// Returns whether a filter or the tag tree is narrowing the list
+    pub fn is_filtered(&self) -> bool {
+        !self.filter_text().trim().is_empty() || self.tag_tree.scope.is_some()
     }
 
     /// Update the application state based on a message (Update in Elm Architecture)
     pub fn update(&mut self, msg: Message) -> Result<()> {
         match msg {
+            Message::MoveUp if self.mode == Mode::ManagingTags => {
+                if let Some(manager) = &mut self.tag_manager {
+                    manager.selected = manager.selected.saturating_sub(1);
+                }
+            }
+            Message::MoveDown if self.mode == Mode::ManagingTags => {
+                if let Some(manager) = &mut self.tag_manager {
+                    if manager.selected + 1 < manager.tags.len() {
+                        manager.selected += 1;
+                    }
+                }
+            }
+            Message::MoveUp if self.mode == Mode::BrowsingTrash => {
+                if let Some(trash) = &mut self.trash {
+                    trash.selected = trash.selected.saturating_sub(1);
+                }
+            }
+            Message::MoveDown if self.mode == Mode::BrowsingTrash => {
+                if let Some(trash) = &mut self.trash {
+                    if trash.selected + 1 < trash.entries.len() {
+                        trash.selected += 1;
+                    }
+                }
+            }
+            Message::MoveUp if self.mode == Mode::BrowsingTagTree => {
+                self.tag_tree.move_by(false);
+            }
+            Message::MoveDown if self.mode == Mode::BrowsingTagTree => {
+                self.tag_tree.move_by(true);
+            }
             Message::MoveUp => {
                 if self.selected_index > 0 {
                     self.selected_index -= 1;
@@ -162,7 +603,7 @@ impl<'a> App<'a> {
                 self.selected_index = 0;
             }
             Message::MoveToBottom => {
-                if !self.db.entries.is_empty() {
+                if !self.visible.is_empty() {
                     self.selected_index = self.entry_count() - 1;
                 }
             }
@@ -180,7 +621,7 @@ impl<'a> App<'a> {
             }
             Message::StartEditing => {
                 if let Some(entry) = self.selected_entry().cloned() {
-                    self.editing_id = Some(entry.id);
+                    self.editing_id = Some(entry.reference());
                     self.load_entry_to_inputs(&entry);
                     self.mode = Mode::Editing(InputField::Command);
                 }
@@ -202,6 +643,11 @@ impl<'a> App<'a> {
                 Mode::Editing(field) => {
                     self.mode = Mode::Editing(Self::next_field(field));
                 }
+                Mode::FillingPlaceholders => {
+                    if let Some(form) = &mut self.placeholder_form {
+                        form.focused = (form.focused + 1) % form.inputs.len();
+                    }
+                }
                 _ => {}
             },
             Message::PrevField => match &self.mode {
@@ -211,10 +657,15 @@ impl<'a> App<'a> {
                 Mode::Editing(field) => {
                     self.mode = Mode::Editing(Self::prev_field(field));
                 }
+                Mode::FillingPlaceholders => {
+                    if let Some(form) = &mut self.placeholder_form {
+                        form.focused = (form.focused + form.inputs.len() - 1) % form.inputs.len();
+                    }
+                }
                 _ => {}
             },
             Message::StartDelete => {
-                if !self.db.entries.is_empty() {
+                if self.selected_entry().is_some() {
                     self.mode = Mode::ConfirmDelete;
                 }
             }
@@ -225,14 +676,151 @@ impl<'a> App<'a> {
                 self.delete_selected()?;
                 self.mode = Mode::Normal;
             }
+            Message::ConfirmFill => {
+                self.confirm_fill()?;
+            }
+            Message::CancelFill => {
+                self.mode = if self.picker {
+                    Mode::Filtering
+                } else {
+                    Mode::Normal
+                };
+                self.placeholder_form = None;
+            }
             Message::CopyToClipboard => {
-                self.copy_to_clipboard()?;
+                if let Some(entry) = self.selected_entry().cloned() {
+                    self.start_action(PendingAction::Copy, entry.reference(), entry.command)?;
+                }
             }
             Message::ExecuteCommand => {
-                if self.selected_entry().is_some() {
-                    self.execute_requested = true;
+                if let Some(entry) = self.selected_entry().cloned() {
+                    let action = if self.picker {
+                        PendingAction::Insert
+                    } else {
+                        PendingAction::Execute {
+                            shell: entry.shell.clone(),
+                        }
+                    };
+                    self.start_action(action, entry.reference(), entry.command)?;
+                }
+            }
+            Message::Undo => {
+                self.undo_redo(true)?;
+            }
+            Message::Redo => {
+                self.undo_redo(false)?;
+            }
+            Message::ToggleSort => {
+                self.sort_order = match self.sort_order {
+                    SortOrder::Default => SortOrder::Frecency,
+                    SortOrder::Frecency => SortOrder::Default,
+                };
+                let selected_id = self.selected_entry().map(|e| e.reference());
+                self.refilter(selected_id);
+                self.status_message = Some(match self.sort_order {
+                    SortOrder::Default => "Sorted by ID / relevance".to_string(),
+                    SortOrder::Frecency => "Sorted by frecency".to_string(),
+                });
+            }
+            Message::StartFilter => {
+                self.mode = Mode::Filtering;
+            }
+            Message::FilterChanged => {
+                self.apply_filter();
+                // Jump to the best match
+                self.selected_index = 0;
+            }
+            Message::ConfirmFilter => {
+                self.mode = Mode::Normal;
+            }
+            Message::ClearFilter => {
+                let selected_id = self.selected_entry().map(|e| e.reference());
+                self.filter_input = empty_filter_input();
+                self.tag_tree.scope = None;
+                self.mode = Mode::Normal;
+                self.refilter(selected_id);
+            }
+            Message::OpenTags => {
+                self.tag_manager = Some(TagManager::new(&self.storage.peek()?));
+                self.mode = Mode::ManagingTags;
+            }
+            Message::CloseTags => {
+                self.tag_manager = None;
+                self.mode = Mode::Normal;
+            }
+            Message::StartRenameTag => {
+                if let Some(manager) = &mut self.tag_manager {
+                    if let Some(tag) = manager.selected_tag() {
+                        let mut input = TextArea::new(vec![tag.to_string()]);
+                        input.move_cursor(tui_textarea::CursorMove::End);
+                        input.set_cursor_line_style(ratatui::style::Style::default());
+                        manager.rename_input = Some(input);
+                    }
+                }
+            }
+            Message::ConfirmRenameTag => {
+                self.rename_selected_tag()?;
+            }
+            Message::StartRemoveTag => {
+                if let Some(manager) = &mut self.tag_manager {
+                    manager.confirm_remove = manager.selected_tag().is_some();
+                }
+            }
+            Message::ConfirmRemoveTag => {
+                self.remove_selected_tag()?;
+            }
+            Message::CancelTagAction => {
+                if let Some(manager) = &mut self.tag_manager {
+                    manager.rename_input = None;
+                    manager.confirm_remove = false;
+                }
+            }
+            Message::FilterByTag => {
+                let tag = self
+                    .tag_manager
+                    .as_ref()
+                    .and_then(|m| m.selected_tag())
+                    .map(str::to_string);
+                if let Some(tag) = tag {
+                    self.filter_input = empty_filter_input();
+                    self.filter_input
+                        .insert_str(format!("tag:\"{}\"", tag.replace('"', "")));
+                    self.tag_manager = None;
+                    self.mode = Mode::Normal;
+                    self.apply_filter();
+                    self.selected_index = 0;
+                }
+            }
+            Message::FocusTagTree => {
+                if !self.tag_tree.roots.is_empty() {
+                    self.mode = Mode::BrowsingTagTree;
                 }
             }
+            Message::FocusList => {
+                self.mode = Mode::Normal;
+            }
+            Message::ExpandTag => {
+                self.tag_tree.expand();
+            }
+            Message::CollapseTag => {
+                self.tag_tree.collapse();
+            }
+            Message::SelectTagScope => {
+                self.tag_tree.scope = self.tag_tree.selected_node().map(|n| n.path.clone());
+                self.apply_filter();
+                self.selected_index = 0;
+            }
+            Message::OpenTrash => {
+                self.trash = Some(TrashView::new(&self.storage.peek()?));
+                self.mode = Mode::BrowsingTrash;
+            }
+            Message::CloseTrash => {
+                self.trash = None;
+                self.mode = Mode::Normal;
+            }
+            Message::RestoreFromTrash => {
+                self.restore_selected_from_trash()?;
+            }
             Message::Quit => {
                 self.should_quit = true;
             }
@@ -240,6 +828,150 @@ impl<'a> App<'a> {
         Ok(())
     }
 
+    /// Renames the selected tag to the name typed in the tag manager
+    ///
+    /// Renaming onto a tag that already exists merges the two.
+    fn rename_selected_tag(&mut self) -> Result<()> {
+        let Some(manager) = &self.tag_manager else {
+            return Ok(());
+        };
+        let (Some(old), Some(input)) = (manager.selected_tag(), &manager.rename_input) else {
+            return Ok(());
+        };
+        let old = old.to_string();
+        let new = input.lines().join(" ").trim().to_string();
+
+        if new.is_empty() || new.contains(',') {
+            self.status_message = Some("✗ Tag names cannot be empty or contain commas".to_string());
+            return Ok(());
+        }
+
+        let merging = !new.eq_ignore_ascii_case(&old) && self.storage.peek()?.has_tag(&new);
+        let selected_id = self.selected_entry().map(|e| e.reference());
+        let changed = self.mutate(Source::Global, |db| db.replace_tags(&[&old], Some(&new)))?;
+        self.refilter(selected_id);
+        let global = self.storage.peek()?;
+        if let Some(manager) = &mut self.tag_manager {
+            manager.reload(&global, Some(&new));
+        }
+
+        self.status_message = Some(if merging {
+            format!("✓ Merged '{}' into '{}' ({} command(s))", old, new, changed)
+        } else {
+            format!("✓ Renamed '{}' to '{}' ({} command(s))", old, new, changed)
+        });
+        Ok(())
+    }
+
+    /// Removes the selected tag from every entry
+    fn remove_selected_tag(&mut self) -> Result<()> {
+        let Some(tag) = self
+            .tag_manager
+            .as_ref()
+            .and_then(|m| m.selected_tag())
+            .map(str::to_string)
+        else {
+            return Ok(());
+        };
+
+        let selected_id = self.selected_entry().map(|e| e.reference());
+        let changed = self.mutate(Source::Global, |db| db.replace_tags(&[&tag], None))?;
+        self.refilter(selected_id);
+        let global = self.storage.peek()?;
+        if let Some(manager) = &mut self.tag_manager {
+            manager.reload(&global, None);
+        }
+
+        self.status_message = Some(format!(
+            "✓ Removed tag '{}' from {} command(s)",
+            tag, changed
+        ));
+        Ok(())
+    }
+
+    /// Restores the entry selected in the trash view, keeping the view open
+    fn restore_selected_from_trash(&mut self) -> Result<()> {
+        let Some(id) = self
+            .trash
+            .as_ref()
+            .and_then(|t| t.selected_entry())
+            .map(|e| e.id)
+        else {
+            return Ok(());
+        };
+
+        let selected_id = self.selected_entry().map(|e| e.reference());
+        self.status_message = Some(
+            match self.mutate(Source::Global, |db| db.restore_from_trash(id))? {
+                Some(restored) if restored == id => format!("✓ Command {} restored", id),
+                Some(restored) => format!("✓ Command {} restored as {}", id, restored),
+                None => format!("✗ Command {} is no longer in the trash", id),
+            },
+        );
+        self.refilter(selected_id);
+
+        let global = self.storage.peek()?;
+        if let Some(trash) = &mut self.trash {
+            let selected = trash.selected;
+            *trash = TrashView::new(&global);
+            trash.selected = selected.min(trash.entries.len().saturating_sub(1));
+        }
+        Ok(())
+    }
+
+    /// Recomputes the visible entries from the filter
+    ///
+    /// While the filter can't be parsed (e.g. a quote is still open) the
+    /// previous results stay in place.
+    fn apply_filter(&mut self) {
+        let text = self.filter_text();
+        let query = match Query::parse(&text) {
+            Ok(query) => query,
+            Err(e) => {
+                self.filter_error = Some(e.to_string());
+                return;
+            }
+        };
+        self.filter_error = None;
+
+        let positions: HashMap<EntryRef, usize> = self
+            .db
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (e.reference(), i))
+            .collect();
+        let mut hits = self.db.search_ranked(&query);
+        if let Some(scope) = &self.tag_tree.scope {
+            hits.retain(|hit| hit.entry.tags.iter().any(|t| tags::matches(t, scope)));
+        }
+        if self.sort_order == SortOrder::Frecency {
+            models::sort_by_frecency(&mut hits, |hit| hit.entry);
+        }
+        self.visible = hits
+            .into_iter()
+            .map(|hit| VisibleEntry {
+                index: positions[&hit.entry.reference()],
+                command_indices: hit.command_indices,
+            })
+            .collect();
+    }
+
+    /// Recomputes the visible entries after the database changed, keeping
+    /// the entry with `keep_id` selected if it's still visible
+    fn refilter(&mut self, keep_id: Option<EntryRef>) {
+        self.tag_tree.reload(&self.db);
+        self.apply_filter();
+        self.selected_index = keep_id
+            .and_then(|id| {
+                self.visible
+                    .iter()
+                    .position(|v| self.db.entries[v.index].reference() == id)
+            })
+            .unwrap_or(self.selected_index)
+            .min(self.entry_count().saturating_sub(1));
+    }
+
     /// Returns the next field in sequence
     fn next_field(field: &InputField) -> InputField {
         match field {
@@ -313,13 +1045,22 @@ impl<'a> App<'a> {
             tags_str.split(',').map(|s| s.trim().to_string()).collect()
         };
 
-        let id = self.db.add(command, description, tags);
-        self.storage.save(&self.db)?;
-        self.status_message = Some(format!("✓ Command added (ID: {})", id));
+        let id =
+            EntryRef::global(self.mutate(Source::Global, |db| db.add(command, description, tags))?);
         self.clear_inputs();
 
-        // Select the newly added item
-        self.selected_index = self.entry_count().saturating_sub(1);
+        // Select the newly added item, unless the filter hides it
+        let selected_id = self.selected_entry().map(|e| e.reference());
+        self.refilter(Some(id));
+        if self.selected_entry().map(|e| e.reference()) == Some(id) {
+            self.status_message = Some(format!("✓ Command added (ID: {})", id));
+        } else {
+            self.refilter(selected_id);
+            self.status_message = Some(format!(
+                "✓ Command added (ID: {}), hidden by the current filter",
+                id
+            ));
+        }
 
         Ok(())
     }
@@ -346,43 +1087,161 @@ impl<'a> App<'a> {
             tags_str.split(',').map(|s| s.trim().to_string()).collect()
         };
 
-        if self
-            .db
-            .update(id, Some(command), Some(description), Some(tags))
-        {
-            self.storage.save(&self.db)?;
+        if self.mutate(id.source, |db| {
+            db.update(id.id, Some(command), Some(description), Some(tags))
+        })? {
             self.status_message = Some(format!("✓ Command {} updated", id));
         } else {
             self.status_message = Some(format!("✗ Command {} not found", id));
         }
+        self.refilter(Some(id));
 
         self.clear_inputs();
         Ok(())
     }
 
-    /// Deletes the currently selected command
+    /// Undoes or redoes the last change to the global database (see
+    /// [`crate::storage::journal`])
+    fn undo_redo(&mut self, undo: bool) -> Result<()> {
+        let (result, verb) = if undo {
+            (self.storage.undo(), "undo")
+        } else {
+            (self.storage.redo(), "redo")
+        };
+        // Select the entry that changed, falling back to the selection
+        let mut selected_id = self.selected_entry().map(|e| e.reference());
+        if let Ok(Some(record)) = &result {
+            let remains = |c: &&Change| {
+                if undo {
+                    c.before.is_some()
+                } else {
+                    c.after.is_some()
+                }
+            };
+            if let Some(change) = record.changes.iter().find(remains) {
+                selected_id = Some(EntryRef::global(change.id));
+            }
+        }
+        self.status_message = Some(match result {
+            Ok(Some(record)) => format!(
+                "✓ {} #{}: {}",
+                if undo { "Undid" } else { "Redid" },
+                record.seq,
+                record.summary()
+            ),
+            Ok(None) => format!("Nothing to {}", verb),
+            Err(e) => format!("✗ {}", e),
+        });
+        self.db = self.storage.load_merged()?;
+        self.refilter(selected_id);
+        Ok(())
+    }
+
+    /// Deletes the currently selected command, moving global ones to the
+    /// trash
     fn delete_selected(&mut self) -> Result<()> {
         if let Some(entry) = self.selected_entry() {
-            let id = entry.id;
-            self.db.remove_by_id(id);
-            self.storage.save(&self.db)?;
-            self.status_message = Some(format!("✓ Command {} deleted", id));
+            let id = entry.reference();
+            if id.source == Source::Global {
+                self.mutate(id.source, |db| db.trash_by_id(id.id))?;
+                self.status_message = Some(format!("✓ Command {} moved to the trash", id));
+            } else {
+                self.mutate(id.source, |db| db.remove_by_id(id.id))?;
+                self.status_message = Some(format!("✓ Command {} deleted", id));
+            }
 
-            // Adjust selection if needed
-            if self.selected_index >= self.entry_count() && self.selected_index > 0 {
-                self.selected_index -= 1;
+            // The next entry moves up into the selection
+            self.refilter(None);
+        }
+        Ok(())
+    }
+
+    /// Starts copying or executing a command
+    ///
+    /// Commands with placeholders open the placeholder form first.
+    fn start_action(
+        &mut self,
+        action: PendingAction,
+        entry_id: EntryRef,
+        command: String,
+    ) -> Result<()> {
+        if template::has_placeholders(&command) {
+            self.placeholder_form = Some(PlaceholderForm::new(action, entry_id, command));
+            self.mode = Mode::FillingPlaceholders;
+            Ok(())
+        } else {
+            self.perform_action(action, entry_id, command)
+        }
+    }
+
+    /// Renders the placeholder form and performs its action
+    fn confirm_fill(&mut self) -> Result<()> {
+        let Some(form) = &self.placeholder_form else {
+            return Ok(());
+        };
+
+        match form.render() {
+            Ok(command) => {
+                let action = form.action.clone();
+                let entry_id = form.entry_id;
+                self.placeholder_form = None;
+                self.mode = Mode::Normal;
+                self.perform_action(action, entry_id, command)?;
+            }
+            Err(e) => {
+                self.status_message = Some(format!("✗ {}", e));
             }
         }
         Ok(())
     }
 
-    /// Copies the selected command to clipboard
-    fn copy_to_clipboard(&mut self) -> Result<()> {
-        if let Some(entry) = self.selected_entry() {
-            let mut clipboard = arboard::Clipboard::new()?;
-            clipboard.set_text(&entry.command)?;
-            self.status_message = Some("✓ Copied to clipboard".to_string());
+    /// Copies or schedules execution of a fully rendered command
+    fn perform_action(
+        &mut self,
+        action: PendingAction,
+        entry_id: EntryRef,
+        command: String,
+    ) -> Result<()> {
+        match action {
+            PendingAction::Copy => {
+                self.copy_to_clipboard(&command)?;
+                self.record_use(entry_id, None)
+            }
+            PendingAction::Execute { shell } => {
+                self.pending_command = Some(PendingCommand {
+                    entry_id,
+                    command,
+                    shell,
+                });
+                Ok(())
+            }
+            PendingAction::Insert => {
+                self.picked = Some(command);
+                self.should_quit = true;
+                self.record_use(entry_id, None)
+            }
         }
+    }
+
+    /// Records that an entry was copied or run, keeping it selected
+    ///
+    /// Project entries keep no usage statistics (see
+    /// [`Storage::record_use`]).
+    pub fn record_use(&mut self, id: EntryRef, exit_code: Option<i32>) -> Result<()> {
+        if id.source == Source::Project {
+            return Ok(());
+        }
+        let selected_id = self.selected_entry().map(|e| e.reference());
+        self.mutate(Source::Global, |db| db.record_use(id.id, exit_code))?;
+        self.refilter(selected_id);
+        Ok(())
+    }
+
+    /// Copies a command to clipboard
+    fn copy_to_clipboard(&mut self, command: &str) -> Result<()> {
+        let mut clipboard = arboard::Clipboard::new()?;
+        clipboard.set_text(command)?;
+        self.status_message = Some("✓ Copied to clipboard".to_string());
         Ok(())
     }
 
@@ -394,6 +1253,15 @@ impl<'a> App<'a> {
                 InputField::Description => &mut self.description_input,
                 InputField::Tags => &mut self.tags_input,
             }),
+            Mode::FillingPlaceholders => self
+                .placeholder_form
+                .as_mut()
+                .and_then(|form| form.inputs.get_mut(form.focused)),
+            Mode::Filtering => Some(&mut self.filter_input),
+            Mode::ManagingTags => self
+                .tag_manager
+                .as_mut()
+                .and_then(|manager| manager.rename_input.as_mut()),
             _ => None,
         }
     }
@@ -406,10 +1274,305 @@ impl<'a> App<'a> {
         }
     }
 
-    /// Reloads the database from storage
-    #[allow(dead_code)]
-    pub fn reload(&mut self) -> Result<()> {
-        self.db = self.storage.load()?;
+    /// Applies a change to the global database or the project file and
+    /// saves it
+    ///
+    /// The change is applied to the latest state on disk (for the database,
+    /// while holding the storage lock), so edits made by other processes
+    /// since the last load are kept rather than overwritten.
+    fn mutate<T>(
+        &mut self,
+        source: Source,
+        f: impl FnOnce(&mut CommandDatabase) -> T,
+    ) -> Result<T> {
+        let result = self
+            .storage
+            .update_in(source, |db| Ok::<_, CmdKeeperError>(f(db)))?;
+        self.db = self.storage.load_merged()?;
+        Ok(result)
+    }
+
+    /// Reloads the database if another process changed it on disk
+    ///
+    /// Only done in Normal mode so that a pending action never ends up
+    /// targeting a different entry. The selection follows the selected entry.
+    pub fn refresh_if_changed(&mut self) -> Result<()> {
+        if self.mode != Mode::Normal || !self.storage.changed_on_disk()? {
+            return Ok(());
+        }
+
+        let selected_id = self.selected_entry().map(|e| e.reference());
+        self.db = self.storage.load_merged()?;
+        self.refilter(selected_id);
+        self.status_message = Some("↻ Reloaded changes from disk".to_string());
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::{tempdir, TempDir};
+
+    fn app_with(commands: &[&str]) -> (TempDir, App<'static>) {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_path(dir.path().join("commands.json"));
+        storage
+            .update(|db| {
+                for command in commands {
+                    db.add(command.to_string(), String::new(), vec![]);
+                }
+                Ok::<_, CmdKeeperError>(())
+            })
+            .unwrap();
+        (dir, App::with_storage(storage).unwrap())
+    }
+
+    fn set_filter(app: &mut App, text: &str) {
+        app.update(Message::StartFilter).unwrap();
+        app.filter_input.insert_str(text);
+        app.update(Message::FilterChanged).unwrap();
+    }
+
+    fn visible_ids(app: &App) -> Vec<u64> {
+        app.visible
+            .iter()
+            .map(|v| app.db.entries[v.index].id)
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_selects_best_match() {
+        let (_dir, mut app) = app_with(&["ls -la", "git status", "git checkout main"]);
+        app.update(Message::MoveToBottom).unwrap();
+
+        set_filter(&mut app, "gco");
+        assert_eq!(visible_ids(&app), vec![3]);
+        assert_eq!(app.selected_index, 0);
+        assert_eq!(app.selected_entry().unwrap().id, 3);
+
+        app.update(Message::ClearFilter).unwrap();
+        assert_eq!(visible_ids(&app), vec![1, 2, 3]);
+        // The selection stays on the entry that was selected
+        assert_eq!(app.selected_entry().unwrap().id, 3);
+    }
+
+    #[test]
+    fn test_invalid_filter_keeps_results() {
+        let (_dir, mut app) = app_with(&["ls -la", "git status"]);
+
+        set_filter(&mut app, "git \"unterminated");
+        assert!(app.filter_error.is_some());
+        assert_eq!(visible_ids(&app), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_tag_manager_renames_and_removes() {
+        let (_dir, mut app) = app_with(&["kubectl get pods", "helm list", "ls"]);
+        app.mutate(Source::Global, |db| {
+            db.update(1, None, None, Some(vec!["k8s".to_string()]));
+            db.update(2, None, None, Some(vec!["kubernetes".to_string()]));
+        })
+        .unwrap();
+
+        app.update(Message::OpenTags).unwrap();
+        app.update(Message::MoveDown).unwrap();
+        assert_eq!(
+            app.tag_manager.as_ref().unwrap().selected_tag(),
+            Some("kubernetes")
+        );
+
+        // Renaming onto an existing tag merges the two
+        app.update(Message::StartRenameTag).unwrap();
+        let input = app.current_textarea_mut().unwrap();
+        *input = TextArea::new(vec!["k8s".to_string()]);
+        app.update(Message::ConfirmRenameTag).unwrap();
+        let manager = app.tag_manager.as_ref().unwrap();
+        assert_eq!(manager.tags, vec![("k8s".to_string(), 2)]);
+        assert_eq!(manager.selected_tag(), Some("k8s"));
+
+        app.update(Message::FilterByTag).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(visible_ids(&app), vec![1, 2]);
+
+        app.update(Message::OpenTags).unwrap();
+        app.update(Message::StartRemoveTag).unwrap();
+        app.update(Message::ConfirmRemoveTag).unwrap();
+        assert!(app.tag_manager.as_ref().unwrap().tags.is_empty());
+        assert!(app.db.entries.iter().all(|e| e.tags.is_empty()));
+    }
+
+    #[test]
+    fn test_tag_tree_scopes_list() {
+        let (_dir, mut app) = app_with(&["aws s3 ls", "aws ec2 describe", "gcloud list", "ls"]);
+        app.mutate(Source::Global, |db| {
+            db.update(1, None, None, Some(vec!["cloud/aws/s3".to_string()]));
+            db.update(2, None, None, Some(vec!["cloud/aws/ec2".to_string()]));
+            db.update(3, None, None, Some(vec!["cloud/gcp".to_string()]));
+        })
+        .unwrap();
+        app.refilter(None);
+
+        let paths = |app: &App| -> Vec<String> {
+            app.tag_tree
+                .rows()
+                .iter()
+                .map(|row| row.node.map(|n| n.path.clone()).unwrap_or_default())
+                .collect()
+        };
+        assert_eq!(
+            paths(&app),
+            vec![
+                "",
+                "cloud",
+                "cloud/aws",
+                "cloud/aws/ec2",
+                "cloud/aws/s3",
+                "cloud/gcp"
+            ]
+        );
+
+        app.update(Message::FocusTagTree).unwrap();
+        assert_eq!(app.mode, Mode::BrowsingTagTree);
+        app.update(Message::MoveDown).unwrap();
+        app.update(Message::MoveDown).unwrap();
+        app.update(Message::SelectTagScope).unwrap();
+        assert_eq!(app.tag_tree.scope.as_deref(), Some("cloud/aws"));
+        assert_eq!(visible_ids(&app), vec![1, 2]);
+
+        // Collapsing hides the children; collapsing again selects the parent
+        app.update(Message::CollapseTag).unwrap();
+        assert_eq!(paths(&app), vec!["", "cloud", "cloud/aws", "cloud/gcp"]);
+        app.update(Message::CollapseTag).unwrap();
+        assert_eq!(app.tag_tree.selected_node().unwrap().path, "cloud");
+        app.update(Message::CollapseTag).unwrap();
+        assert_eq!(paths(&app), vec!["", "cloud"]);
+        app.update(Message::ExpandTag).unwrap();
+        assert_eq!(paths(&app).len(), 4);
+
+        // Clearing the filter also clears the scope
+        app.update(Message::FocusList).unwrap();
+        app.update(Message::ClearFilter).unwrap();
+        assert_eq!(visible_ids(&app), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_project_entries_are_edited_in_place() {
+        let dir = tempdir().unwrap();
+        let project_path = dir.path().join(".cmd-keeper.toml");
+        std::fs::write(&project_path, "[[entries]]\ncommand = \"make deploy\"\n").unwrap();
+        let storage = Storage::with_path(dir.path().join("commands.json")).with_project(
+            crate::storage::project::ProjectFile::new(project_path.clone()),
+        );
+        storage
+            .update(|db| {
+                db.add("ls".to_string(), String::new(), vec![]);
+                Ok::<_, CmdKeeperError>(())
+            })
+            .unwrap();
+        let mut app = App::with_storage(storage).unwrap();
+
+        // Both entries have ID 1 in their own file
+        let refs: Vec<String> = app
+            .visible
+            .iter()
+            .map(|v| app.db.entries[v.index].reference().to_string())
+            .collect();
+        assert_eq!(refs, vec!["1", "p1"]);
+
+        app.update(Message::MoveDown).unwrap();
+        app.record_use(app.selected_entry().unwrap().reference(), Some(0))
+            .unwrap();
+        app.update(Message::StartDelete).unwrap();
+        app.update(Message::ConfirmDelete).unwrap();
+
+        assert_eq!(visible_ids(&app), vec![1]);
+        assert_eq!(app.db.entries[0].source, Source::Global);
+        assert!(!std::fs::read_to_string(&project_path)
+            .unwrap()
+            .contains("make deploy"));
+    }
+
+    #[test]
+    fn test_undo_and_redo_delete() {
+        let (_dir, mut app) = app_with(&["ls -la", "git status"]);
+        app.update(Message::MoveDown).unwrap();
+        app.update(Message::StartDelete).unwrap();
+        app.update(Message::ConfirmDelete).unwrap();
+        assert_eq!(visible_ids(&app), vec![1]);
+
+        app.update(Message::Undo).unwrap();
+        assert_eq!(visible_ids(&app), vec![1, 2]);
+        assert_eq!(app.selected_entry().unwrap().id, 2);
+        app.update(Message::Redo).unwrap();
+        assert_eq!(visible_ids(&app), vec![1]);
+        app.update(Message::Redo).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("Nothing to redo"));
+    }
+
+    #[test]
+    fn test_trash_view_restores() {
+        let (_dir, mut app) = app_with(&["ls -la", "git status", "make"]);
+        for _ in 0..2 {
+            app.update(Message::StartDelete).unwrap();
+            app.update(Message::ConfirmDelete).unwrap();
+        }
+        assert_eq!(visible_ids(&app), vec![3]);
+
+        app.update(Message::OpenTrash).unwrap();
+        assert_eq!(app.mode, Mode::BrowsingTrash);
+        let ids = |app: &App| -> Vec<u64> {
+            let trash = app.trash.as_ref().unwrap();
+            trash.entries.iter().map(|e| e.id).collect()
+        };
+        assert_eq!(ids(&app).len(), 2);
+
+        // Restoring keeps the ID and leaves the view open
+        let id = app.trash.as_ref().unwrap().selected_entry().unwrap().id;
+        app.update(Message::RestoreFromTrash).unwrap();
+        assert_eq!(app.mode, Mode::BrowsingTrash);
+        assert_eq!(ids(&app).len(), 1);
+        assert!(visible_ids(&app).contains(&id));
+
+        app.update(Message::RestoreFromTrash).unwrap();
+        assert!(ids(&app).is_empty());
+        app.update(Message::CloseTrash).unwrap();
+        assert_eq!(visible_ids(&app), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_outside_changes_survive_lookups() {
+        let (dir, mut app) = app_with(&["ls -la"]);
+        Storage::with_path(dir.path().join("commands.json"))
+            .update(|db| {
+                db.add("make".to_string(), String::new(), vec![]);
+                Ok::<_, CmdKeeperError>(())
+            })
+            .unwrap();
+
+        // Looking at the tags or the trash doesn't hide the change
+        app.update(Message::OpenTags).unwrap();
+        app.update(Message::CloseTags).unwrap();
+        app.update(Message::OpenTrash).unwrap();
+        app.update(Message::CloseTrash).unwrap();
+        app.refresh_if_changed().unwrap();
+        assert_eq!(visible_ids(&app), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_actions_target_filtered_selection() {
+        let (_dir, mut app) = app_with(&["echo one", "git status", "echo two", "git log"]);
+
+        set_filter(&mut app, "cmd:git");
+        app.update(Message::ConfirmFilter).unwrap();
+        app.update(Message::MoveDown).unwrap();
+        assert_eq!(app.selected_entry().unwrap().id, 4);
+
+        app.update(Message::StartDelete).unwrap();
+        app.update(Message::ConfirmDelete).unwrap();
+        assert_eq!(app.db.entries.len(), 3);
+        // The filter stays in place after the change
+        assert_eq!(visible_ids(&app), vec![2]);
+        assert_eq!(app.selected_entry().unwrap().id, 2);
+    }
+}
@@ -19,17 +19,32 @@ pub fn handle_event(app: &mut App) -> Result<Option<Message>> {
 
 /// Handles a single key event based on the current mode
 fn handle_key_event(app: &mut App, key: KeyEvent) -> Option<Message> {
+    // The picker is cancelled like fzf
+    let cancel = key.code == KeyCode::Esc
+        || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL));
+    if app.picker && cancel && matches!(app.mode, Mode::Normal | Mode::Filtering) {
+        return Some(Message::Quit);
+    }
+
     match &app.mode {
-        Mode::Normal => handle_normal_mode(key),
+        Mode::Normal => handle_normal_mode(app, key),
         Mode::Adding(field) => handle_input_mode(app, key, field.clone(), false),
         Mode::Editing(field) => handle_input_mode(app, key, field.clone(), true),
         Mode::ConfirmDelete => handle_confirm_delete_mode(key),
+        Mode::FillingPlaceholders => handle_fill_mode(app, key),
+        Mode::Filtering => handle_filter_mode(app, key),
+        Mode::ManagingTags => handle_tags_mode(app, key),
+        Mode::BrowsingTagTree => handle_tag_tree_mode(key),
+        Mode::BrowsingTrash => handle_trash_mode(key),
     }
 }
 
 /// Handles key events in Normal mode
-fn handle_normal_mode(key: KeyEvent) -> Option<Message> {
+fn handle_normal_mode(app: &App, key: KeyEvent) -> Option<Message> {
     match key.code {
+        // Esc clears an active filter before quitting
+        KeyCode::Esc if app.is_filtered() => Some(Message::ClearFilter),
+
         // Quit
         KeyCode::Char('q') | KeyCode::Esc => Some(Message::Quit),
 
@@ -47,7 +62,39 @@ fn handle_normal_mode(key: KeyEvent) -> Option<Message> {
         KeyCode::Char('d') => Some(Message::StartDelete),
         KeyCode::Char('y') => Some(Message::CopyToClipboard),
         KeyCode::Char('x') | KeyCode::Enter => Some(Message::ExecuteCommand),
+        KeyCode::Char('/') => Some(Message::StartFilter),
+        KeyCode::Char('s') => Some(Message::ToggleSort),
+        KeyCode::Char('t') => Some(Message::OpenTags),
+        KeyCode::Char('T') if !app.picker => Some(Message::OpenTrash),
+        KeyCode::Char('u') => Some(Message::Undo),
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(Message::Redo),
+        KeyCode::Tab if !app.picker => Some(Message::FocusTagTree),
+
+        _ => None,
+    }
+}
+
+/// Handles key events in BrowsingTagTree mode
+fn handle_tag_tree_mode(key: KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Tab => Some(Message::FocusList),
+        KeyCode::Char('q') => Some(Message::Quit),
+        KeyCode::Char('j') | KeyCode::Down => Some(Message::MoveDown),
+        KeyCode::Char('k') | KeyCode::Up => Some(Message::MoveUp),
+        KeyCode::Char('l') | KeyCode::Right => Some(Message::ExpandTag),
+        KeyCode::Char('h') | KeyCode::Left => Some(Message::CollapseTag),
+        KeyCode::Enter | KeyCode::Char(' ') => Some(Message::SelectTagScope),
+        _ => None,
+    }
+}
 
+/// Handles key events in BrowsingTrash mode
+fn handle_trash_mode(key: KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('T') => Some(Message::CloseTrash),
+        KeyCode::Char('j') | KeyCode::Down => Some(Message::MoveDown),
+        KeyCode::Char('k') | KeyCode::Up => Some(Message::MoveUp),
+        KeyCode::Enter | KeyCode::Char('r') => Some(Message::RestoreFromTrash),
         _ => None,
     }
 }
@@ -114,3 +161,153 @@ fn handle_confirm_delete_mode(key: KeyEvent) -> Option<Message> {
         _ => None,
     }
 }
+
+/// Handles key events in FillingPlaceholders mode
+fn handle_fill_mode(app: &mut App, key: KeyEvent) -> Option<Message> {
+    let on_last_field = app
+        .placeholder_form
+        .as_ref()
+        .is_some_and(|form| form.focused + 1 == form.inputs.len());
+
+    match key.code {
+        KeyCode::Esc => Some(Message::CancelFill),
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::ConfirmFill)
+        }
+        KeyCode::Tab | KeyCode::Down => Some(Message::NextField),
+        KeyCode::BackTab | KeyCode::Up => Some(Message::PrevField),
+
+        // Enter on the last field confirms, otherwise moves to the next one
+        KeyCode::Enter if on_last_field => Some(Message::ConfirmFill),
+        KeyCode::Enter => Some(Message::NextField),
+
+        _ => {
+            if let Some(textarea) = app.current_textarea_mut() {
+                textarea.input(key);
+            }
+            None
+        }
+    }
+}
+
+/// Handles key events in Filtering mode
+fn handle_filter_mode(app: &mut App, key: KeyEvent) -> Option<Message> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+    match key.code {
+        KeyCode::Esc => Some(Message::ClearFilter),
+        // The picker takes the best match right away
+        KeyCode::Enter if app.picker => Some(Message::ExecuteCommand),
+        KeyCode::Enter => Some(Message::ConfirmFilter),
+
+        // Move through the results without leaving the filter
+        KeyCode::Down => Some(Message::MoveDown),
+        KeyCode::Up => Some(Message::MoveUp),
+        KeyCode::Char('n') if ctrl => Some(Message::MoveDown),
+        KeyCode::Char('p') if ctrl => Some(Message::MoveUp),
+
+        _ => {
+            let textarea = app.current_textarea_mut()?;
+            textarea.input(key).then_some(Message::FilterChanged)
+        }
+    }
+}
+
+/// Handles key events in ManagingTags mode
+fn handle_tags_mode(app: &mut App, key: KeyEvent) -> Option<Message> {
+    let manager = app.tag_manager.as_ref()?;
+
+    if manager.confirm_remove {
+        return match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => Some(Message::ConfirmRemoveTag),
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                Some(Message::CancelTagAction)
+            }
+            _ => None,
+        };
+    }
+
+    if manager.rename_input.is_some() {
+        return match key.code {
+            KeyCode::Esc => Some(Message::CancelTagAction),
+            KeyCode::Enter => Some(Message::ConfirmRenameTag),
+            _ => {
+                if let Some(textarea) = app.current_textarea_mut() {
+                    textarea.input(key);
+                }
+                None
+            }
+        };
+    }
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('t') => Some(Message::CloseTags),
+        KeyCode::Char('j') | KeyCode::Down => Some(Message::MoveDown),
+        KeyCode::Char('k') | KeyCode::Up => Some(Message::MoveUp),
+        KeyCode::Char('r') => Some(Message::StartRenameTag),
+        KeyCode::Char('d') => Some(Message::StartRemoveTag),
+        KeyCode::Enter => Some(Message::FilterByTag),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CmdKeeperError;
+    use crate::storage::Storage;
+    use tempfile::{tempdir, TempDir};
+
+    fn picker(commands: &[&str], query: &str) -> (TempDir, App<'static>) {
+        let dir = tempdir().unwrap();
+        let storage = Storage::with_path(dir.path().join("commands.json"));
+        storage
+            .update(|db| {
+                for command in commands {
+                    db.add(command.to_string(), String::new(), vec![]);
+                }
+                Ok::<_, CmdKeeperError>(())
+            })
+            .unwrap();
+        (dir, App::picker_with_storage(storage, Some(query)).unwrap())
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_picker_enter_picks_instead_of_running() {
+        let (_dir, mut app) = picker(&["ls -la", "git status"], "git");
+
+        let msg = handle_key_event(&mut app, key(KeyCode::Enter)).unwrap();
+        assert!(matches!(msg, Message::ExecuteCommand));
+        app.update(msg).unwrap();
+        assert_eq!(app.picked.as_deref(), Some("git status"));
+        assert!(app.pending_command.is_none());
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn test_picker_cancels_on_esc_and_ctrl_c() {
+        let (_dir, mut app) = picker(&["ls -la"], "ls");
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+
+        for mode in [Mode::Filtering, Mode::Normal] {
+            app.mode = mode;
+            for cancel in [key(KeyCode::Esc), ctrl_c] {
+                assert!(matches!(
+                    handle_key_event(&mut app, cancel),
+                    Some(Message::Quit)
+                ));
+            }
+        }
+        // Outside the picker, Esc only clears the filter
+        app.picker = false;
+        app.mode = Mode::Filtering;
+        assert!(matches!(
+            handle_key_event(&mut app, key(KeyCode::Esc)),
+            Some(Message::ClearFilter)
+        ));
+    }
+}
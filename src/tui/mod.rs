@@ -5,6 +5,7 @@
 
 mod app;
 mod event;
+mod select;
 mod ui;
 
 use anyhow::{Context, Result};
@@ -15,24 +16,59 @@ use crossterm::{
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io::{self, Write};
-use std::process::Command;
+
+use crate::executor;
 
 pub use app::App;
+use app::PendingCommand;
+pub use select::select_many;
 
 /// Runs the TUI application
 pub fn run() -> Result<()> {
+    let mut app = App::new()?;
+    with_terminal(io::stdout(), |terminal| run_app(terminal, &mut app))
+}
+
+/// Runs the TUI as a picker and returns the chosen command with its
+/// placeholders filled, or None if the picker was cancelled
+///
+/// The UI is drawn on the terminal (`/dev/tty`) rather than stdout, so the
+/// caller can capture the result, e.g. `$(cmd-keeper pick)`.
+pub fn pick(query: Option<&str>) -> Result<Option<String>> {
+    let mut app = App::picker(query)?;
+    with_terminal(open_tty()?, |terminal| run_app(terminal, &mut app))?;
+    Ok(app.picked.take())
+}
+
+/// Opens the controlling terminal for drawing
+#[cfg(unix)]
+fn open_tty() -> Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .context("Failed to open the terminal (/dev/tty)")
+}
+
+/// Opens the controlling terminal for drawing
+#[cfg(not(unix))]
+fn open_tty() -> Result<io::Stderr> {
+    Ok(io::stderr())
+}
+
+/// Sets up the terminal on `out`, runs `f` and restores the terminal
+fn with_terminal<W: Write, T>(
+    mut out: W,
+    f: impl FnOnce(&mut Terminal<CrosstermBackend<W>>) -> Result<T>,
+) -> Result<T> {
     // Setup terminal
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    execute!(out, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(out);
     let mut terminal = Terminal::new(backend)?;
 
-    // Create app state
-    let mut app = App::new()?;
-
     // Main loop
-    let result = run_app(&mut terminal, &mut app);
+    let result = f(&mut terminal);
 
     // Restore terminal
     disable_raw_mode()?;
@@ -47,7 +83,7 @@ pub fn run() -> Result<()> {
 }
 
 /// Main application loop
-fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> Result<()> {
+fn run_app<W: Write>(terminal: &mut Terminal<CrosstermBackend<W>>, app: &mut App) -> Result<()> {
     loop {
         // Draw the UI
         terminal.draw(|frame| ui::render(frame, app))?;
@@ -57,29 +93,28 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App)
             app.update(msg)?;
         }
 
+        // Pick up changes made by other processes (e.g. `cmd-keeper add`)
+        app.refresh_if_changed()?;
+
         // Check if command execution is requested
-        if app.execute_requested {
-            app.execute_requested = false;
-
-            if let Some(command) = app.selected_command() {
-                // Execute the command
-                let result = execute_command(terminal, &command);
-
-                // Update status message based on result
-                match result {
-                    Ok(exit_code) => {
-                        if exit_code == 0 {
-                            app.status_message =
-                                Some("✓ Command executed successfully".to_string());
-                        } else {
-                            app.status_message =
-                                Some(format!("⚠ Command exited with code {}", exit_code));
-                        }
-                    }
-                    Err(e) => {
-                        app.status_message = Some(format!("✗ Error: {}", e));
+        if let Some(pending) = app.pending_command.take() {
+            // Execute the command
+            let result = execute_command(terminal, &pending);
+
+            // Update status message based on result
+            match result {
+                Ok(exit_code) => {
+                    app.record_use(pending.entry_id, Some(exit_code))?;
+                    if exit_code == 0 {
+                        app.status_message = Some("✓ Command executed successfully".to_string());
+                    } else {
+                        app.status_message =
+                            Some(format!("⚠ Command exited with code {}", exit_code));
                     }
                 }
+                Err(e) => {
+                    app.status_message = Some(format!("✗ Error: {}", e));
+                }
             }
         }
 
@@ -96,12 +131,12 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App)
 ///
 /// This function:
 /// 1. Leaves the alternate screen and disables raw mode
-/// 2. Executes the command with inherited stdin/stdout/stderr
+/// 2. Executes the command through the shell with inherited stdin/stdout/stderr
 /// 3. Waits for user to press Enter
 /// 4. Restores the TUI
-fn execute_command(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    command: &str,
+fn execute_command<W: Write>(
+    terminal: &mut Terminal<CrosstermBackend<W>>,
+    pending: &PendingCommand,
 ) -> Result<i32> {
     // Step 1: Leave alternate screen and disable raw mode
     disable_raw_mode()?;
@@ -111,46 +146,20 @@ fn execute_command(
         DisableMouseCapture
     )?;
 
-    // Step 2: Parse the command using shell-words
-    let args = shell_words::split(command).context("Failed to parse command")?;
-
-    if args.is_empty() {
-        // Re-enter TUI immediately if command is empty
-        enable_raw_mode()?;
-        execute!(
-            terminal.backend_mut(),
-            EnterAlternateScreen,
-            EnableMouseCapture
-        )?;
-        terminal.clear()?;
-        anyhow::bail!("Empty command");
-    }
+    // Step 2: Print command being executed
+    println!("\n\x1b[1;36m$ {}\x1b[0m\n", pending.command);
 
-    // Step 3: Print command being executed
-    println!("\n\x1b[1;36m$ {}\x1b[0m\n", command);
-
-    // Step 4: Execute the command
-    let exit_code = match Command::new(&args[0])
-        .args(&args[1..])
-        .stdin(std::process::Stdio::inherit())
-        .stdout(std::process::Stdio::inherit())
-        .stderr(std::process::Stdio::inherit())
-        .spawn()
-    {
-        Ok(mut child) => {
-            let status = child.wait().context("Failed to wait for command")?;
-            status.code().unwrap_or(-1)
-        }
+    // Step 3: Execute the command through the shell
+    let shell = executor::resolve_shell(None, pending.shell.as_deref());
+    let exit_code = match executor::run(&shell, &pending.command) {
+        Ok(code) => code,
         Err(e) => {
             eprintln!("\n\x1b[1;31mError:\x1b[0m {}", e);
-            if e.kind() == std::io::ErrorKind::NotFound {
-                eprintln!("Command not found: {}", args[0]);
-            }
             -1
         }
     };
 
-    // Step 5: Print exit status and wait for user input
+    // Step 4: Print exit status and wait for user input
     println!();
     if exit_code == 0 {
         println!("\x1b[1;32m✓ Command completed successfully\x1b[0m");
@@ -165,7 +174,7 @@ fn execute_command(
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
 
-    // Step 6: Re-enter alternate screen and enable raw mode
+    // Step 5: Re-enter alternate screen and enable raw mode
     enable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
@@ -0,0 +1,267 @@
+//! Multi-selection list
+//!
+//! A small fzf-like list: typing filters the items, Tab marks the
+//! highlighted one and Enter confirms. Used to choose what to import.
+
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style, Stylize},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame, Terminal,
+};
+use tui_textarea::TextArea;
+
+use super::with_terminal;
+
+/// Lets the user mark any number of items
+///
+/// Returns the indices of the marked items in ascending order, or None if
+/// the selection was cancelled.
+pub fn select_many(title: &str, items: &[String]) -> Result<Option<Vec<usize>>> {
+    let mut selector = Selector::new(title, items);
+    with_terminal(io::stdout(), |terminal| selector.run(terminal))
+}
+
+/// What a key press did to the selection
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Confirmed(Vec<usize>),
+    Cancelled,
+}
+
+/// State of the selection list
+struct Selector<'a> {
+    title: &'a str,
+    items: &'a [String],
+    filter: TextArea<'a>,
+    /// Indices of the items matching the filter
+    visible: Vec<usize>,
+    /// Indices of the marked items
+    marked: BTreeSet<usize>,
+    /// Highlighted position in `visible`
+    selected: usize,
+}
+
+impl<'a> Selector<'a> {
+    fn new(title: &'a str, items: &'a [String]) -> Self {
+        let mut filter = TextArea::default();
+        filter.set_placeholder_text("Type to filter...");
+        filter.set_cursor_line_style(Style::default());
+
+        Self {
+            title,
+            items,
+            filter,
+            visible: (0..items.len()).collect(),
+            marked: BTreeSet::new(),
+            selected: 0,
+        }
+    }
+
+    fn run<W: Write>(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<W>>,
+    ) -> Result<Option<Vec<usize>>> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+
+            if let Event::Key(key) = event::read()? {
+                match self.handle_key(key) {
+                    Some(Outcome::Confirmed(indices)) => return Ok(Some(indices)),
+                    Some(Outcome::Cancelled) => return Ok(None),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Outcome> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Esc => return Some(Outcome::Cancelled),
+            KeyCode::Char('c') if ctrl => return Some(Outcome::Cancelled),
+            KeyCode::Enter => {
+                // Like fzf, Enter with nothing marked takes the highlighted item
+                if self.marked.is_empty() {
+                    self.marked.extend(self.visible.get(self.selected));
+                }
+                return Some(Outcome::Confirmed(self.marked.iter().copied().collect()));
+            }
+            KeyCode::Tab => {
+                if let Some(&index) = self.visible.get(self.selected) {
+                    if !self.marked.remove(&index) {
+                        self.marked.insert(index);
+                    }
+                    self.move_down();
+                }
+            }
+            KeyCode::Char('a') if ctrl => {
+                // Marks every visible item, or unmarks them if all are marked
+                if self.visible.iter().all(|i| self.marked.contains(i)) {
+                    for i in &self.visible {
+                        self.marked.remove(i);
+                    }
+                } else {
+                    self.marked.extend(&self.visible);
+                }
+            }
+            KeyCode::Down => self.move_down(),
+            KeyCode::Char('n') if ctrl => self.move_down(),
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('p') if ctrl => self.selected = self.selected.saturating_sub(1),
+            _ => {
+                if self.filter.input(key) {
+                    self.apply_filter();
+                }
+            }
+        }
+        None
+    }
+
+    fn move_down(&mut self) {
+        if self.selected + 1 < self.visible.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Keeps the items containing every word of the filter (ignoring case)
+    fn apply_filter(&mut self) {
+        let filter = self.filter.lines().join(" ").to_lowercase();
+        let words: Vec<&str> = filter.split_whitespace().collect();
+
+        self.visible = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| {
+                let item = item.to_lowercase();
+                words.iter().all(|w| item.contains(w))
+            })
+            .map(|(i, _)| i)
+            .collect();
+        self.selected = 0;
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3), // Filter
+                Constraint::Min(3),    // List
+                Constraint::Length(1), // Help bar
+            ])
+            .split(frame.area());
+
+        self.filter.set_block(
+            Block::default()
+                .title(" Filter ")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+        frame.render_widget(&self.filter, chunks[0]);
+
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|&i| {
+                let mark = if self.marked.contains(&i) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                // Show multi-line commands on one line
+                let text = self.items[i].replace('\n', " ⏎ ");
+                ListItem::new(format!(" {} {}", mark, text))
+            })
+            .collect();
+
+        let title = format!(
+            " {} ({} marked, {}/{} shown) ",
+            self.title,
+            self.marked.len(),
+            self.visible.len(),
+            self.items.len()
+        );
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(title)
+                    .title_style(Style::default().fg(Color::Cyan).bold())
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            )
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            );
+
+        let mut state = ListState::default();
+        state.select((!self.visible.is_empty()).then_some(self.selected));
+        frame.render_stateful_widget(list, chunks[1], &mut state);
+
+        let help = Paragraph::new(
+            " Tab: Mark │ Ctrl+A: Mark all │ ↑/↓: Move │ Enter: Confirm │ Esc: Cancel ",
+        )
+        .style(Style::default().fg(Color::DarkGray).bg(Color::Black));
+        frame.render_widget(help, chunks[2]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn items() -> Vec<String> {
+        ["git status", "ls -la", "git log"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_mark_filtered_items() {
+        let items = items();
+        let mut selector = Selector::new("Test", &items);
+
+        for c in "git".chars() {
+            selector.handle_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(selector.visible, vec![0, 2]);
+
+        selector.handle_key(key(KeyCode::Down));
+        selector.handle_key(key(KeyCode::Tab));
+        assert_eq!(
+            selector.handle_key(key(KeyCode::Enter)),
+            Some(Outcome::Confirmed(vec![2]))
+        );
+    }
+
+    #[test]
+    fn test_enter_takes_highlighted_item() {
+        let items = items();
+        let mut selector = Selector::new("Test", &items);
+
+        selector.handle_key(key(KeyCode::Down));
+        assert_eq!(
+            selector.handle_key(key(KeyCode::Enter)),
+            Some(Outcome::Confirmed(vec![1]))
+        );
+        assert_eq!(
+            Selector::new("Test", &items).handle_key(key(KeyCode::Esc)),
+            Some(Outcome::Cancelled)
+        );
+    }
+}
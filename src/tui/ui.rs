@@ -10,7 +10,8 @@ use ratatui::{
     Frame,
 };
 
-use super::app::{App, InputField, Mode};
+use super::app::{App, InputField, Mode, PendingAction};
+use crate::models::{CommandEntry, SortOrder, Source};
 
 /// Main render function (View in Elm Architecture)
 pub fn render(frame: &mut Frame, app: &mut App) {
@@ -24,15 +25,31 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         ])
         .split(frame.area());
 
-    // Split main content into list and detail
+    // Split main content into the tag tree (once there are tags), list and detail
+    let show_tree = !app.picker && !app.tag_tree.roots.is_empty();
     let main_chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .constraints(if show_tree {
+            [
+                Constraint::Percentage(20),
+                Constraint::Percentage(40),
+                Constraint::Percentage(40),
+            ]
+        } else {
+            [
+                Constraint::Length(0),
+                Constraint::Percentage(50),
+                Constraint::Percentage(50),
+            ]
+        })
         .split(chunks[0]);
 
     // Render components
-    render_list(frame, app, main_chunks[0]);
-    render_detail(frame, app, main_chunks[1]);
+    if show_tree {
+        render_tag_tree(frame, app, main_chunks[0]);
+    }
+    render_list(frame, app, main_chunks[1]);
+    render_detail(frame, app, main_chunks[2]);
     render_status_bar(frame, app, chunks[1]);
     render_help_bar(frame, app, chunks[2]);
 
@@ -41,19 +58,35 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         Mode::Adding(_) => render_input_popup(frame, app, "Add New Command", Color::Green),
         Mode::Editing(_) => render_input_popup(frame, app, "Edit Command", Color::Yellow),
         Mode::ConfirmDelete => render_delete_confirm(frame, app),
+        Mode::FillingPlaceholders => render_fill_popup(frame, app),
+        Mode::ManagingTags => render_tags_popup(frame, app),
+        Mode::BrowsingTrash => render_trash_popup(frame, app),
         _ => {}
     }
 }
 
-/// Renders the command list
-fn render_list(frame: &mut Frame, app: &App, area: Rect) {
+/// Renders the command list, with the filter bar above it while a filter
+/// is being typed or applied
+fn render_list(frame: &mut Frame, app: &mut App, area: Rect) {
+    let area = if app.mode == Mode::Filtering || !app.filter_text().trim().is_empty() {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(3)])
+            .split(area);
+        render_filter_bar(frame, app, chunks[0]);
+        chunks[1]
+    } else {
+        area
+    };
+
     let items: Vec<ListItem> = app
-        .db
-        .entries
+        .visible
         .iter()
         .enumerate()
-        .map(|(i, entry)| {
-            let style = if i == app.selected_index {
+        .map(|(i, visible)| {
+            let entry = &app.db.entries[visible.index];
+            let selected = i == app.selected_index;
+            let style = if selected {
                 Style::default()
                     .fg(Color::Black)
                     .bg(Color::Cyan)
@@ -61,16 +94,54 @@ fn render_list(frame: &mut Frame, app: &App, area: Rect) {
             } else {
                 Style::default()
             };
+            // Yellow is unreadable on the cyan selection bar
+            let match_style = if selected {
+                Style::default().add_modifier(Modifier::UNDERLINED)
+            } else {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            };
 
-            let content = format!(" {:>3} │ {}", entry.id, truncate_str(&entry.command, 40));
-            ListItem::new(content).style(style)
+            // Project commands stand out by their ID
+            let id_style = if entry.source == Source::Project && !selected {
+                Style::default().fg(Color::Magenta)
+            } else {
+                Style::default()
+            };
+            let mut spans = vec![Span::styled(
+                format!(" {:>3} │ ", entry.reference()),
+                id_style,
+            )];
+            spans.extend(highlight_spans(
+                &entry.command,
+                &visible.command_indices,
+                40,
+                match_style,
+            ));
+            ListItem::new(Line::from(spans)).style(style)
         })
         .collect();
 
+    let count = if app.is_filtered() {
+        format!(" ({}/{})", app.entry_count(), app.db.entries.len())
+    } else {
+        String::new()
+    };
+    let sort = match app.sort_order {
+        SortOrder::Default => "",
+        SortOrder::Frecency => " · frecent",
+    };
+    let scope = match &app.tag_tree.scope {
+        Some(tag) => format!(" in {}", tag),
+        None => String::new(),
+    };
+    let title = format!(" Commands{}{}{} ", scope, count, sort);
+
     let list = List::new(items)
         .block(
             Block::default()
-                .title(" Commands ")
+                .title(title)
                 .title_style(Style::default().fg(Color::Cyan).bold())
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(Color::DarkGray)),
@@ -83,13 +154,109 @@ fn render_list(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_stateful_widget(list, area, &mut state);
 }
 
+/// Renders the tag tree sidebar
+fn render_tag_tree(frame: &mut Frame, app: &App, area: Rect) {
+    let tree = &app.tag_tree;
+    let focused = app.mode == Mode::BrowsingTagTree;
+
+    let items: Vec<ListItem> = tree
+        .rows()
+        .iter()
+        .map(|row| {
+            let Some(node) = row.node else {
+                return ListItem::new(Line::from(vec![
+                    Span::raw(" All "),
+                    Span::styled(
+                        format!("({})", app.db.entries.len()),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]));
+            };
+            let marker = if node.children.is_empty() {
+                "  "
+            } else if row.collapsed {
+                "▸ "
+            } else {
+                "▾ "
+            };
+            let in_scope = tree
+                .scope
+                .as_deref()
+                .is_some_and(|scope| scope.eq_ignore_ascii_case(&node.path));
+            let name_style = if in_scope {
+                Style::default().fg(Color::Yellow).bold()
+            } else {
+                Style::default().fg(Color::Green)
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(format!(" {}{}", "  ".repeat(row.depth), marker)),
+                Span::styled(format!("{} ", node.name), name_style),
+                Span::styled(
+                    format!("({})", node.count),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(" Tags ")
+                .title_style(Style::default().fg(Color::Cyan).bold())
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(if focused {
+                    Color::Cyan
+                } else {
+                    Color::DarkGray
+                })),
+        )
+        .highlight_style(if focused {
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().add_modifier(Modifier::BOLD)
+        });
+
+    let mut state = ListState::default();
+    state.select(Some(tree.selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+/// Renders the filter query input
+fn render_filter_bar(frame: &mut Frame, app: &mut App, area: Rect) {
+    let active = app.mode == Mode::Filtering;
+    let (title, color) = match &app.filter_error {
+        Some(error) => (format!(" Filter - {} ", error), Color::Red),
+        None if active => (" Filter ".to_string(), Color::Yellow),
+        None => (" Filter ".to_string(), Color::DarkGray),
+    };
+
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(color));
+    app.filter_input.set_block(block);
+    app.filter_input.set_cursor_style(if active {
+        Style::default().add_modifier(Modifier::REVERSED)
+    } else {
+        Style::default()
+    });
+    frame.render_widget(&app.filter_input, area);
+}
+
 /// Renders the detail panel for the selected command
 fn render_detail(frame: &mut Frame, app: &App, area: Rect) {
     let content = if let Some(entry) = app.selected_entry() {
-        let lines = vec![
+        let mut lines = vec![
             Line::from(vec![
                 Span::styled("ID: ", Style::default().fg(Color::DarkGray)),
-                Span::styled(entry.id.to_string(), Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    entry.reference().to_string(),
+                    Style::default().fg(Color::Yellow),
+                ),
             ]),
             Line::from(""),
             Line::from(vec![Span::styled(
@@ -126,9 +293,27 @@ fn render_detail(frame: &mut Frame, app: &App, area: Rect) {
                     Style::default().fg(Color::DarkGray),
                 ),
             ]),
+            Line::from(vec![
+                Span::styled("Used: ", Style::default().fg(Color::DarkGray)),
+                Span::styled(usage_display(entry), Style::default().fg(Color::DarkGray)),
+            ]),
         ];
+        if let (Source::Project, Some(path)) = (entry.source, app.project_path()) {
+            lines.push(Line::from(vec![
+                Span::styled("Source: ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    path.display().to_string(),
+                    Style::default().fg(Color::Magenta),
+                ),
+            ]));
+        }
 
         Text::from(lines)
+    } else if app.is_filtered() && !app.db.entries.is_empty() {
+        Text::from(vec![Line::from(vec![Span::styled(
+            "No commands match the filter. Press Esc to clear it.",
+            Style::default().fg(Color::DarkGray).italic(),
+        )])])
     } else {
         Text::from(vec![Line::from(vec![Span::styled(
             "No commands yet. Press 'a' to add one.",
@@ -149,14 +334,37 @@ fn render_detail(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(paragraph, area);
 }
 
+/// Describes how often and how recently an entry was used
+fn usage_display(entry: &CommandEntry) -> String {
+    let usage = &entry.usage;
+    let Some(last_used) = usage.last_used_at else {
+        return "never".to_string();
+    };
+
+    let mut text = format!(
+        "{} time(s), last {}",
+        usage.use_count,
+        last_used.format("%Y-%m-%d %H:%M")
+    );
+    if let Some(code) = usage.last_exit_code {
+        text.push_str(&format!(" (last exit code {})", code));
+    }
+    text
+}
+
 /// Renders the status bar
 fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     let status = if let Some(msg) = &app.status_message {
         msg.clone()
     } else {
+        let count = if app.is_filtered() {
+            format!("{} of {}", app.entry_count(), app.db.entries.len())
+        } else {
+            app.entry_count().to_string()
+        };
         format!(
             " {} command(s) │ Selected: {}/{}",
-            app.entry_count(),
+            count,
             if app.entry_count() > 0 {
                 app.selected_index + 1
             } else {
@@ -180,13 +388,33 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
 /// Renders the help bar at the bottom
 fn render_help_bar(frame: &mut Frame, app: &App, area: Rect) {
     let help_text = match &app.mode {
+        Mode::Normal | Mode::Filtering if app.picker => {
+            " Type to filter │ ↑/↓: Move │ Enter: Insert into prompt │ Esc: Cancel "
+        }
         Mode::Normal => {
-            " q: Quit │ a: Add │ e: Edit │ d: Delete │ x/Enter: Run │ y: Copy │ j/k: Move "
+            " q: Quit │ /: Filter │ Tab: Tag Tree │ s: Sort │ t: Tags │ T: Trash │ a: Add │ e: Edit │ d: Delete │ u/^R: Undo/Redo │ x/Enter: Run │ y: Copy │ j/k: Move "
+        }
+        Mode::ManagingTags => match &app.tag_manager {
+            Some(manager) if manager.confirm_remove => " y: Confirm Remove │ n/Esc: Cancel ",
+            Some(manager) if manager.rename_input.is_some() => {
+                " Enter: Rename (an existing name merges) │ Esc: Cancel "
+            }
+            _ => " Enter: Show Commands │ r: Rename/Merge │ d: Remove │ j/k: Move │ Esc: Back ",
+        },
+        Mode::BrowsingTagTree => {
+            " Enter/Space: Show Tag │ h/l: Collapse/Expand │ j/k: Move │ Tab/Esc: Back to List "
         }
+        Mode::BrowsingTrash => " Enter/r: Restore │ j/k: Move │ Esc: Back ",
         Mode::Adding(_) | Mode::Editing(_) => {
             " Tab: Next Field │ Shift+Tab: Prev │ Ctrl+S: Save │ Esc: Cancel "
         }
         Mode::ConfirmDelete => " y: Confirm Delete │ n/Esc: Cancel ",
+        Mode::FillingPlaceholders => {
+            " Tab: Next Field │ Shift+Tab: Prev │ Enter/Ctrl+S: Confirm │ Esc: Cancel "
+        }
+        Mode::Filtering => {
+            " Type to filter (tag:, cmd:, desc:, -, OR) │ ↑/↓: Move │ Enter: Apply │ Esc: Clear "
+        }
     };
 
     let paragraph =
@@ -292,6 +520,213 @@ fn render_delete_confirm(frame: &mut Frame, app: &App) {
     frame.render_widget(paragraph, area);
 }
 
+/// Renders the popup for filling in placeholder values
+fn render_fill_popup(frame: &mut Frame, app: &mut App) {
+    let Some(form) = app.placeholder_form.as_mut() else {
+        return;
+    };
+
+    let title = match form.action {
+        PendingAction::Copy => " Fill Placeholders (Copy) ",
+        PendingAction::Execute { .. } => " Fill Placeholders (Run) ",
+        PendingAction::Insert => " Fill Placeholders ",
+    };
+
+    // Size the popup to fit the template line plus one input per placeholder
+    let height = (form.inputs.len() as u16 * 3 + 4).min(frame.area().height);
+    let outer = centered_rect(60, 100, frame.area());
+    let area = Rect {
+        y: outer.y + outer.height.saturating_sub(height) / 2,
+        height,
+        ..outer
+    };
+
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(title)
+        .title_style(Style::default().fg(Color::Magenta).bold())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Magenta));
+    frame.render_widget(block, area);
+
+    let mut constraints = vec![Constraint::Length(1)];
+    constraints.extend(form.inputs.iter().map(|_| Constraint::Length(3)));
+    let inner = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(constraints)
+        .split(area);
+
+    let template_line = Paragraph::new(Line::from(vec![
+        Span::styled("$ ", Style::default().fg(Color::DarkGray)),
+        Span::styled(form.template.as_str(), Style::default().fg(Color::White)),
+    ]));
+    frame.render_widget(template_line, inner[0]);
+
+    for (i, (placeholder, input)) in form
+        .placeholders
+        .iter()
+        .zip(form.inputs.iter_mut())
+        .enumerate()
+    {
+        let title = match &placeholder.description {
+            Some(desc) => format!(" {} - {} ", placeholder.name, desc),
+            None => format!(" {} ", placeholder.name),
+        };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(if i == form.focused {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            });
+        input.set_block(block);
+        frame.render_widget(&*input, inner[i + 1]);
+    }
+}
+
+/// Renders the tag management popup
+fn render_tags_popup(frame: &mut Frame, app: &mut App) {
+    let Some(manager) = app.tag_manager.as_mut() else {
+        return;
+    };
+
+    let area = centered_rect(50, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let footer_height = if manager.rename_input.is_some() {
+        3
+    } else if manager.confirm_remove {
+        1
+    } else {
+        0
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(footer_height)])
+        .split(area);
+
+    let items: Vec<ListItem> = manager
+        .tags
+        .iter()
+        .map(|(tag, count)| {
+            ListItem::new(Line::from(vec![
+                Span::styled(format!(" {} ", tag), Style::default().fg(Color::Cyan)),
+                Span::styled(format!("({})", count), Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+
+    let title = format!(" Tags ({}) ", manager.tags.len());
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(title)
+                .title_style(Style::default().fg(Color::Cyan).bold())
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    if manager.tags.is_empty() {
+        let empty = Paragraph::new("No tags yet.")
+            .style(Style::default().fg(Color::DarkGray).italic())
+            .block(
+                Block::default()
+                    .title(" Tags ")
+                    .title_style(Style::default().fg(Color::Cyan).bold())
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            );
+        frame.render_widget(empty, chunks[0]);
+    } else {
+        let mut state = ListState::default();
+        state.select(Some(manager.selected));
+        frame.render_stateful_widget(list, chunks[0], &mut state);
+    }
+
+    let selected = manager.selected_tag().unwrap_or_default().to_string();
+    if let Some(input) = manager.rename_input.as_mut() {
+        input.set_block(
+            Block::default()
+                .title(format!(" Rename '{}' to ", selected))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+        frame.render_widget(&*input, chunks[1]);
+    } else if manager.confirm_remove {
+        let prompt = Paragraph::new(format!(
+            " Remove tag '{}' from every command? [y] Yes  [n] No",
+            selected
+        ))
+        .style(Style::default().fg(Color::Red).bold());
+        frame.render_widget(prompt, chunks[1]);
+    }
+}
+
+/// Renders the trash popup
+fn render_trash_popup(frame: &mut Frame, app: &App) {
+    let Some(trash) = &app.trash else {
+        return;
+    };
+
+    let area = centered_rect(60, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(format!(" Trash ({}) ", trash.entries.len()))
+        .title_style(Style::default().fg(Color::Red).bold())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Red));
+
+    if trash.entries.is_empty() {
+        let empty = Paragraph::new("The trash is empty.")
+            .style(Style::default().fg(Color::DarkGray).italic())
+            .block(block);
+        frame.render_widget(empty, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = trash
+        .entries
+        .iter()
+        .map(|entry| {
+            let deleted = entry
+                .deleted_at
+                .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!(" {:>3} ", entry.id),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::raw(truncate_str(&entry.command, 40)),
+                Span::styled(
+                    format!("  {}", deleted),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items).block(block).highlight_style(
+        Style::default()
+            .fg(Color::Black)
+            .bg(Color::Red)
+            .add_modifier(Modifier::BOLD),
+    );
+    let mut state = ListState::default();
+    state.select(Some(trash.selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
 /// Helper function to create a centered rectangle
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -313,6 +748,49 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
+/// Splits a string into spans, styling the characters at `indices` and
+/// truncating it to `max_chars` characters
+fn highlight_spans<'a>(
+    s: &'a str,
+    indices: &[usize],
+    max_chars: usize,
+    match_style: Style,
+) -> Vec<Span<'a>> {
+    let char_count = s.chars().count();
+    let (keep, suffix) = if char_count > max_chars {
+        (max_chars.saturating_sub(3), "...")
+    } else {
+        (char_count, "")
+    };
+
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let mut current_matched = false;
+    for (i, c) in s.chars().take(keep).enumerate() {
+        let matched = indices.binary_search(&i).is_ok();
+        if matched != current_matched && !current.is_empty() {
+            let style = if current_matched {
+                match_style
+            } else {
+                Style::default()
+            };
+            spans.push(Span::styled(std::mem::take(&mut current), style));
+        }
+        current_matched = matched;
+        current.push(c);
+    }
+    current.push_str(suffix);
+    if !current.is_empty() {
+        let style = if current_matched {
+            match_style
+        } else {
+            Style::default()
+        };
+        spans.push(Span::styled(current, style));
+    }
+    spans
+}
+
 /// Truncates a string to a maximum length
 fn truncate_str(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {